@@ -0,0 +1,20 @@
+-- Timestamps are stored as unix epoch milliseconds so the schema works on SQLite and PostgreSQL.
+CREATE TABLE IF NOT EXISTS wraps (
+    id TEXT PRIMARY KEY,
+    redirect_url TEXT NOT NULL,
+    password TEXT NOT NULL,
+    auth_type TEXT NOT NULL,
+    comment TEXT NOT NULL,
+    expiration_at BIGINT NOT NULL,
+    created_at BIGINT NOT NULL,
+    failed_attempts BIGINT NOT NULL DEFAULT 0,
+    locked_until BIGINT
+);
+
+CREATE TABLE IF NOT EXISTS rate_limits (
+    id TEXT PRIMARY KEY,
+    count BIGINT NOT NULL,
+    reset_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS rate_limits_reset_at ON rate_limits (reset_at);
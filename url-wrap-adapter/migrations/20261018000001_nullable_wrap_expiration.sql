@@ -0,0 +1,23 @@
+-- `expiration_at` is NULL for wraps that never expire.
+-- SQLite cannot drop a NOT NULL constraint in place, so the table is rebuilt.
+CREATE TABLE wraps_new (
+    id TEXT PRIMARY KEY,
+    redirect_url TEXT NOT NULL,
+    password TEXT NOT NULL,
+    auth_type TEXT NOT NULL,
+    comment TEXT NOT NULL,
+    expiration_at BIGINT,
+    created_at BIGINT NOT NULL,
+    failed_attempts BIGINT NOT NULL DEFAULT 0,
+    locked_until BIGINT
+);
+
+INSERT INTO wraps_new (id, redirect_url, password, auth_type, comment,
+    expiration_at, created_at, failed_attempts, locked_until)
+SELECT id, redirect_url, password, auth_type, comment,
+    expiration_at, created_at, failed_attempts, locked_until
+FROM wraps;
+
+DROP TABLE wraps;
+
+ALTER TABLE wraps_new RENAME TO wraps;
@@ -0,0 +1,3 @@
+-- Both are NULL for wraps without a view limit.
+ALTER TABLE wraps ADD COLUMN max_views BIGINT;
+ALTER TABLE wraps ADD COLUMN remaining_views BIGINT;
@@ -0,0 +1,2 @@
+-- SHA-256 of the management token. NULL for wraps created before tokens were issued.
+ALTER TABLE wraps ADD COLUMN management_token TEXT;
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS owners (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+-- `id` is the SHA-256 of the API key.
+CREATE TABLE IF NOT EXISTS api_keys (
+    id TEXT PRIMARY KEY,
+    owner_id TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS api_keys_owner_id ON api_keys (owner_id);
+
+-- NULL for wraps created anonymously.
+ALTER TABLE wraps ADD COLUMN owner_id TEXT;
+
+CREATE INDEX IF NOT EXISTS wraps_owner_id ON wraps (owner_id);
@@ -0,0 +1,11 @@
+-- `wrap_id` is not a foreign key: attempts on unknown ids are recorded too.
+CREATE TABLE IF NOT EXISTS wrap_access_events (
+    id TEXT PRIMARY KEY,
+    wrap_id TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    client_ip TEXT,
+    user_agent TEXT,
+    occurred_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS wrap_access_events_wrap_id ON wrap_access_events (wrap_id, id);
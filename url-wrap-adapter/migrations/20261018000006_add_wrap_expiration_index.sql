@@ -0,0 +1,2 @@
+-- Used by the purge of expired wraps.
+CREATE INDEX IF NOT EXISTS wraps_expiration_at ON wraps (expiration_at);
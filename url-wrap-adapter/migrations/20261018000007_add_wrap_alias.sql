@@ -0,0 +1,4 @@
+-- NULL for wraps without an alias. NULLs never collide in a unique index.
+ALTER TABLE wraps ADD COLUMN alias TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS wraps_alias ON wraps (alias);
@@ -0,0 +1,2 @@
+-- Encrypted shared secret of TOTP wraps, NULL for password wraps.
+ALTER TABLE wraps ADD COLUMN totp_secret TEXT;
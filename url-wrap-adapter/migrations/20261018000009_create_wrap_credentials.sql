@@ -0,0 +1,17 @@
+-- Named credentials accepted besides the primary password of a wrap.
+-- Deleted together with their wrap by the repository.
+CREATE TABLE IF NOT EXISTS wrap_credentials (
+    id TEXT PRIMARY KEY,
+    wrap_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    password TEXT NOT NULL,
+    expiration_at BIGINT,
+    max_uses BIGINT,
+    uses BIGINT NOT NULL DEFAULT 0,
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS wrap_credentials_wrap_id ON wrap_credentials (wrap_id, id);
+
+-- The credential that matched, NULL for the primary password and failed attempts.
+ALTER TABLE wrap_access_events ADD COLUMN credential_id TEXT;
@@ -0,0 +1,2 @@
+-- Salt and Argon2 parameters the URL key of zero-knowledge wraps is derived with, NULL otherwise.
+ALTER TABLE wraps ADD COLUMN redirect_url_kdf TEXT;
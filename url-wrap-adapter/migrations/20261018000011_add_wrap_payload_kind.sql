@@ -0,0 +1,2 @@
+-- Name of the payload kind, existing wraps all redirect to a URL.
+ALTER TABLE wraps ADD COLUMN payload_kind TEXT NOT NULL DEFAULT 'Url';
@@ -0,0 +1,2 @@
+-- `FourDigit` predates configurable PIN lengths, it is the same as `Pin4`.
+UPDATE wraps SET auth_type = 'Pin4' WHERE auth_type = 'FourDigit';
@@ -0,0 +1,2 @@
+-- Time step of the last accepted TOTP code, codes at or before it are replays.
+ALTER TABLE wraps ADD COLUMN totp_last_step INTEGER;
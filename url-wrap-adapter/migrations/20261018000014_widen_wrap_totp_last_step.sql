@@ -0,0 +1,10 @@
+-- PostgreSQL's INTEGER is 32 bits, while the time step is bound as a 64-bit integer like the
+-- other BIGINT columns. Neither database can change the type in a way the other accepts,
+-- so the value moves to a new column that then takes the old name.
+ALTER TABLE wraps ADD COLUMN totp_last_step_wide BIGINT;
+
+UPDATE wraps SET totp_last_step_wide = totp_last_step;
+
+ALTER TABLE wraps DROP COLUMN totp_last_step;
+
+ALTER TABLE wraps RENAME COLUMN totp_last_step_wide TO totp_last_step;
@@ -2,3 +2,5 @@ pub mod model;
 pub mod modules;
 pub mod persistence;
 pub mod repository;
+#[cfg(test)]
+mod testing;
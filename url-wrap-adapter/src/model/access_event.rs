@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use url_wrap_kernel::model::access_event::{AccessEvent, NewAccessEvent};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AccessEventDocument {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub wrap_id: String,
+    pub outcome: String,
+    #[serde(default)]
+    pub credential_id: Option<String>,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub occurred_at: bson::DateTime,
+}
+
+impl TryFrom<AccessEventDocument> for AccessEvent {
+    type Error = anyhow::Error;
+
+    fn try_from(ad: AccessEventDocument) -> Result<Self, Self::Error> {
+        Ok(AccessEvent::new(
+            ad.id.try_into()?,
+            ad.wrap_id,
+            ad.outcome.as_str().try_into()?,
+            ad.credential_id,
+            ad.client_ip,
+            ad.user_agent,
+            ad.occurred_at.to_chrono(),
+        ))
+    }
+}
+
+impl From<NewAccessEvent> for AccessEventDocument {
+    fn from(na: NewAccessEvent) -> Self {
+        Self {
+            id: na.id.value.to_string(),
+            wrap_id: na.wrap_id,
+            outcome: na.outcome.as_str().to_string(),
+            credential_id: na.credential_id,
+            client_ip: na.client_ip,
+            user_agent: na.user_agent,
+            occurred_at: bson::DateTime::from_chrono(na.occurred_at),
+        }
+    }
+}
@@ -1 +1,5 @@
+pub mod access_event;
+pub mod owner;
+pub mod rate_limit;
+mod secret;
 pub mod wrap;
@@ -0,0 +1,58 @@
+use crate::model::secret::hash_secret;
+use serde::{Deserialize, Serialize};
+use url_wrap_kernel::model::owner::{NewApiKey, NewOwner, Owner};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OwnerDocument {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub name: String,
+    pub created_at: bson::DateTime,
+}
+
+impl TryFrom<OwnerDocument> for Owner {
+    type Error = anyhow::Error;
+
+    fn try_from(od: OwnerDocument) -> Result<Self, Self::Error> {
+        Ok(Owner::new(
+            od.id.try_into()?,
+            od.name,
+            od.created_at.to_chrono(),
+        ))
+    }
+}
+
+impl From<NewOwner> for OwnerDocument {
+    fn from(no: NewOwner) -> Self {
+        Self {
+            id: no.id.value.to_string(),
+            name: no.name,
+            created_at: bson::DateTime::now(),
+        }
+    }
+}
+
+/// Keyed by the hash of the API key, so a request can be authenticated with a single lookup.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ApiKeyDocument {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub owner_id: String,
+    pub created_at: bson::DateTime,
+}
+
+impl ApiKeyDocument {
+    pub fn hash(api_key: &str) -> String {
+        hash_secret(api_key)
+    }
+}
+
+impl From<NewApiKey> for ApiKeyDocument {
+    fn from(nak: NewApiKey) -> Self {
+        Self {
+            id: ApiKeyDocument::hash(&nak.api_key.0),
+            owner_id: nak.owner_id.value.to_string(),
+            created_at: bson::DateTime::now(),
+        }
+    }
+}
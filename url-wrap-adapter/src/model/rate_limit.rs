@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url_wrap_kernel::model::rate_limit::RateLimit;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RateLimitDocument {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub count: i64,
+    pub reset_at: bson::DateTime,
+}
+
+impl RateLimitDocument {
+    pub fn new(id: String, reset_at: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            count: 0,
+            reset_at: bson::DateTime::from_chrono(reset_at),
+        }
+    }
+}
+
+impl TryFrom<RateLimitDocument> for RateLimit {
+    type Error = anyhow::Error;
+
+    fn try_from(rld: RateLimitDocument) -> Result<Self, Self::Error> {
+        Ok(RateLimit::new(
+            rld.id,
+            rld.count as u64,
+            rld.reset_at.to_chrono(),
+        ))
+    }
+}
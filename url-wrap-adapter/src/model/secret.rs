@@ -0,0 +1,8 @@
+use data_encoding::HEXLOWER;
+use sha2::{Digest, Sha256};
+
+/// SHA-256 of a random secret such as a management token or an API key.
+/// Secrets carry 256 bits of randomness, so unlike passwords they need no salted, slow hash.
+pub(crate) fn hash_secret(secret: &str) -> String {
+    HEXLOWER.encode(&Sha256::digest(secret.as_bytes()))
+}
@@ -1,74 +1,408 @@
+pub mod credential;
+mod lockout;
+mod management_token;
 mod password;
 mod redirect_url;
+pub mod schema;
+mod totp;
+mod zero_knowledge;
 
+use crate::model::wrap::credential::WrapCredentialDocument;
+use crate::model::wrap::lockout::init_lockout_policy;
+use crate::model::wrap::management_token::HashedManagementToken;
 use crate::model::wrap::password::HashedPassword;
 use crate::model::wrap::redirect_url::{DecryptedRedirectUrl, EncryptedRedirectUrl};
-use chrono::{TimeZone, Utc};
-use mongodb::bson::Timestamp;
+use crate::model::wrap::schema::WRAP_SCHEMA_VERSION;
+use crate::model::wrap::totp::EncryptedTotpSecret;
+use crate::model::wrap::zero_knowledge::UrlKeyDerivation;
+use bson::{doc, Bson, Document};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use url_wrap_kernel::model::wrap::{NewWrap, Wrap};
+use url_wrap_kernel::model::wrap::credential::WrapCredential;
+use url_wrap_kernel::model::wrap::error::WrapError;
+use url_wrap_kernel::model::wrap::payload::WrapPayloadKind;
+use url_wrap_kernel::model::wrap::{NewWrap, Wrap, WrapSecret, WrapUpdate};
+use url_wrap_kernel::model::Id;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct WrapDocument {
     #[serde(rename = "_id")]
     pub id: String,
+    /// The URL or the note, sealed with the server key, or with a key derived from
+    /// the password when `redirect_url_kdf` is set.
     pub redirect_url: String,
+    /// Name of the `WrapPayloadKind`, URL for wraps created before text payloads.
+    #[serde(default = "default_payload_kind")]
+    pub payload_kind: String,
+    /// Salt and Argon2 parameters of zero-knowledge wraps, see `UrlKeyDerivation`.
+    #[serde(default)]
+    pub redirect_url_kdf: Option<String>,
+    /// PHC string of the password, empty for TOTP wraps.
     pub password: String,
     pub auth_type: String,
     pub comment: String,
-    pub expiration_at: Timestamp,
-    pub created_at: Timestamp,
+    /// `null` when the wrap never expires.
+    #[serde(default)]
+    pub expiration_at: Option<bson::DateTime>,
+    pub created_at: bson::DateTime,
+    #[serde(default)]
+    pub failed_attempts: u32,
+    #[serde(default)]
+    pub locked_until: Option<bson::DateTime>,
+    #[serde(default)]
+    pub max_views: Option<u32>,
+    #[serde(default)]
+    pub remaining_views: Option<u32>,
+    /// `None` for wraps created before management tokens were introduced.
+    #[serde(default)]
+    pub management_token: Option<String>,
+    #[serde(default)]
+    pub owner_id: Option<String>,
+    /// Unique among wraps when set, see `WrapAlias`.
+    #[serde(default)]
+    pub alias: Option<String>,
+    /// Encrypted shared secret, set only for TOTP wraps.
+    #[serde(default)]
+    pub totp_secret: Option<String>,
+    /// Time step of the last accepted TOTP code, see `EncryptedTotpSecret::verify`.
+    #[serde(default)]
+    pub totp_last_step: Option<i64>,
+    /// Named credentials accepted besides the primary password.
+    #[serde(default)]
+    pub credentials: Vec<WrapCredentialDocument>,
+    /// Documents are upgraded with `schema::upgrade` before they are deserialized.
+    #[serde(default)]
+    pub schema_version: u32,
+}
+
+fn default_payload_kind() -> String {
+    WrapPayloadKind::Url.to_string()
+}
+
+/// Secret of a wrap that `WrapDocument::verify_password` accepted.
+pub enum PasswordMatch {
+    Password,
+    /// TOTP code of this time step, which must be stored to reject replays.
+    Totp(i64),
+    /// Id of the named credential.
+    Credential(String),
+}
+
+impl PasswordMatch {
+    /// Credential id for `VerifiedWrap`, `None` for the secret of the wrap itself.
+    pub fn credential_id(&self) -> Result<Option<Id<WrapCredential>>, WrapError> {
+        match self {
+            PasswordMatch::Credential(credential_id) => Id::try_from(credential_id.clone())
+                .map(Some)
+                .map_err(WrapError::Corrupted),
+            _ => Ok(None),
+        }
+    }
 }
 
 impl WrapDocument {
-    pub fn verify_password(&self, password: &str) -> anyhow::Result<()> {
+    /// Checks the password, or the current code of TOTP wraps, or with `credential_id`
+    /// only that credential if it is still usable.
+    pub fn verify_password(
+        &self,
+        password: &str,
+        credential_id: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<PasswordMatch, WrapError> {
+        if let Some(credential_id) = credential_id {
+            let credential = self
+                .credentials
+                .iter()
+                .find(|v| v.id == credential_id && v.usable(now))
+                .ok_or(WrapError::InvalidCredentials)?;
+            HashedPassword::new(&credential.password).verify(password)?;
+            return Ok(PasswordMatch::Credential(credential.id.clone()));
+        }
+
+        match &self.totp_secret {
+            Some(totp_secret) => EncryptedTotpSecret::new(totp_secret)
+                .verify(password, now, self.totp_last_step)
+                .map(PasswordMatch::Totp),
+            None => HashedPassword::new(&self.password)
+                .verify(password)
+                .map(|_| PasswordMatch::Password),
+        }
+    }
+
+    /// Uses up one use of the credential, `false` when it is gone or used up meanwhile.
+    pub fn use_credential(&mut self, credential_id: &str, now: DateTime<Utc>) -> bool {
+        match self
+            .credentials
+            .iter_mut()
+            .find(|v| v.id == credential_id && v.usable(now))
+        {
+            Some(credential) => {
+                credential.uses += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Records the time step of an accepted TOTP code, `false` when a code of that step
+    /// or a later one was accepted meanwhile.
+    pub fn use_totp_step(&mut self, step: i64) -> bool {
+        if self.totp_last_step.is_some_and(|last| last >= step) {
+            return false;
+        }
+        self.totp_last_step = Some(step);
+        true
+    }
+
+    /// Returns a fresh PHC string for `password` when the stored hash is outdated.
+    /// Must only be called after `verify_password` succeeded.
+    pub fn rehash_password(&self, password: &str) -> anyhow::Result<Option<String>> {
+        if self.totp_secret.is_some() {
+            return Ok(None);
+        }
+
         let hashed_password = HashedPassword::new(&self.password);
-        hashed_password.verify(password)
+        if !hashed_password.needs_rehash()? {
+            return Ok(None);
+        }
+
+        let rehashed_password: HashedPassword = password.to_string().try_into()?;
+        Ok(Some(rehashed_password.to_string()))
+    }
+
+    /// Converts a document whose password was verified, so zero-knowledge wraps
+    /// come with their redirect URL.
+    pub fn open(self, password: &str) -> Result<Wrap, WrapError> {
+        wrap_from_document(self, Some(password)).map_err(WrapError::Corrupted)
+    }
+
+    pub fn verify_management_token(&self, management_token: &str) -> Result<(), WrapError> {
+        match &self.management_token {
+            Some(hashed) => HashedManagementToken::new(hashed).verify(management_token),
+            None => Err(WrapError::InvalidCredentials),
+        }
+    }
+
+    /// Returns the lock that is still in effect at `now`, if any.
+    pub fn active_lock(&self, now: DateTime<Utc>) -> Option<WrapError> {
+        let locked_until = self.locked_until?.to_chrono();
+
+        if locked_until > now {
+            Some(WrapError::Locked { locked_until })
+        } else {
+            None
+        }
+    }
+
+    /// `failed_attempts` after one more failure, and when the lock it triggers ends, if any.
+    /// Stored only while the read `failed_attempts` are unchanged and no lock is active,
+    /// so that concurrent failures are all counted and none slips past a lock.
+    pub fn after_failure(&self, now: DateTime<Utc>) -> (u32, Option<DateTime<Utc>>) {
+        let failed_attempts = self.failed_attempts.saturating_add(1);
+        let locked_until = init_lockout_policy()
+            .lock_duration(failed_attempts)
+            .map(|duration| now + duration);
+        (failed_attempts, locked_until)
     }
 }
 
 impl TryFrom<WrapDocument> for Wrap {
-    type Error = anyhow::Error;
+    type Error = WrapError;
 
     fn try_from(wd: WrapDocument) -> Result<Self, Self::Error> {
-        let decrypted_redirect_url: DecryptedRedirectUrl = wd.redirect_url.try_into()?;
-
-        let expiration_at = Utc.timestamp(wd.expiration_at.time as i64, wd.expiration_at.increment);
-        let created_at = Utc.timestamp(wd.created_at.time as i64, wd.created_at.increment);
-
-        Ok(Wrap {
-            id: wd.id.try_into()?,
-            redirect_url: decrypted_redirect_url.to_string(),
-            password: wd.password.into(),
-            auth_type: wd.auth_type.into(),
-            comment: wd.comment,
-            expiration_at,
-            created_at,
-        })
+        wrap_from_document(wd, None).map_err(WrapError::Corrupted)
     }
 }
 
+/// The redirect URL of zero-knowledge wraps is left out unless `password` is given.
+fn wrap_from_document(wd: WrapDocument, password: Option<&str>) -> anyhow::Result<Wrap> {
+    let zero_knowledge = wd.redirect_url_kdf.is_some();
+    let redirect_url = match (&wd.redirect_url_kdf, password) {
+        (None, _) => {
+            let decrypted_redirect_url: DecryptedRedirectUrl = wd.redirect_url.try_into()?;
+            Some(decrypted_redirect_url.to_string())
+        }
+        (Some(kdf), Some(password)) => {
+            Some(UrlKeyDerivation::new(kdf).open(password, &wd.redirect_url)?)
+        }
+        (Some(_), None) => None,
+    };
+
+    Ok(Wrap {
+        id: wd.id.try_into()?,
+        redirect_url,
+        payload_kind: wd.payload_kind.try_into()?,
+        password: wd.password.into(),
+        auth_type: wd.auth_type.try_into()?,
+        comment: wd.comment,
+        expiration_at: wd.expiration_at.map(bson::DateTime::to_chrono),
+        created_at: wd.created_at.to_chrono(),
+        max_views: wd.max_views,
+        remaining_views: wd.remaining_views,
+        owner_id: wd.owner_id.map(Id::try_from).transpose()?,
+        alias: wd.alias,
+        zero_knowledge,
+    })
+}
+
 impl TryFrom<NewWrap> for WrapDocument {
     type Error = anyhow::Error;
 
     fn try_from(nw: NewWrap) -> Result<Self, Self::Error> {
-        let encrypted_redirect_url: EncryptedRedirectUrl = nw.redirect_url.try_into()?;
-        let hashed_password: HashedPassword = nw.password.try_into()?;
+        let (redirect_url, redirect_url_kdf) = match (&nw.secret, nw.zero_knowledge) {
+            (WrapSecret::Password(password), true) => {
+                let kdf = UrlKeyDerivation::gen()?;
+                (kdf.seal(password, &nw.redirect_url)?, Some(kdf.to_string()))
+            }
+            _ => {
+                let encrypted_redirect_url: EncryptedRedirectUrl = nw.redirect_url.try_into()?;
+                (encrypted_redirect_url.to_string(), None)
+            }
+        };
+        let (password, totp_secret) = match nw.secret {
+            WrapSecret::Password(password) => {
+                let hashed_password: HashedPassword = password.try_into()?;
+                (hashed_password.to_string(), None)
+            }
+            WrapSecret::Totp(secret) => {
+                let encrypted_secret = EncryptedTotpSecret::try_from(&secret)?;
+                (String::new(), Some(encrypted_secret.to_string()))
+            }
+        };
+        let hashed_management_token = HashedManagementToken::from(&nw.management_token);
 
         Ok(WrapDocument {
             id: nw.id.value.to_string(),
-            redirect_url: encrypted_redirect_url.to_string(),
-            password: hashed_password.to_string(),
+            redirect_url,
+            redirect_url_kdf,
+            payload_kind: nw.payload_kind.to_string(),
+            password,
             auth_type: nw.auth_type.to_string(),
             comment: nw.comment,
-            expiration_at: Timestamp {
-                time: nw.expiration_at.timestamp() as u32,
-                increment: 0u32,
-            },
-            created_at: Timestamp {
-                time: Utc::now().timestamp() as u32,
-                increment: 0u32,
-            },
+            expiration_at: nw.expiration_at.map(bson::DateTime::from_chrono),
+            created_at: bson::DateTime::now(),
+            failed_attempts: 0,
+            locked_until: None,
+            max_views: nw.max_views,
+            remaining_views: nw.max_views,
+            management_token: Some(hashed_management_token.to_string()),
+            owner_id: nw.owner_id.map(|v| v.value.to_string()),
+            alias: nw.alias.map(|v| v.as_str().to_string()),
+            totp_secret,
+            totp_last_step: None,
+            credentials: Vec::new(),
+            schema_version: WRAP_SCHEMA_VERSION,
+        })
+    }
+}
+
+/// `WrapUpdate` with the redirect URL encrypted and the password hashed.
+pub struct WrapDocumentUpdate {
+    pub redirect_url: Option<String>,
+    /// Fresh key derivation of a zero-knowledge wrap whose URL was sealed again.
+    pub redirect_url_kdf: Option<String>,
+    pub password: Option<String>,
+    pub comment: Option<String>,
+    pub expiration_at: Option<Option<bson::DateTime>>,
+}
+
+impl WrapDocumentUpdate {
+    /// Applies the changes to `wd`. A new password also lifts the lockout.
+    pub fn apply(self, wd: &mut WrapDocument) {
+        if let Some(redirect_url) = self.redirect_url {
+            wd.redirect_url = redirect_url;
+        }
+        if let Some(redirect_url_kdf) = self.redirect_url_kdf {
+            wd.redirect_url_kdf = Some(redirect_url_kdf);
+        }
+        if let Some(password) = self.password {
+            wd.password = password;
+            wd.failed_attempts = 0;
+            wd.locked_until = None;
+        }
+        if let Some(comment) = self.comment {
+            wd.comment = comment;
+        }
+        if let Some(expiration_at) = self.expiration_at {
+            wd.expiration_at = expiration_at;
+        }
+    }
+
+    /// Same changes as `apply`, as the body of a MongoDB `$set`.
+    pub fn to_set_document(&self) -> Document {
+        let mut set = doc! {};
+        if let Some(redirect_url) = &self.redirect_url {
+            set.insert("redirect_url", redirect_url);
+        }
+        if let Some(redirect_url_kdf) = &self.redirect_url_kdf {
+            set.insert("redirect_url_kdf", redirect_url_kdf);
+        }
+        if let Some(password) = &self.password {
+            set.insert("password", password);
+            set.insert("failed_attempts", 0);
+            set.insert("locked_until", Bson::Null);
+        }
+        if let Some(comment) = &self.comment {
+            set.insert("comment", comment);
+        }
+        if let Some(expiration_at) = self.expiration_at {
+            set.insert("expiration_at", expiration_at);
+        }
+        set
+    }
+
+    /// The URL of a zero-knowledge wrap is sealed again with a key derived from the new
+    /// password, so both must be changed together.
+    pub fn new(wu: WrapUpdate, zero_knowledge: bool) -> Result<Self, WrapError> {
+        let (redirect_url, redirect_url_kdf) = match (&wu.redirect_url, zero_knowledge) {
+            (Some(redirect_url), true) => {
+                let password = wu.password.as_deref().ok_or_else(|| {
+                    WrapError::InvalidInput(
+                        "Zero-knowledge wraps need the password to seal the redirect URL."
+                            .to_string(),
+                    )
+                })?;
+                let kdf = UrlKeyDerivation::gen().map_err(WrapError::Internal)?;
+                (
+                    Some(
+                        kdf.seal(password, redirect_url)
+                            .map_err(WrapError::Internal)?,
+                    ),
+                    Some(kdf.to_string()),
+                )
+            }
+            (None, true) if wu.password.is_some() => {
+                return Err(WrapError::InvalidInput(
+                    "Zero-knowledge wraps need the redirect URL to change the password."
+                        .to_string(),
+                ));
+            }
+            (Some(redirect_url), false) => {
+                let encrypted_redirect_url: EncryptedRedirectUrl = redirect_url
+                    .clone()
+                    .try_into()
+                    .map_err(WrapError::Internal)?;
+                (Some(encrypted_redirect_url.to_string()), None)
+            }
+            (None, _) => (None, None),
+        };
+        let password = match wu.password {
+            Some(password) => {
+                let hashed_password: HashedPassword =
+                    password.try_into().map_err(WrapError::Internal)?;
+                Some(hashed_password.to_string())
+            }
+            None => None,
+        };
+
+        Ok(WrapDocumentUpdate {
+            redirect_url,
+            redirect_url_kdf,
+            password,
+            comment: wu.comment,
+            expiration_at: wu
+                .expiration_at
+                .map(|expiration_at| expiration_at.map(bson::DateTime::from_chrono)),
         })
     }
 }
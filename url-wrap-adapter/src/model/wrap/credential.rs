@@ -0,0 +1,62 @@
+use crate::model::wrap::password::HashedPassword;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url_wrap_kernel::model::wrap::credential::{NewWrapCredential, WrapCredential};
+
+/// Named credential, embedded in its `WrapDocument`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WrapCredentialDocument {
+    pub id: String,
+    pub name: String,
+    /// PHC string, hashed like the primary password.
+    pub password: String,
+    #[serde(default)]
+    pub expiration_at: Option<bson::DateTime>,
+    #[serde(default)]
+    pub max_uses: Option<u32>,
+    #[serde(default)]
+    pub uses: u32,
+    pub created_at: bson::DateTime,
+}
+
+impl WrapCredentialDocument {
+    /// Neither expired nor used up at `now`.
+    pub fn usable(&self, now: DateTime<Utc>) -> bool {
+        let expired = self.expiration_at.is_some_and(|v| v.to_chrono() <= now);
+        let used_up = self.max_uses.is_some_and(|v| self.uses >= v);
+        !expired && !used_up
+    }
+}
+
+impl TryFrom<WrapCredentialDocument> for WrapCredential {
+    type Error = anyhow::Error;
+
+    fn try_from(cd: WrapCredentialDocument) -> Result<Self, Self::Error> {
+        Ok(WrapCredential::new(
+            cd.id.try_into()?,
+            cd.name,
+            cd.expiration_at.map(bson::DateTime::to_chrono),
+            cd.max_uses,
+            cd.uses,
+            cd.created_at.to_chrono(),
+        ))
+    }
+}
+
+impl TryFrom<NewWrapCredential> for WrapCredentialDocument {
+    type Error = anyhow::Error;
+
+    fn try_from(nc: NewWrapCredential) -> Result<Self, Self::Error> {
+        let hashed_password: HashedPassword = nc.password.try_into()?;
+
+        Ok(WrapCredentialDocument {
+            id: nc.id.value.to_string(),
+            name: nc.name,
+            password: hashed_password.to_string(),
+            expiration_at: nc.expiration_at.map(bson::DateTime::from_chrono),
+            max_uses: nc.max_uses,
+            uses: 0,
+            created_at: bson::DateTime::now(),
+        })
+    }
+}
@@ -0,0 +1,111 @@
+use chrono::Duration;
+use std::env;
+
+const DEFAULT_THRESHOLD: u32 = 5;
+const DEFAULT_BACKOFF_SECONDS: i64 = 30;
+const DEFAULT_MAX_BACKOFF_SECONDS: i64 = 86_400;
+/// Longest lock that can be configured, far below where `DateTime` arithmetic overflows.
+const BACKOFF_LIMIT_SECONDS: i64 = 365 * 86_400;
+
+pub struct LockoutPolicy {
+    threshold: u32,
+    backoff_seconds: i64,
+    max_backoff_seconds: i64,
+}
+
+impl LockoutPolicy {
+    /// Out-of-range back-offs are clamped to `0..=BACKOFF_LIMIT_SECONDS`,
+    /// and the maximum is never below the first back-off.
+    fn new(threshold: u32, backoff_seconds: i64, max_backoff_seconds: i64) -> Self {
+        let backoff_seconds = backoff_seconds.clamp(0, BACKOFF_LIMIT_SECONDS);
+        let max_backoff_seconds = max_backoff_seconds.clamp(backoff_seconds, BACKOFF_LIMIT_SECONDS);
+        Self {
+            threshold,
+            backoff_seconds,
+            max_backoff_seconds,
+        }
+    }
+
+    /// Lock duration after `failed_attempts` consecutive failures.
+    /// The back-off doubles with every failure beyond the threshold.
+    pub fn lock_duration(&self, failed_attempts: u32) -> Option<Duration> {
+        if self.threshold == 0 || failed_attempts < self.threshold {
+            return None;
+        }
+
+        let exponent = (failed_attempts - self.threshold).min(32);
+        let seconds = self
+            .backoff_seconds
+            .saturating_mul(2i64.saturating_pow(exponent))
+            .min(self.max_backoff_seconds);
+        Some(Duration::seconds(seconds))
+    }
+}
+
+pub fn init_lockout_policy() -> LockoutPolicy {
+    let threshold = env::var_os("WRAP_LOCKOUT_THRESHOLD")
+        .map(|v| {
+            v.into_string()
+                .expect("WRAP_LOCKOUT_THRESHOLD is invalid value.")
+                .parse::<u32>()
+                .expect("WRAP_LOCKOUT_THRESHOLD is invalid value.")
+        })
+        .unwrap_or(DEFAULT_THRESHOLD);
+    let backoff_seconds = env::var_os("WRAP_LOCKOUT_BACKOFF_SECONDS")
+        .map(|v| {
+            v.into_string()
+                .expect("WRAP_LOCKOUT_BACKOFF_SECONDS is invalid value.")
+                .parse::<i64>()
+                .expect("WRAP_LOCKOUT_BACKOFF_SECONDS is invalid value.")
+        })
+        .unwrap_or(DEFAULT_BACKOFF_SECONDS);
+    let max_backoff_seconds = env::var_os("WRAP_LOCKOUT_MAX_BACKOFF_SECONDS")
+        .map(|v| {
+            v.into_string()
+                .expect("WRAP_LOCKOUT_MAX_BACKOFF_SECONDS is invalid value.")
+                .parse::<i64>()
+                .expect("WRAP_LOCKOUT_MAX_BACKOFF_SECONDS is invalid value.")
+        })
+        .unwrap_or(DEFAULT_MAX_BACKOFF_SECONDS);
+
+    LockoutPolicy::new(threshold, backoff_seconds, max_backoff_seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_off_doubles_up_to_the_maximum() {
+        let policy = LockoutPolicy::new(3, 30, 100);
+
+        assert_eq!(policy.lock_duration(2), None);
+        assert_eq!(policy.lock_duration(3), Some(Duration::seconds(30)));
+        assert_eq!(policy.lock_duration(4), Some(Duration::seconds(60)));
+        assert_eq!(policy.lock_duration(5), Some(Duration::seconds(100)));
+        assert_eq!(policy.lock_duration(u32::MAX), Some(Duration::seconds(100)));
+    }
+
+    #[test]
+    fn zero_threshold_never_locks() {
+        let policy = LockoutPolicy::new(0, 30, 100);
+
+        assert_eq!(policy.lock_duration(100), None);
+    }
+
+    #[test]
+    fn out_of_range_back_off_is_clamped() {
+        let negative = LockoutPolicy::new(1, -30, -100);
+        assert_eq!(negative.lock_duration(1), Some(Duration::seconds(0)));
+        assert_eq!(negative.lock_duration(10), Some(Duration::seconds(0)));
+
+        let huge = LockoutPolicy::new(1, i64::MAX, i64::MAX);
+        assert_eq!(
+            huge.lock_duration(40),
+            Some(Duration::seconds(BACKOFF_LIMIT_SECONDS))
+        );
+
+        let inverted = LockoutPolicy::new(1, 60, 10);
+        assert_eq!(inverted.lock_duration(1), Some(Duration::seconds(60)));
+    }
+}
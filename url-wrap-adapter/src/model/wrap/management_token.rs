@@ -0,0 +1,36 @@
+use crate::model::secret::hash_secret;
+use std::fmt;
+use std::fmt::Formatter;
+use subtle::ConstantTimeEq;
+use url_wrap_kernel::model::wrap::error::WrapError;
+use url_wrap_kernel::model::wrap::ManagementToken;
+
+/// SHA-256 of a management token.
+pub struct HashedManagementToken(String);
+
+impl HashedManagementToken {
+    pub fn new(value: &str) -> Self {
+        Self(value.to_string())
+    }
+
+    pub fn verify(&self, management_token: &str) -> Result<(), WrapError> {
+        let hashed = hash_secret(management_token);
+        if bool::from(hashed.as_bytes().ct_eq(self.0.as_bytes())) {
+            Ok(())
+        } else {
+            Err(WrapError::InvalidCredentials)
+        }
+    }
+}
+
+impl fmt::Display for HashedManagementToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&ManagementToken> for HashedManagementToken {
+    fn from(management_token: &ManagementToken) -> Self {
+        Self(hash_secret(&management_token.0))
+    }
+}
@@ -1,9 +1,12 @@
 use anyhow::anyhow;
+use argon2::password_hash;
+use argon2::password_hash::rand_core::OsRng;
 use argon2::password_hash::{Ident, SaltString};
 use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
 use std::env;
 use std::fmt;
 use std::fmt::Formatter;
+use url_wrap_kernel::model::wrap::error::WrapError;
 use url_wrap_kernel::model::wrap::PHCString;
 
 pub struct HashedPassword(String);
@@ -13,9 +16,38 @@ impl HashedPassword {
         Self(value.to_string())
     }
 
-    pub fn verify(&self, password: &str) -> anyhow::Result<()> {
+    pub fn verify(&self, password: &str) -> Result<(), WrapError> {
         verify_password(&self.to_string(), password)
     }
+
+    /// Returns `true` when the hash is weaker than the current `ARGON2_PHC_*` policy
+    /// or was created with the deployment-wide `ARGON2_PHC_SALT`.
+    pub fn needs_rehash(&self) -> anyhow::Result<bool> {
+        self.is_outdated(&init_hashing_parameter(), init_legacy_salt().as_deref())
+    }
+
+    fn is_outdated(
+        &self,
+        policy: &HashingParameter,
+        legacy_salt: Option<&str>,
+    ) -> anyhow::Result<bool> {
+        let password_hash = PasswordHash::new(&self.0).map_err(|e| anyhow!(e))?;
+        let (algorithm, version, params) = read_hashing_parameter(&password_hash)?;
+        let (policy_algorithm, policy_version, policy_params) = policy.to_argon2_parameter()?;
+
+        let outdated = algorithm != policy_algorithm
+            || (version as u32) < (policy_version as u32)
+            || params.m_cost() < policy_params.m_cost()
+            || params.t_cost() < policy_params.t_cost()
+            || params.p_cost() < policy_params.p_cost();
+
+        let legacy_salt = match (password_hash.salt, legacy_salt) {
+            (Some(salt), Some(legacy_salt)) => salt.as_str() == legacy_salt,
+            _ => false,
+        };
+
+        Ok(outdated || legacy_salt)
+    }
 }
 
 impl fmt::Display for HashedPassword {
@@ -39,8 +71,7 @@ impl From<HashedPassword> for PHCString {
     }
 }
 
-struct HashingParameter {
-    salt: String,
+pub(super) struct HashingParameter {
     variant: String,
     version: u32,
     time_cost: u32,
@@ -50,7 +81,6 @@ struct HashingParameter {
 
 impl HashingParameter {
     fn new(
-        salt: String,
         variant: String,
         version: u32,
         time_cost: u32,
@@ -58,7 +88,6 @@ impl HashingParameter {
         parallelism_cost: u32,
     ) -> Self {
         Self {
-            salt,
             variant,
             version,
             time_cost,
@@ -66,48 +95,77 @@ impl HashingParameter {
             parallelism_cost,
         }
     }
+
+    pub(super) fn to_argon2_parameter(&self) -> anyhow::Result<(Algorithm, Version, Params)> {
+        let ident = Ident::try_from(self.variant.as_str()).map_err(|e| anyhow!(e))?;
+        let algorithm = Algorithm::try_from(ident).map_err(|e| anyhow!(e))?;
+        let version = Version::try_from(self.version).map_err(|e| anyhow!(e))?;
+
+        let params = Params::new(
+            self.memory_cost,
+            self.time_cost,
+            self.parallelism_cost,
+            None,
+        )
+        .map_err(|e| anyhow!(e))?;
+
+        Ok((algorithm, version, params))
+    }
 }
 
 fn hash_password(password: &str) -> anyhow::Result<String> {
-    let bin_password = password.as_bytes();
-    let encryption_data = init_hashing_parameter();
+    let salt_string = SaltString::generate(&mut OsRng);
+    hash_password_with(password, &init_hashing_parameter(), &salt_string)
+}
 
-    let salt_string = SaltString::new(&encryption_data.salt).map_err(|e| anyhow!(e))?;
+fn hash_password_with(
+    password: &str,
+    parameter: &HashingParameter,
+    salt_string: &SaltString,
+) -> anyhow::Result<String> {
+    let bin_password = password.as_bytes();
 
     // Argon2 with customized params
-    let ident = Ident::try_from(encryption_data.variant.as_str()).map_err(|e| anyhow!(e))?;
-    let algorithm = Algorithm::try_from(ident).map_err(|e| anyhow!(e))?;
-    let version = Version::try_from(encryption_data.version).map_err(|e| anyhow!(e))?;
-
-    let params = Params::new(
-        encryption_data.memory_cost,
-        encryption_data.time_cost,
-        encryption_data.parallelism_cost,
-        None,
-    )
-    .map_err(|e| anyhow!(e))?;
+    let (algorithm, version, params) = parameter.to_argon2_parameter()?;
     let argon2 = Argon2::new(algorithm, version, params);
 
     Ok(argon2
-        .hash_password(bin_password, &salt_string)
+        .hash_password(bin_password, salt_string)
         .map_err(|e| anyhow!(e))?
         .to_string())
 }
 
-fn verify_password(hashed_password: &str, password: &str) -> anyhow::Result<()> {
+fn verify_password(hashed_password: &str, password: &str) -> Result<(), WrapError> {
     let bin_password = password.as_bytes();
-    let password_hash = PasswordHash::new(hashed_password).map_err(|e| anyhow!(e))?;
-    match Argon2::default().verify_password(bin_password, &password_hash) {
+    let password_hash =
+        PasswordHash::new(hashed_password).map_err(|e| WrapError::Corrupted(anyhow!(e)))?;
+
+    // Argon2 with the params the hash was created with
+    let (algorithm, version, params) =
+        read_hashing_parameter(&password_hash).map_err(WrapError::Corrupted)?;
+    let argon2 = Argon2::new(algorithm, version, params);
+
+    match argon2.verify_password(bin_password, &password_hash) {
         Ok(_) => Ok(()),
-        Err(err) => Err(anyhow!(err)),
+        Err(password_hash::Error::Password) => Err(WrapError::InvalidCredentials),
+        Err(err) => Err(WrapError::Corrupted(anyhow!(err))),
     }
 }
 
-fn init_hashing_parameter() -> HashingParameter {
-    let salt = env::var_os("ARGON2_PHC_SALT")
-        .expect("ARGON2_PHC_SALT is undefined.")
-        .into_string()
-        .expect("ARGON2_PHC_SALT is invalid value.");
+pub(super) fn read_hashing_parameter(
+    password_hash: &PasswordHash,
+) -> anyhow::Result<(Algorithm, Version, Params)> {
+    let algorithm = Algorithm::try_from(password_hash.algorithm).map_err(|e| anyhow!(e))?;
+    let version = match password_hash.version {
+        Some(version) => Version::try_from(version).map_err(|e| anyhow!(e))?,
+        None => Version::default(),
+    };
+    let params = Params::try_from(password_hash).map_err(|e| anyhow!(e))?;
+
+    Ok((algorithm, version, params))
+}
+
+pub(super) fn init_hashing_parameter() -> HashingParameter {
     let variant = env::var_os("ARGON2_PHC_VARIANT")
         .expect("ARGON2_PHC_VARIANT is undefined.")
         .into_string()
@@ -137,12 +195,74 @@ fn init_hashing_parameter() -> HashingParameter {
         .parse::<u32>()
         .expect("ARGON2_PHC_PARALLELISM_COST is invalid value.");
 
-    HashingParameter::new(
-        salt,
-        variant,
-        version,
-        time_cost,
-        memory_cost,
-        parallelism_cost,
-    )
+    HashingParameter::new(variant, version, time_cost, memory_cost, parallelism_cost)
+}
+
+/// The salt shared by every hash written before per-password salts were introduced.
+fn init_legacy_salt() -> Option<String> {
+    env::var_os("ARGON2_PHC_SALT").map(|salt| {
+        salt.into_string()
+            .expect("ARGON2_PHC_SALT is invalid value.")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGACY_SALT: &str = "bGVnYWN5X3NhbHRfc3RyaW5n";
+
+    fn policy() -> HashingParameter {
+        HashingParameter::new("argon2id".to_string(), 19, 2, 1024, 1)
+    }
+
+    fn hash(parameter: &HashingParameter, salt: Option<&str>) -> HashedPassword {
+        let salt_string = match salt {
+            Some(salt) => SaltString::new(salt).unwrap(),
+            None => SaltString::generate(&mut OsRng),
+        };
+        HashedPassword::new(&hash_password_with("correct horse", parameter, &salt_string).unwrap())
+    }
+
+    #[test]
+    fn current_hash_is_kept() {
+        let hashed = hash(&policy(), None);
+
+        assert!(!hashed.is_outdated(&policy(), Some(LEGACY_SALT)).unwrap());
+    }
+
+    #[test]
+    fn weaker_params_need_rehash() {
+        let weaker = [
+            HashingParameter::new("argon2id".to_string(), 19, 1, 1024, 1),
+            HashingParameter::new("argon2id".to_string(), 19, 2, 512, 1),
+            HashingParameter::new("argon2id".to_string(), 16, 2, 1024, 1),
+            HashingParameter::new("argon2i".to_string(), 19, 2, 1024, 1),
+        ];
+
+        for parameter in weaker {
+            let hashed = hash(&parameter, None);
+            assert!(hashed.is_outdated(&policy(), None).unwrap(), "{}", hashed);
+        }
+    }
+
+    #[test]
+    fn legacy_salt_needs_rehash() {
+        let hashed = hash(&policy(), Some(LEGACY_SALT));
+
+        assert!(hashed.is_outdated(&policy(), Some(LEGACY_SALT)).unwrap());
+        assert!(!hashed.is_outdated(&policy(), None).unwrap());
+    }
+
+    #[test]
+    fn outdated_hashes_still_verify() {
+        let weaker = HashingParameter::new("argon2i".to_string(), 16, 1, 512, 1);
+        let hashed = hash(&weaker, Some(LEGACY_SALT));
+
+        assert!(hashed.verify("correct horse").is_ok());
+        assert!(matches!(
+            hashed.verify("wrong"),
+            Err(WrapError::InvalidCredentials)
+        ));
+    }
 }
@@ -1,6 +1,6 @@
 use aes_gcm::aead::generic_array::GenericArray;
-use aes_gcm::aead::Aead;
-use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use aes_gcm::aead::{Aead, OsRng};
+use aes_gcm::{AeadCore, Aes256Gcm, KeyInit, Nonce};
 use anyhow::anyhow;
 use data_encoding::HEXLOWER;
 use std::env;
@@ -8,6 +8,11 @@ use std::fmt;
 use std::fmt::Formatter;
 use std::str;
 
+/// Prefix of the current envelope format: `v1:<hex nonce>:<hex ciphertext>`.
+/// Documents without a prefix are legacy ciphertexts sealed with `AES_GCM_NONCE`.
+const ENVELOPE_V1: &str = "v1";
+const ENVELOPE_SEPARATOR: char = ':';
+
 pub struct EncryptedRedirectUrl(String);
 
 impl fmt::Display for EncryptedRedirectUrl {
@@ -20,7 +25,7 @@ impl TryFrom<String> for EncryptedRedirectUrl {
     type Error = anyhow::Error;
 
     fn try_from(url: String) -> Result<Self, Self::Error> {
-        let encrypted = encrypt_url(url.as_bytes())?;
+        let encrypted = encrypt(url.as_bytes())?;
         Ok(EncryptedRedirectUrl(encrypted))
     }
 }
@@ -37,7 +42,7 @@ impl TryFrom<String> for DecryptedRedirectUrl {
     type Error = anyhow::Error;
 
     fn try_from(url: String) -> Result<Self, Self::Error> {
-        let decrypted = decrypt_url(&url)?;
+        let decrypted = decrypt(&url)?;
         Ok(DecryptedRedirectUrl(decrypted))
     }
 }
@@ -45,51 +50,94 @@ impl TryFrom<String> for DecryptedRedirectUrl {
 #[derive(Debug)]
 struct EncryptionParameter {
     key: String,
-    nonce: String,
+    legacy_nonce: Option<String>,
 }
 
 impl EncryptionParameter {
-    fn new(key: String, nonce: String) -> Self {
-        Self { key, nonce }
+    fn new(key: String, legacy_nonce: Option<String>) -> Self {
+        Self { key, legacy_nonce }
     }
 }
 
-fn encrypt_url(url: &[u8]) -> anyhow::Result<String> {
+/// Seals `plaintext` into a `v1` envelope with the server-wide key.
+pub(super) fn encrypt(plaintext: &[u8]) -> anyhow::Result<String> {
     let parameter = init_encryption_parameter();
-    let k = parameter.key.as_bytes();
-    let n = parameter.nonce.as_bytes();
+    seal(parameter.key.as_bytes(), plaintext)
+}
 
-    let key = GenericArray::from_slice(k);
-    let nonce = Nonce::from_slice(n);
+/// Opens an envelope sealed with the server-wide key, or a legacy ciphertext.
+pub(super) fn decrypt(envelope: &str) -> anyhow::Result<String> {
+    let parameter = init_encryption_parameter();
+    open(
+        parameter.key.as_bytes(),
+        parameter.legacy_nonce.as_deref(),
+        envelope,
+    )
+}
+
+/// Seals `plaintext` into a `v1` envelope with a fresh nonce. `key` must be 32 bytes.
+pub(super) fn seal(key: &[u8], plaintext: &[u8]) -> anyhow::Result<String> {
+    let key = GenericArray::from_slice(key);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
 
     // encryption
     let cipher = Aes256Gcm::new(key);
-    let encrypted_url = cipher
-        .encrypt(nonce, url.as_ref())
-        .map_err(|e| anyhow!(e))?;
-
-    Ok(HEXLOWER.encode(&encrypted_url))
+    let encrypted = cipher.encrypt(&nonce, plaintext).map_err(|e| anyhow!(e))?;
+
+    Ok(format!(
+        "{}{}{}{}{}",
+        ENVELOPE_V1,
+        ENVELOPE_SEPARATOR,
+        HEXLOWER.encode(&nonce),
+        ENVELOPE_SEPARATOR,
+        HEXLOWER.encode(&encrypted)
+    ))
 }
 
-pub fn decrypt_url(encrypted_url: &str) -> anyhow::Result<String> {
-    let cipher_url = HEXLOWER
-        .decode(encrypted_url.as_ref())
-        .map_err(|e| anyhow!(e))?;
-
-    let parameter = init_encryption_parameter();
-    let k = parameter.key.as_bytes();
-    let n = parameter.nonce.as_bytes();
-
-    let key = GenericArray::from_slice(k);
-    let nonce = Nonce::from_slice(n);
+/// Opens a `v1` envelope. Ciphertexts without a prefix need `legacy_nonce`.
+pub(super) fn open(
+    key: &[u8],
+    legacy_nonce: Option<&str>,
+    envelope: &str,
+) -> anyhow::Result<String> {
+    let (n, ciphertext) = match envelope.split_once(ENVELOPE_SEPARATOR) {
+        Some((ENVELOPE_V1, sealed)) => {
+            let (n, c) = sealed
+                .split_once(ENVELOPE_SEPARATOR)
+                .ok_or_else(|| anyhow!("Encrypted envelope is malformed."))?;
+            let n = HEXLOWER.decode(n.as_bytes()).map_err(|e| anyhow!(e))?;
+            let c = HEXLOWER.decode(c.as_bytes()).map_err(|e| anyhow!(e))?;
+            (n, c)
+        }
+        Some((version, _)) => {
+            return Err(anyhow!("Encrypted envelope `{}` is unsupported.", version))
+        }
+        None => {
+            // legacy document sealed with the global nonce
+            let n = legacy_nonce
+                .ok_or_else(|| anyhow!("AES_GCM_NONCE is required to decrypt legacy documents."))?
+                .as_bytes()
+                .to_vec();
+            let c = HEXLOWER
+                .decode(envelope.as_bytes())
+                .map_err(|e| anyhow!(e))?;
+            (n, c)
+        }
+    };
+
+    if n.len() != 12 {
+        return Err(anyhow!("AES-GCM nonce must be 12 bytes."));
+    }
+    let key = GenericArray::from_slice(key);
+    let nonce = Nonce::from_slice(&n);
 
     // decryption
     let cipher = Aes256Gcm::new(key);
-    let decrypted_url = cipher
-        .decrypt(nonce, cipher_url.as_slice())
+    let decrypted = cipher
+        .decrypt(nonce, ciphertext.as_slice())
         .map_err(|e| anyhow!(e))?;
 
-    Ok(str::from_utf8(&decrypted_url)?.to_string())
+    Ok(str::from_utf8(&decrypted)?.to_string())
 }
 
 fn init_encryption_parameter() -> EncryptionParameter {
@@ -97,10 +145,58 @@ fn init_encryption_parameter() -> EncryptionParameter {
         .expect("AES_GCM_SALT is undefined.")
         .into_string()
         .expect("AES_GCM_SALT is invalid value.");
-    let nonce = env::var_os("AES_GCM_NONCE")
-        .expect("AES_GCM_NONCE is undefined.")
-        .into_string()
-        .expect("AES_GCM_NONCE is invalid value.");
+    // Only used to decrypt documents written before per-record nonces were introduced.
+    let legacy_nonce = env::var_os("AES_GCM_NONCE").map(|nonce| {
+        nonce
+            .into_string()
+            .expect("AES_GCM_NONCE is invalid value.")
+    });
+
+    EncryptionParameter::new(key, legacy_nonce)
+}
 
-    EncryptionParameter::new(key, nonce)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"legacy_key_32_bytes_for_fixture_";
+    const LEGACY_NONCE: &str = "legacy_nonce";
+    /// `https://example.com/legacy` as written before per-record nonces: hex of the
+    /// AES-256-GCM ciphertext under `KEY` and `LEGACY_NONCE`, without an envelope.
+    const LEGACY_CIPHERTEXT: &str =
+        "9d2b3119ea2c8ad307f29b578cbbb47c0879b864171e70dd808e56dc8a9d95121c774ab1bb3bbe7126a2";
+
+    #[test]
+    fn opens_legacy_ciphertext() {
+        let url = open(KEY, Some(LEGACY_NONCE), LEGACY_CIPHERTEXT).unwrap();
+
+        assert_eq!(url, "https://example.com/legacy");
+    }
+
+    #[test]
+    fn legacy_ciphertext_needs_the_nonce() {
+        assert!(open(KEY, None, LEGACY_CIPHERTEXT).is_err());
+    }
+
+    #[test]
+    fn opens_v1_envelope_next_to_legacy() {
+        let envelope = seal(KEY, b"https://example.com/current").unwrap();
+
+        assert!(envelope.starts_with("v1:"));
+        let url = open(KEY, Some(LEGACY_NONCE), &envelope).unwrap();
+        assert_eq!(url, "https://example.com/current");
+    }
+
+    #[test]
+    fn v1_envelopes_use_fresh_nonces() {
+        let first = seal(KEY, b"https://example.com/").unwrap();
+        let second = seal(KEY, b"https://example.com/").unwrap();
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn unknown_envelope_version_is_rejected() {
+        assert!(open(KEY, Some(LEGACY_NONCE), "v9:00:00").is_err());
+    }
 }
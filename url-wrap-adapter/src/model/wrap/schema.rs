@@ -0,0 +1,390 @@
+use anyhow::anyhow;
+use bson::{doc, Bson, Document};
+
+const SCHEMA_VERSION_FIELD: &str = "schema_version";
+
+/// Version written by this build. Documents without `schema_version` are version 0.
+pub const WRAP_SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// One step of the wrap document schema. Step `n` of `MIGRATIONS` upgrades version `n`
+/// to version `n + 1`, so new steps are only ever appended.
+struct Migration {
+    name: &'static str,
+    up: fn(&mut Document) -> anyhow::Result<()>,
+}
+
+const MIGRATIONS: [Migration; 6] = [
+    Migration {
+        name: "timestamps_to_datetime",
+        up: timestamps_to_datetime,
+    },
+    Migration {
+        name: "four_digit_to_pin4",
+        up: four_digit_to_pin4,
+    },
+    Migration {
+        name: "add_totp_fields",
+        up: add_totp_fields,
+    },
+    Migration {
+        name: "add_credentials",
+        up: add_credentials,
+    },
+    Migration {
+        name: "add_redirect_url_kdf",
+        up: add_redirect_url_kdf,
+    },
+    Migration {
+        name: "add_payload_kind",
+        up: add_payload_kind,
+    },
+];
+
+/// Filter and update that persist an upgrade, guarded by the version that was read
+/// so that a concurrent upgrade is not applied twice.
+pub struct SchemaUpgrade {
+    pub filter: Document,
+    pub update: Document,
+}
+
+/// Version `document` was written with.
+pub fn schema_version(document: &Document) -> anyhow::Result<u32> {
+    match document.get(SCHEMA_VERSION_FIELD) {
+        None | Some(Bson::Null) => Ok(0),
+        Some(Bson::Int32(v)) => u32::try_from(*v).map_err(anyhow::Error::from),
+        Some(Bson::Int64(v)) => u32::try_from(*v).map_err(anyhow::Error::from),
+        Some(other) => Err(anyhow!("`schema_version` is invalid: {}", other)),
+    }
+}
+
+/// Upgrades `document` in place to `WRAP_SCHEMA_VERSION`. Returns `None` when it already was
+/// current, otherwise how to store the upgrade. Only the changed fields are written.
+pub fn upgrade(document: &mut Document) -> anyhow::Result<Option<SchemaUpgrade>> {
+    upgrade_with(document, &MIGRATIONS)
+}
+
+fn upgrade_with(
+    document: &mut Document,
+    migrations: &[Migration],
+) -> anyhow::Result<Option<SchemaUpgrade>> {
+    let current = migrations.len() as u32;
+    let version = schema_version(document)?;
+    if version > current {
+        return Err(anyhow!(
+            "Wrap schema version {} is newer than {}.",
+            version,
+            current
+        ));
+    }
+    if version == current {
+        return Ok(None);
+    }
+
+    let original = document.clone();
+    for (i, migration) in migrations.iter().enumerate().skip(version as usize) {
+        (migration.up)(document)
+            .map_err(|e| anyhow!("Wrap migration `{}` failed: {}", migration.name, e))?;
+        document.insert(SCHEMA_VERSION_FIELD, i as i64 + 1);
+    }
+
+    let id = original
+        .get("_id")
+        .cloned()
+        .ok_or_else(|| anyhow!("Wrap document has no `_id`."))?;
+    let filter = match version {
+        // `null` also matches documents without the field
+        0 => doc! {"_id": id, SCHEMA_VERSION_FIELD: {"$in": [Bson::Null, 0]}},
+        v => doc! {"_id": id, SCHEMA_VERSION_FIELD: v as i64},
+    };
+
+    let mut set = Document::new();
+    for (key, value) in document.iter() {
+        if original.get(key) != Some(value) {
+            set.insert(key, value.clone());
+        }
+    }
+    let mut update = doc! {"$set": set};
+    let unset: Document = original
+        .keys()
+        .filter(|key| !document.contains_key(key.as_str()))
+        .map(|key| (key.clone(), Bson::String(String::new())))
+        .collect();
+    if !unset.is_empty() {
+        update.insert("$unset", unset);
+    }
+
+    Ok(Some(SchemaUpgrade { filter, update }))
+}
+
+/// 0 → 1: `expiration_at` and `created_at` were `Timestamp`s with second precision,
+/// which overflow in 2106 and are ignored by TTL indexes.
+fn timestamps_to_datetime(document: &mut Document) -> anyhow::Result<()> {
+    for field in ["expiration_at", "created_at"] {
+        if let Some(Bson::Timestamp(timestamp)) = document.get(field) {
+            let datetime = bson::DateTime::from_millis(timestamp.time as i64 * 1000);
+            document.insert(field, datetime);
+        }
+    }
+    Ok(())
+}
+
+/// 1 → 2: `FourDigit` was the only PIN auth type before PIN lengths became configurable.
+fn four_digit_to_pin4(document: &mut Document) -> anyhow::Result<()> {
+    if document.get_str("auth_type") == Ok("FourDigit") {
+        document.insert("auth_type", "Pin4");
+    }
+    Ok(())
+}
+
+/// 2 → 3: password wraps have no TOTP secret, and no TOTP code was accepted yet.
+fn add_totp_fields(document: &mut Document) -> anyhow::Result<()> {
+    for field in ["totp_secret", "totp_last_step"] {
+        if !document.contains_key(field) {
+            document.insert(field, Bson::Null);
+        }
+    }
+    Ok(())
+}
+
+/// 3 → 4: wraps could only be opened with the primary password.
+fn add_credentials(document: &mut Document) -> anyhow::Result<()> {
+    if !document.contains_key("credentials") {
+        document.insert("credentials", Bson::Array(Vec::new()));
+    }
+    Ok(())
+}
+
+/// 4 → 5: every URL was sealed with the server key.
+fn add_redirect_url_kdf(document: &mut Document) -> anyhow::Result<()> {
+    if !document.contains_key("redirect_url_kdf") {
+        document.insert("redirect_url_kdf", Bson::Null);
+    }
+    Ok(())
+}
+
+/// 5 → 6: every wrap revealed a URL.
+fn add_payload_kind(document: &mut Document) -> anyhow::Result<()> {
+    if !document.contains_key("payload_kind") {
+        document.insert("payload_kind", "Url");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::Timestamp;
+
+    fn legacy_wrap() -> Document {
+        doc! {
+            "_id": "01HZY5J3Q8V7N2K4M6P8R0T2W4",
+            "redirect_url": "sealed",
+            "password": "hashed",
+            "auth_type": "Text",
+            "expiration_at": Timestamp { time: 1_700_000_000, increment: 0 },
+            "created_at": Timestamp { time: 1_600_000_000, increment: 0 },
+        }
+    }
+
+    #[test]
+    fn upgrades_legacy_timestamps() {
+        let mut document = legacy_wrap();
+
+        let upgrade = upgrade(&mut document).unwrap().unwrap();
+
+        let expiration_at = bson::DateTime::from_millis(1_700_000_000_000);
+        let created_at = bson::DateTime::from_millis(1_600_000_000_000);
+        assert_eq!(
+            document.get_datetime("expiration_at").unwrap(),
+            &expiration_at
+        );
+        assert_eq!(document.get_datetime("created_at").unwrap(), &created_at);
+        assert_eq!(schema_version(&document).unwrap(), WRAP_SCHEMA_VERSION);
+
+        assert_eq!(
+            upgrade.filter,
+            doc! {
+                "_id": "01HZY5J3Q8V7N2K4M6P8R0T2W4",
+                "schema_version": {"$in": [Bson::Null, 0]},
+            }
+        );
+        assert_eq!(
+            upgrade.update,
+            doc! {"$set": {
+                "expiration_at": expiration_at,
+                "created_at": created_at,
+                "schema_version": WRAP_SCHEMA_VERSION as i64,
+                "totp_secret": Bson::Null,
+                "totp_last_step": Bson::Null,
+                "credentials": [],
+                "redirect_url_kdf": Bson::Null,
+                "payload_kind": "Url",
+            }}
+        );
+    }
+
+    #[test]
+    fn renames_four_digit_auth_type() {
+        let mut document = legacy_wrap();
+        document.insert("auth_type", "FourDigit");
+        document.insert("schema_version", 1);
+
+        let upgrade = upgrade(&mut document).unwrap().unwrap();
+
+        assert_eq!(document.get_str("auth_type").unwrap(), "Pin4");
+        assert_eq!(
+            upgrade.filter,
+            doc! {"_id": "01HZY5J3Q8V7N2K4M6P8R0T2W4", "schema_version": 1_i64}
+        );
+        let set = upgrade.update.get_document("$set").unwrap();
+        assert_eq!(set.get_str("auth_type").unwrap(), "Pin4");
+    }
+
+    #[test]
+    fn adds_missing_totp_fields() {
+        let mut document = legacy_wrap();
+        document.insert("schema_version", 2);
+
+        let upgrade = upgrade(&mut document).unwrap().unwrap();
+
+        let set = upgrade.update.get_document("$set").unwrap();
+        assert_eq!(set.get("totp_secret"), Some(&Bson::Null));
+        assert_eq!(set.get("totp_last_step"), Some(&Bson::Null));
+    }
+
+    #[test]
+    fn keeps_existing_totp_secret() {
+        let mut document = legacy_wrap();
+        document.insert("schema_version", 2);
+        document.insert("totp_secret", "sealed");
+
+        let upgrade = upgrade(&mut document).unwrap().unwrap();
+
+        assert_eq!(document.get_str("totp_secret").unwrap(), "sealed");
+        let set = upgrade.update.get_document("$set").unwrap();
+        assert!(!set.contains_key("totp_secret"));
+    }
+
+    #[test]
+    fn adds_missing_credentials() {
+        let mut document = legacy_wrap();
+        document.insert("schema_version", 3);
+
+        let upgrade = upgrade(&mut document).unwrap().unwrap();
+
+        let set = upgrade.update.get_document("$set").unwrap();
+        assert_eq!(set.get_array("credentials").unwrap(), &Vec::<Bson>::new());
+    }
+
+    #[test]
+    fn keeps_existing_credentials() {
+        let mut document = legacy_wrap();
+        document.insert("schema_version", 3);
+        document.insert("credentials", vec![doc! {"id": "credential"}]);
+
+        let upgrade = upgrade(&mut document).unwrap().unwrap();
+
+        assert_eq!(document.get_array("credentials").unwrap().len(), 1);
+        let set = upgrade.update.get_document("$set").unwrap();
+        assert!(!set.contains_key("credentials"));
+    }
+
+    #[test]
+    fn adds_missing_redirect_url_kdf() {
+        let mut document = legacy_wrap();
+        document.insert("schema_version", 4);
+
+        let upgrade = upgrade(&mut document).unwrap().unwrap();
+
+        let set = upgrade.update.get_document("$set").unwrap();
+        assert_eq!(set.get("redirect_url_kdf"), Some(&Bson::Null));
+    }
+
+    #[test]
+    fn keeps_existing_redirect_url_kdf() {
+        let mut document = legacy_wrap();
+        document.insert("schema_version", 4);
+        document.insert("redirect_url_kdf", "argon2id$salt");
+
+        let upgrade = upgrade(&mut document).unwrap().unwrap();
+
+        assert_eq!(
+            document.get_str("redirect_url_kdf").unwrap(),
+            "argon2id$salt"
+        );
+        let set = upgrade.update.get_document("$set").unwrap();
+        assert!(!set.contains_key("redirect_url_kdf"));
+    }
+
+    #[test]
+    fn adds_missing_payload_kind() {
+        let mut document = legacy_wrap();
+        document.insert("schema_version", 5);
+
+        let upgrade = upgrade(&mut document).unwrap().unwrap();
+
+        let set = upgrade.update.get_document("$set").unwrap();
+        assert_eq!(set.get_str("payload_kind").unwrap(), "Url");
+    }
+
+    #[test]
+    fn keeps_existing_payload_kind() {
+        let mut document = legacy_wrap();
+        document.insert("schema_version", 5);
+        document.insert("payload_kind", "Text");
+
+        let upgrade = upgrade(&mut document).unwrap().unwrap();
+
+        assert_eq!(document.get_str("payload_kind").unwrap(), "Text");
+        let set = upgrade.update.get_document("$set").unwrap();
+        assert!(!set.contains_key("payload_kind"));
+    }
+
+    #[test]
+    fn current_document_is_left_alone() {
+        let mut document = legacy_wrap();
+        upgrade(&mut document).unwrap();
+        let upgraded = document.clone();
+
+        assert!(upgrade(&mut document).unwrap().is_none());
+        assert_eq!(document, upgraded);
+    }
+
+    #[test]
+    fn newer_document_is_rejected() {
+        let mut document = legacy_wrap();
+        document.insert("schema_version", WRAP_SCHEMA_VERSION as i64 + 1);
+
+        assert!(upgrade(&mut document).is_err());
+    }
+
+    #[test]
+    fn removed_fields_are_unset_and_the_read_version_is_guarded() {
+        fn drop_legacy_field(document: &mut Document) -> anyhow::Result<()> {
+            document.remove("legacy");
+            Ok(())
+        }
+        let migrations = [
+            Migration {
+                name: "noop",
+                up: |_| Ok(()),
+            },
+            Migration {
+                name: "drop_legacy_field",
+                up: drop_legacy_field,
+            },
+        ];
+        let mut document = doc! {"_id": "id", "legacy": true, "schema_version": 1};
+
+        let upgrade = upgrade_with(&mut document, &migrations).unwrap().unwrap();
+
+        assert_eq!(upgrade.filter, doc! {"_id": "id", "schema_version": 1_i64});
+        assert_eq!(
+            upgrade.update,
+            doc! {
+                "$set": {"schema_version": 2_i64},
+                "$unset": {"legacy": ""},
+            }
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::model::wrap::redirect_url::{decrypt, encrypt};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use data_encoding::BASE32_NOPAD;
+use std::env;
+use std::fmt;
+use std::fmt::Formatter;
+use totp_rs::{Algorithm, TOTP};
+use url_wrap_kernel::model::wrap::error::WrapError;
+use url_wrap_kernel::model::wrap::totp::{TotpSecret, TOTP_DIGITS, TOTP_PERIOD_SECONDS};
+
+const DEFAULT_SKEW_STEPS: u8 = 1;
+
+/// TOTP secret sealed with the same AES-GCM envelope as the redirect URL.
+pub struct EncryptedTotpSecret(String);
+
+impl EncryptedTotpSecret {
+    pub fn new(value: &str) -> Self {
+        Self(value.to_string())
+    }
+
+    /// Accepts the code of the current period and of `TOTP_SKEW_STEPS` periods around it,
+    /// but never of `last_step` or before, so that a code cannot be replayed.
+    /// Returns the time step of the accepted code.
+    pub fn verify(
+        &self,
+        code: &str,
+        now: DateTime<Utc>,
+        last_step: Option<i64>,
+    ) -> Result<i64, WrapError> {
+        let secret = decrypt(&self.0)
+            .and_then(|v| BASE32_NOPAD.decode(v.as_bytes()).map_err(|e| anyhow!(e)))
+            .map_err(WrapError::Corrupted)?;
+
+        // each step is checked on its own to learn which one matched
+        let totp = TOTP::new(Algorithm::SHA1, TOTP_DIGITS, 0, TOTP_PERIOD_SECONDS, secret)
+            .map_err(|e| WrapError::Corrupted(anyhow!(e)))?;
+
+        let period = TOTP_PERIOD_SECONDS as i64;
+        let current = now.timestamp().div_euclid(period);
+        let skew = init_skew_steps() as i64;
+        ((current - skew)..=(current + skew))
+            .filter(|step| *step >= 0 && last_step.is_none_or(|last| *step > last))
+            .find(|step| totp.check(code, (step * period) as u64))
+            .ok_or(WrapError::InvalidCredentials)
+    }
+}
+
+impl fmt::Display for EncryptedTotpSecret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<&TotpSecret> for EncryptedTotpSecret {
+    type Error = anyhow::Error;
+
+    fn try_from(secret: &TotpSecret) -> Result<Self, Self::Error> {
+        let encrypted = encrypt(secret.to_base32().as_bytes())?;
+        Ok(EncryptedTotpSecret(encrypted))
+    }
+}
+
+fn init_skew_steps() -> u8 {
+    env::var_os("TOTP_SKEW_STEPS")
+        .map(|v| {
+            v.into_string()
+                .expect("TOTP_SKEW_STEPS is invalid value.")
+                .parse::<u8>()
+                .expect("TOTP_SKEW_STEPS is invalid value.")
+        })
+        .unwrap_or(DEFAULT_SKEW_STEPS)
+}
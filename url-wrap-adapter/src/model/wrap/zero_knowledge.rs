@@ -0,0 +1,66 @@
+use crate::model::wrap::password::{init_hashing_parameter, read_hashing_parameter};
+use crate::model::wrap::redirect_url::{open, seal};
+use anyhow::anyhow;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, SaltString};
+use argon2::Argon2;
+use std::fmt;
+use std::fmt::Formatter;
+
+const KEY_BYTES: usize = 32;
+
+/// Argon2 parameters and per-wrap salt the key of a zero-knowledge wrap is derived with.
+/// Kept as a PHC string without hash, so changing the `ARGON2_PHC_*` policy keeps older wraps readable.
+pub struct UrlKeyDerivation(String);
+
+impl UrlKeyDerivation {
+    pub fn new(value: &str) -> Self {
+        Self(value.to_string())
+    }
+
+    /// A fresh salt with the current `ARGON2_PHC_*` policy.
+    pub fn gen() -> anyhow::Result<Self> {
+        let (algorithm, version, params) = init_hashing_parameter().to_argon2_parameter()?;
+        let salt = SaltString::generate(&mut OsRng);
+
+        Ok(Self(format!(
+            "${}$v={}$m={},t={},p={}${}",
+            algorithm.ident(),
+            version as u32,
+            params.m_cost(),
+            params.t_cost(),
+            params.p_cost(),
+            salt.as_str()
+        )))
+    }
+
+    pub fn seal(&self, password: &str, url: &str) -> anyhow::Result<String> {
+        seal(&self.derive_key(password)?, url.as_bytes())
+    }
+
+    pub fn open(&self, password: &str, envelope: &str) -> anyhow::Result<String> {
+        open(&self.derive_key(password)?, None, envelope)
+    }
+
+    fn derive_key(&self, password: &str) -> anyhow::Result<[u8; KEY_BYTES]> {
+        let password_hash = PasswordHash::new(&self.0).map_err(|e| anyhow!(e))?;
+        let (algorithm, version, params) = read_hashing_parameter(&password_hash)?;
+        let salt = password_hash
+            .salt
+            .ok_or_else(|| anyhow!("Key derivation has no salt."))?;
+        let mut salt_buf = [0u8; 64];
+        let salt = salt.b64_decode(&mut salt_buf).map_err(|e| anyhow!(e))?;
+
+        let mut key = [0u8; KEY_BYTES];
+        Argon2::new(algorithm, version, params)
+            .hash_password_into(password.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow!(e))?;
+        Ok(key)
+    }
+}
+
+impl fmt::Display for UrlKeyDerivation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
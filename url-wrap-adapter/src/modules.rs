@@ -1,30 +1,139 @@
+use crate::persistence::in_memory::InMemoryDb;
+#[cfg(feature = "mongodb")]
 use crate::persistence::mongodb::Db;
-use crate::repository::MongoDBRepositoryImpl;
+#[cfg(feature = "sql")]
+use crate::persistence::sql::SqlDb;
+use crate::repository::in_memory::InMemoryRepositoryImpl;
+#[cfg(feature = "mongodb")]
+use crate::repository::mongodb::MongoDBRepositoryImpl;
+#[cfg(feature = "sql")]
+use crate::repository::sql::SqlRepositoryImpl;
+#[cfg(any(feature = "mongodb", feature = "sql"))]
+use std::env;
+use url_wrap_kernel::model::access_event::AccessEvent;
+use url_wrap_kernel::model::owner::{ApiKey, Owner};
+use url_wrap_kernel::model::rate_limit::RateLimit;
 use url_wrap_kernel::model::wrap::Wrap;
+use url_wrap_kernel::repository::access_log::AccessLogRepository;
+use url_wrap_kernel::repository::api_key::ApiKeyRepository;
+use url_wrap_kernel::repository::owner::OwnerRepository;
+use url_wrap_kernel::repository::rate_limit::RateLimitRepository;
 use url_wrap_kernel::repository::wrap::WrapRepository;
 
+#[cfg(any(feature = "mongodb", feature = "sql"))]
+const RATE_LIMIT_STORE: &str = "RATE_LIMIT_STORE";
+
 pub struct RepositoriesModule {
-    wrap_repository: MongoDBRepositoryImpl<Wrap>,
+    wrap_repository: Box<dyn WrapRepository + Send + Sync>,
+    rate_limit_repository: Box<dyn RateLimitRepository + Send + Sync>,
+    owner_repository: Box<dyn OwnerRepository + Send + Sync>,
+    api_key_repository: Box<dyn ApiKeyRepository + Send + Sync>,
+    access_log_repository: Box<dyn AccessLogRepository + Send + Sync>,
 }
 
 pub trait RepositoriesModuleExt {
-    type WrapRepo: WrapRepository;
+    type WrapRepo: WrapRepository + ?Sized;
+    type RateLimitRepo: RateLimitRepository + ?Sized;
+    type OwnerRepo: OwnerRepository + ?Sized;
+    type ApiKeyRepo: ApiKeyRepository + ?Sized;
+    type AccessLogRepo: AccessLogRepository + ?Sized;
 
     fn wrap_repository(&self) -> &Self::WrapRepo;
+    fn rate_limit_repository(&self) -> &Self::RateLimitRepo;
+    fn owner_repository(&self) -> &Self::OwnerRepo;
+    fn api_key_repository(&self) -> &Self::ApiKeyRepo;
+    fn access_log_repository(&self) -> &Self::AccessLogRepo;
 }
 
 impl RepositoriesModuleExt for RepositoriesModule {
-    type WrapRepo = MongoDBRepositoryImpl<Wrap>;
+    type WrapRepo = dyn WrapRepository + Send + Sync;
+    type RateLimitRepo = dyn RateLimitRepository + Send + Sync;
+    type OwnerRepo = dyn OwnerRepository + Send + Sync;
+    type ApiKeyRepo = dyn ApiKeyRepository + Send + Sync;
+    type AccessLogRepo = dyn AccessLogRepository + Send + Sync;
 
     fn wrap_repository(&self) -> &Self::WrapRepo {
-        &self.wrap_repository
+        self.wrap_repository.as_ref()
+    }
+
+    fn rate_limit_repository(&self) -> &Self::RateLimitRepo {
+        self.rate_limit_repository.as_ref()
+    }
+
+    fn owner_repository(&self) -> &Self::OwnerRepo {
+        self.owner_repository.as_ref()
+    }
+
+    fn api_key_repository(&self) -> &Self::ApiKeyRepo {
+        self.api_key_repository.as_ref()
+    }
+
+    fn access_log_repository(&self) -> &Self::AccessLogRepo {
+        self.access_log_repository.as_ref()
     }
 }
 
 impl RepositoriesModule {
+    #[cfg(feature = "mongodb")]
     pub fn new(db: Db) -> Self {
-        let wrap_repository = MongoDBRepositoryImpl::new(db.clone());
+        let wrap_repository = Box::new(MongoDBRepositoryImpl::<Wrap>::new(db.clone()));
+        let owner_repository = Box::new(MongoDBRepositoryImpl::<Owner>::new(db.clone()));
+        let api_key_repository = Box::new(MongoDBRepositoryImpl::<ApiKey>::new(db.clone()));
+        let access_log_repository = Box::new(MongoDBRepositoryImpl::<AccessEvent>::new(db.clone()));
+
+        // `mongodb` shares the limits between every instance connected to the same database.
+        let rate_limit_repository: Box<dyn RateLimitRepository + Send + Sync> =
+            match env::var(RATE_LIMIT_STORE).as_deref() {
+                Ok("mongodb") => Box::new(MongoDBRepositoryImpl::<RateLimit>::new(db)),
+                Ok("memory") | Err(_) => {
+                    Box::new(InMemoryRepositoryImpl::<RateLimit>::new(InMemoryDb::new()))
+                }
+                Ok(store) => panic!("{} `{}` is invalid value.", RATE_LIMIT_STORE, store),
+            };
+
+        Self {
+            wrap_repository,
+            rate_limit_repository,
+            owner_repository,
+            api_key_repository,
+            access_log_repository,
+        }
+    }
+
+    #[cfg(feature = "sql")]
+    pub fn sql(db: SqlDb) -> Self {
+        let wrap_repository = Box::new(SqlRepositoryImpl::<Wrap>::new(db.clone()));
+        let owner_repository = Box::new(SqlRepositoryImpl::<Owner>::new(db.clone()));
+        let api_key_repository = Box::new(SqlRepositoryImpl::<ApiKey>::new(db.clone()));
+        let access_log_repository = Box::new(SqlRepositoryImpl::<AccessEvent>::new(db.clone()));
+
+        // `sql` shares the limits between every instance connected to the same database.
+        let rate_limit_repository: Box<dyn RateLimitRepository + Send + Sync> =
+            match env::var(RATE_LIMIT_STORE).as_deref() {
+                Ok("sql") => Box::new(SqlRepositoryImpl::<RateLimit>::new(db)),
+                Ok("memory") | Err(_) => {
+                    Box::new(InMemoryRepositoryImpl::<RateLimit>::new(InMemoryDb::new()))
+                }
+                Ok(store) => panic!("{} `{}` is invalid value.", RATE_LIMIT_STORE, store),
+            };
+
+        Self {
+            wrap_repository,
+            rate_limit_repository,
+            owner_repository,
+            api_key_repository,
+            access_log_repository,
+        }
+    }
 
-        Self { wrap_repository }
+    /// Repositories that keep everything in process memory, for tests and local development.
+    pub fn in_memory(db: InMemoryDb) -> Self {
+        Self {
+            wrap_repository: Box::new(InMemoryRepositoryImpl::<Wrap>::new(db.clone())),
+            rate_limit_repository: Box::new(InMemoryRepositoryImpl::<RateLimit>::new(db.clone())),
+            owner_repository: Box::new(InMemoryRepositoryImpl::<Owner>::new(db.clone())),
+            api_key_repository: Box::new(InMemoryRepositoryImpl::<ApiKey>::new(db.clone())),
+            access_log_repository: Box::new(InMemoryRepositoryImpl::<AccessEvent>::new(db)),
+        }
     }
 }
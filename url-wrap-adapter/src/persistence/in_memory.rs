@@ -0,0 +1,152 @@
+use anyhow::anyhow;
+use bson::Document;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+type Collections = HashMap<String, BTreeMap<String, Document>>;
+
+/// Process-local stand-in for `Db` that keeps BSON documents per collection, ordered by `_id`.
+#[derive(Clone, Default)]
+pub struct InMemoryDb(pub(crate) Arc<Mutex<Collections>>);
+
+impl InMemoryDb {
+    pub fn new() -> InMemoryDb {
+        InMemoryDb::default()
+    }
+
+    pub(crate) fn lock(&self) -> anyhow::Result<MutexGuard<'_, Collections>> {
+        self.0.lock().map_err(|e| anyhow!("{}", e))
+    }
+
+    pub(crate) fn find_one<T>(&self, collection: &str, id: &str) -> anyhow::Result<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let collections = self.lock()?;
+        match collections.get(collection).and_then(|c| c.get(id)) {
+            Some(d) => Ok(Some(bson::from_document(d.clone())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the documents matching `filter` in `_id` order.
+    pub(crate) fn find_many<T, F>(&self, collection: &str, filter: F) -> anyhow::Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+        F: Fn(&T) -> bool,
+    {
+        let collections = self.lock()?;
+        let mut values = Vec::new();
+        for d in collections
+            .get(collection)
+            .into_iter()
+            .flat_map(|c| c.values())
+        {
+            let value: T = bson::from_document(d.clone())?;
+            if filter(&value) {
+                values.push(value);
+            }
+        }
+        Ok(values)
+    }
+
+    pub(crate) fn insert_one<T>(&self, collection: &str, id: &str, value: &T) -> anyhow::Result<()>
+    where
+        T: Serialize,
+    {
+        let mut collections = self.lock()?;
+        let collection = collections.entry(collection.to_string()).or_default();
+        if collection.contains_key(id) {
+            return Err(anyhow!("Duplicate `_id`: {}", id));
+        }
+        collection.insert(id.to_string(), bson::to_document(value)?);
+        Ok(())
+    }
+
+    /// Inserts `value` unless a document matching `conflicts` exists, like a unique index.
+    /// Returns `false` when it was not inserted.
+    pub(crate) fn insert_unique<T, F>(
+        &self,
+        collection: &str,
+        id: &str,
+        value: &T,
+        conflicts: F,
+    ) -> anyhow::Result<bool>
+    where
+        T: DeserializeOwned + Serialize,
+        F: Fn(&T) -> bool,
+    {
+        let mut collections = self.lock()?;
+        let collection = collections.entry(collection.to_string()).or_default();
+        for d in collection.values() {
+            let existing: T = bson::from_document(d.clone())?;
+            if conflicts(&existing) {
+                return Ok(false);
+            }
+        }
+        if collection.contains_key(id) {
+            return Err(anyhow!("Duplicate `_id`: {}", id));
+        }
+        collection.insert(id.to_string(), bson::to_document(value)?);
+        Ok(true)
+    }
+
+    /// Applies `update` to the document while holding the lock and returns the updated document.
+    pub(crate) fn update_one<T, F>(
+        &self,
+        collection: &str,
+        id: &str,
+        update: F,
+    ) -> anyhow::Result<Option<T>>
+    where
+        T: DeserializeOwned + Serialize,
+        F: FnOnce(&mut T),
+    {
+        let mut collections = self.lock()?;
+        let document = match collections.get_mut(collection).and_then(|c| c.get_mut(id)) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        let mut value: T = bson::from_document(document.clone())?;
+        update(&mut value);
+        *document = bson::to_document(&value)?;
+        Ok(Some(value))
+    }
+
+    /// Removes the documents matching `filter` and returns how many were removed.
+    pub(crate) fn delete_many<T, F>(&self, collection: &str, filter: F) -> anyhow::Result<u64>
+    where
+        T: DeserializeOwned,
+        F: Fn(&T) -> bool,
+    {
+        let mut collections = self.lock()?;
+        let documents = match collections.get_mut(collection) {
+            Some(c) => c,
+            None => return Ok(0),
+        };
+
+        let mut ids = Vec::new();
+        for (id, d) in documents.iter() {
+            let value: T = bson::from_document(d.clone())?;
+            if filter(&value) {
+                ids.push(id.clone());
+            }
+        }
+        for id in ids.iter() {
+            documents.remove(id);
+        }
+        Ok(ids.len() as u64)
+    }
+
+    /// Returns `true` when a document was removed.
+    pub(crate) fn delete_one(&self, collection: &str, id: &str) -> anyhow::Result<bool> {
+        let mut collections = self.lock()?;
+        Ok(collections
+            .get_mut(collection)
+            .and_then(|c| c.remove(id))
+            .is_some())
+    }
+}
@@ -1 +1,5 @@
+pub mod in_memory;
+#[cfg(feature = "mongodb")]
 pub mod mongodb;
+#[cfg(feature = "sql")]
+pub mod sql;
@@ -1,7 +1,13 @@
 use std::env;
 use std::sync::Arc;
+use std::time::Duration;
 
-use mongodb::{Client, Database};
+use bson::{doc, Document};
+use futures::TryStreamExt;
+use mongodb::options::IndexOptions;
+use mongodb::{Client, Database, IndexModel};
+
+use crate::model::wrap::schema::{self, WRAP_SCHEMA_VERSION};
 
 #[derive(Clone)]
 pub struct Db(pub(crate) Arc<Database>);
@@ -9,10 +15,15 @@ pub struct Db(pub(crate) Arc<Database>);
 const URL: &str = "DATABASE_URL";
 const DB_NAME: &str = "URL_WRAP_DB_NAME";
 
+const WRAPS: &str = "wraps";
+
+/// Suffix of the names of the indexes managed by `ensure_indexes`, followed by the version.
+const INDEX_VERSION_SEPARATOR: &str = "_v";
+
 impl Db {
     pub async fn new() -> Db {
-        let uri = env::var(URL).expect(undefined_msg(URL).as_str());
-        let db_name = env::var(DB_NAME).expect(undefined_msg(DB_NAME).as_str());
+        let uri = env::var(URL).unwrap_or_else(|_| panic!("{}", undefined_msg(URL)));
+        let db_name = env::var(DB_NAME).unwrap_or_else(|_| panic!("{}", undefined_msg(DB_NAME)));
 
         let client = Client::with_uri_str(&uri)
             .await
@@ -21,8 +32,173 @@ impl Db {
 
         Db(Arc::new(db))
     }
+
+    /// Brings the database up to date: upgrades old wrap documents, then ensures the indexes.
+    /// Safe to run any number of times.
+    pub async fn migrate(&self) -> anyhow::Result<()> {
+        self.upgrade_wraps().await?;
+        self.ensure_indexes().await
+    }
+
+    /// Drops older versions of the indexes of `managed_indexes`, then creates the current ones.
+    /// The old version goes first, as MongoDB refuses a second index on the same keys with
+    /// other options. Any other index is left alone, even when its name happens to end with
+    /// a version.
+    pub async fn ensure_indexes(&self) -> anyhow::Result<()> {
+        let indexes = managed_indexes();
+        let existing = self.0.list_collection_names(None).await?;
+
+        let mut collections: Vec<&str> = indexes.iter().map(|(c, _)| *c).collect();
+        collections.dedup();
+        for collection_name in collections {
+            let collection = self.0.collection::<Document>(collection_name);
+            let models: Vec<IndexModel> = indexes
+                .iter()
+                .filter(|(c, _)| *c == collection_name)
+                .map(|(_, model)| model.clone())
+                .collect();
+            let names: Vec<String> = models
+                .iter()
+                .filter_map(|m| m.options.as_ref().and_then(|o| o.name.clone()))
+                .collect();
+
+            // a collection that does not exist yet has no indexes to list
+            if existing.iter().any(|v| v == collection_name) {
+                for name in collection.list_index_names().await? {
+                    if is_superseded_index(&name, &names) {
+                        collection.drop_index(name, None).await?;
+                    }
+                }
+            }
+
+            collection.create_indexes(models, None).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Upgrades every wrap written with an older schema version and returns how many.
+    /// Wraps are also upgraded when they are read, so this may run while serving.
+    pub async fn upgrade_wraps(&self) -> anyhow::Result<u64> {
+        let collection = self.0.collection::<Document>(WRAPS);
+
+        let filter = doc! {"$or": [
+            {"schema_version": {"$exists": false}},
+            {"schema_version": {"$lt": WRAP_SCHEMA_VERSION as i64}},
+        ]};
+        let mut cursor = collection.find(filter, None).await?;
+
+        let mut upgraded = 0;
+        while let Some(mut document) = cursor.try_next().await? {
+            if let Some(upgrade) = schema::upgrade(&mut document)? {
+                let res = collection
+                    .update_one(upgrade.filter, upgrade.update, None)
+                    .await?;
+                upgraded += res.modified_count;
+            }
+        }
+
+        Ok(upgraded)
+    }
+}
+
+/// Indexes managed by `ensure_indexes`, grouped by collection. Bump the version in the name when the keys
+/// or options of an index change, so that `ensure_indexes` replaces it. The collection is without
+/// the index until the new version is built, and a unique index does not enforce uniqueness then.
+fn managed_indexes() -> Vec<(&'static str, IndexModel)> {
+    vec![
+        // owner listing, newest first
+        (
+            WRAPS,
+            index("wraps_owner_id_v1", doc! {"owner_id": 1, "_id": -1}, None),
+        ),
+        // aliases are unique, wraps without one are not indexed
+        (
+            WRAPS,
+            IndexModel::builder()
+                .keys(doc! {"alias": 1})
+                .options(
+                    IndexOptions::builder()
+                        .name("wraps_alias_v1".to_string())
+                        .unique(true)
+                        .partial_filter_expression(doc! {"alias": {"$type": "string"}})
+                        .build(),
+                )
+                .build(),
+        ),
+        // expired wrap purge
+        (
+            WRAPS,
+            index("wraps_expiration_at_v1", doc! {"expiration_at": 1}, None),
+        ),
+        (
+            "api_keys",
+            index("api_keys_owner_id_v1", doc! {"owner_id": 1}, None),
+        ),
+        (
+            "wrap_access_events",
+            index(
+                "wrap_access_events_wrap_id_v1",
+                doc! {"wrap_id": 1, "_id": -1},
+                None,
+            ),
+        ),
+        // windows are useless once reset, so MongoDB removes them
+        (
+            "rate_limits",
+            index(
+                "rate_limits_reset_at_v1",
+                doc! {"reset_at": 1},
+                Some(Duration::ZERO),
+            ),
+        ),
+    ]
+}
+
+fn index(name: &str, keys: Document, expire_after: Option<Duration>) -> IndexModel {
+    let options = IndexOptions::builder()
+        .name(name.to_string())
+        .expire_after(expire_after)
+        .build();
+    IndexModel::builder().keys(keys).options(options).build()
+}
+
+/// Whether `name` is another version of one of the `current` managed index names.
+fn is_superseded_index(name: &str, current: &[String]) -> bool {
+    match index_base_name(name) {
+        Some(base) => {
+            !current.iter().any(|v| v == name)
+                && current.iter().any(|v| index_base_name(v) == Some(base))
+        }
+        None => false,
+    }
+}
+
+/// `wraps_owner_id` for `wraps_owner_id_v1`, `None` without a version suffix.
+fn index_base_name(name: &str) -> Option<&str> {
+    name.rsplit_once(INDEX_VERSION_SEPARATOR)
+        .filter(|(_, v)| !v.is_empty() && v.chars().all(|c| c.is_ascii_digit()))
+        .map(|(base, _)| base)
 }
 
 fn undefined_msg(subject: &str) -> String {
     format!("{} is undefined.", subject)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_only_older_versions_of_managed_indexes() {
+        let current = vec!["wraps_owner_id_v2".to_string(), "_id_".to_string()];
+
+        assert!(is_superseded_index("wraps_owner_id_v1", &current));
+        assert!(!is_superseded_index("wraps_owner_id_v2", &current));
+        // hand-made indexes that merely look versioned are kept
+        assert!(!is_superseded_index("reports_by_day_v3", &current));
+        assert!(!is_superseded_index("wraps_owner_id", &current));
+        assert!(!is_superseded_index("wraps_owner_id_vx", &current));
+        assert!(!is_superseded_index("_id_", &current));
+    }
+}
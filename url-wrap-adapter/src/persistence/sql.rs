@@ -0,0 +1,34 @@
+use sqlx::any::{install_default_drivers, AnyPoolOptions};
+use sqlx::migrate::Migrator;
+use sqlx::AnyPool;
+use std::env;
+use std::sync::Arc;
+
+/// Connection pool for the SQL backend, either SQLite or PostgreSQL depending on `DATABASE_URL`.
+#[derive(Clone)]
+pub struct SqlDb(pub(crate) Arc<AnyPool>);
+
+const URL: &str = "DATABASE_URL";
+const DEFAULT_URL: &str = "sqlite://url_wrap.db?mode=rwc";
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+impl SqlDb {
+    pub async fn new() -> SqlDb {
+        install_default_drivers();
+        let uri = env::var(URL).unwrap_or_else(|_| DEFAULT_URL.to_string());
+
+        let pool = AnyPoolOptions::new()
+            .connect(&uri)
+            .await
+            .expect("Could not connect to SQL database.");
+
+        SqlDb(Arc::new(pool))
+    }
+
+    /// Applies the pending migrations of the `migrations` directory.
+    pub async fn migrate(&self) -> anyhow::Result<()> {
+        MIGRATOR.run(self.0.as_ref()).await?;
+        Ok(())
+    }
+}
@@ -1,18 +1,63 @@
+#[cfg(feature = "mongodb")]
 use crate::persistence::mongodb::Db;
-use std::sync::Arc;
+#[cfg(feature = "sql")]
+use crate::persistence::sql::SqlDb;
+use anyhow::anyhow;
+
+enum Backend {
+    #[cfg(feature = "mongodb")]
+    MongoDB(Db),
+    #[cfg(feature = "sql")]
+    Sql(SqlDb),
+    InMemory,
+}
 
 pub struct HealthCheckRepository {
-    db: Arc<Db>,
+    backend: Backend,
 }
 
 impl HealthCheckRepository {
+    #[cfg(feature = "mongodb")]
     pub fn new(db: Db) -> Self {
-        Self { db: Arc::new(db) }
+        Self {
+            backend: Backend::MongoDB(db),
+        }
+    }
+
+    #[cfg(feature = "sql")]
+    pub fn sql(db: SqlDb) -> Self {
+        Self {
+            backend: Backend::Sql(db),
+        }
+    }
+
+    /// Health check without a database, used by the in-memory backend.
+    pub fn in_memory() -> Self {
+        Self {
+            backend: Backend::InMemory,
+        }
     }
 
     pub async fn check_mongo_db(&self) -> anyhow::Result<()> {
-        let db = self.db.0.clone();
-        let _ = db.list_collections(None, None).await?;
-        Ok(())
+        match &self.backend {
+            #[cfg(feature = "mongodb")]
+            Backend::MongoDB(db) => {
+                let db = db.0.clone();
+                let _ = db.list_collections(None, None).await?;
+                Ok(())
+            }
+            _ => Err(anyhow!("MongoDB is not configured.")),
+        }
+    }
+
+    pub async fn check_sql_db(&self) -> anyhow::Result<()> {
+        match &self.backend {
+            #[cfg(feature = "sql")]
+            Backend::Sql(db) => {
+                sqlx::query("SELECT 1").execute(db.0.as_ref()).await?;
+                Ok(())
+            }
+            _ => Err(anyhow!("SQL database is not configured.")),
+        }
     }
 }
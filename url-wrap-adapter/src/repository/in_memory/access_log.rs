@@ -0,0 +1,49 @@
+use crate::model::access_event::AccessEventDocument;
+use crate::repository::in_memory::InMemoryRepositoryImpl;
+use async_trait::async_trait;
+use url_wrap_kernel::model::access_event::stats::{AccessStats, AccessStatsQuery};
+use url_wrap_kernel::model::access_event::{AccessEvent, AccessEventListQuery, NewAccessEvent};
+use url_wrap_kernel::model::page::Page;
+use url_wrap_kernel::repository::access_log::AccessLogRepository;
+
+#[async_trait]
+impl AccessLogRepository for InMemoryRepositoryImpl<AccessEvent> {
+    async fn insert(&self, source: NewAccessEvent) -> anyhow::Result<()> {
+        let event_doc: AccessEventDocument = source.into();
+        self.db
+            .insert_one("wrap_access_events", &event_doc.id, &event_doc)
+    }
+
+    async fn list(&self, query: &AccessEventListQuery) -> anyhow::Result<Page<AccessEvent>> {
+        let wrap_id = query.wrap_id.value.to_string();
+        let cursor = query.cursor.as_ref().map(|v| v.value.to_string());
+
+        let ads = self
+            .db
+            .find_many("wrap_access_events", |ad: &AccessEventDocument| {
+                ad.wrap_id == wrap_id && cursor.as_ref().is_none_or(|cursor| ad.id < *cursor)
+            })?;
+        let events = ads
+            .into_iter()
+            .rev()
+            .take(query.limit as usize + 1)
+            .map(AccessEvent::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Page::new(events, query.limit, |v| &v.id))
+    }
+
+    async fn stats(&self, query: &AccessStatsQuery) -> anyhow::Result<AccessStats> {
+        let wrap_id = query.wrap_id.value.to_string();
+
+        let ads = self
+            .db
+            .find_many("wrap_access_events", |ad: &AccessEventDocument| {
+                ad.wrap_id == wrap_id
+            })?;
+        let events = ads
+            .into_iter()
+            .map(AccessEvent::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(AccessStats::from_events(&events, query))
+    }
+}
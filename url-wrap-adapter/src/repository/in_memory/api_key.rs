@@ -0,0 +1,19 @@
+use crate::model::owner::ApiKeyDocument;
+use crate::repository::in_memory::InMemoryRepositoryImpl;
+use async_trait::async_trait;
+use url_wrap_kernel::model::owner::{ApiKey, Owner};
+use url_wrap_kernel::model::Id;
+use url_wrap_kernel::repository::api_key::ApiKeyRepository;
+
+#[async_trait]
+impl ApiKeyRepository for InMemoryRepositoryImpl<ApiKey> {
+    async fn find_owner_id(&self, api_key: &str) -> anyhow::Result<Option<Id<Owner>>> {
+        match self
+            .db
+            .find_one::<ApiKeyDocument>("api_keys", &ApiKeyDocument::hash(api_key))?
+        {
+            Some(akd) => Ok(Some(akd.owner_id.try_into()?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+pub mod access_log;
+pub mod api_key;
+pub mod owner;
+pub mod rate_limit;
+pub mod wrap;
+
+use crate::persistence::in_memory::InMemoryDb;
+use std::marker::PhantomData;
+
+pub struct InMemoryRepositoryImpl<T> {
+    db: InMemoryDb,
+    _marker: PhantomData<T>,
+}
+
+impl<T> InMemoryRepositoryImpl<T> {
+    pub fn new(db: InMemoryDb) -> Self {
+        Self {
+            db,
+            _marker: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::model::owner::{ApiKeyDocument, OwnerDocument};
+use crate::repository::in_memory::InMemoryRepositoryImpl;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use url_wrap_kernel::model::owner::{NewApiKey, NewOwner, Owner};
+use url_wrap_kernel::model::Id;
+use url_wrap_kernel::repository::owner::OwnerRepository;
+
+#[async_trait]
+impl OwnerRepository for InMemoryRepositoryImpl<Owner> {
+    async fn get(&self, id: &Id<Owner>) -> anyhow::Result<Option<Owner>> {
+        match self
+            .db
+            .find_one::<OwnerDocument>("owners", &id.value.to_string())?
+        {
+            Some(od) => Ok(Some(od.try_into()?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn insert(&self, source: NewOwner, api_key: NewApiKey) -> anyhow::Result<Owner> {
+        let owner_doc: OwnerDocument = source.into();
+        let api_key_doc: ApiKeyDocument = api_key.into();
+        self.db.insert_one("owners", &owner_doc.id, &owner_doc)?;
+        if let Err(e) = self
+            .db
+            .insert_one("api_keys", &api_key_doc.id, &api_key_doc)
+        {
+            self.db.delete_one("owners", &owner_doc.id)?;
+            return Err(e);
+        }
+
+        match self.db.find_one::<OwnerDocument>("owners", &owner_doc.id)? {
+            Some(od) => Ok(od.try_into()?),
+            None => Err(anyhow!("Inserted owner is not found.")),
+        }
+    }
+}
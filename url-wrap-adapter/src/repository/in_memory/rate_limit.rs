@@ -0,0 +1,36 @@
+use crate::model::rate_limit::RateLimitDocument;
+use crate::repository::in_memory::InMemoryRepositoryImpl;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use url_wrap_kernel::model::rate_limit::RateLimit;
+use url_wrap_kernel::repository::rate_limit::RateLimitRepository;
+
+#[async_trait]
+impl RateLimitRepository for InMemoryRepositoryImpl<RateLimit> {
+    async fn hit(&self, key: &str, reset_at: DateTime<Utc>) -> anyhow::Result<RateLimit> {
+        let mut collections = self.db.lock()?;
+        let collection = collections.entry("rate_limits".to_string()).or_default();
+
+        let mut rld = match collection.get(key) {
+            Some(d) => bson::from_document::<RateLimitDocument>(d.clone())?,
+            None => RateLimitDocument::new(key.to_string(), reset_at),
+        };
+        rld.count += 1;
+        collection.insert(key.to_string(), bson::to_document(&rld)?);
+
+        Ok(rld.try_into()?)
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> anyhow::Result<u64> {
+        let mut collections = self.db.lock()?;
+        let collection = match collections.get_mut("rate_limits") {
+            Some(collection) => collection,
+            None => return Ok(0),
+        };
+
+        let now = bson::DateTime::from_chrono(now);
+        let before = collection.len();
+        collection.retain(|_, d| d.get_datetime("reset_at").is_ok_and(|r| *r > now));
+        Ok((before - collection.len()) as u64)
+    }
+}
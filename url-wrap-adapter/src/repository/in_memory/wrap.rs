@@ -0,0 +1,563 @@
+use crate::model::access_event::AccessEventDocument;
+use crate::model::wrap::credential::WrapCredentialDocument;
+use crate::model::wrap::{PasswordMatch, WrapDocument, WrapDocumentUpdate};
+use crate::repository::in_memory::InMemoryRepositoryImpl;
+use crate::repository::infrastructure_error;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use url_wrap_kernel::model::page::Page;
+use url_wrap_kernel::model::wrap::alias::WrapAlias;
+use url_wrap_kernel::model::wrap::credential::{
+    NewWrapCredential, VerifiedWrap, WrapCredential, MAX_CREDENTIALS_PER_WRAP,
+};
+use url_wrap_kernel::model::wrap::error::WrapError;
+use url_wrap_kernel::model::wrap::query::WrapListQuery;
+use url_wrap_kernel::model::wrap::{NewWrap, Wrap, WrapUpdate};
+use url_wrap_kernel::model::Id;
+use url_wrap_kernel::repository::wrap::WrapRepository;
+
+#[async_trait]
+impl WrapRepository for InMemoryRepositoryImpl<Wrap> {
+    async fn get(&self, id: &Id<Wrap>) -> Result<Option<Wrap>, WrapError> {
+        match self
+            .db
+            .find_one::<WrapDocument>("wraps", &id.value.to_string())
+            .map_err(infrastructure_error)?
+        {
+            Some(wd) => Ok(Some(wd.try_into()?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_managed(&self, id: &Id<Wrap>, management_token: &str) -> Result<Wrap, WrapError> {
+        match self
+            .db
+            .find_one::<WrapDocument>("wraps", &id.value.to_string())
+            .map_err(infrastructure_error)?
+        {
+            Some(wd) => {
+                wd.verify_management_token(management_token)?;
+                wd.try_into()
+            }
+            None => Err(WrapError::NotFound),
+        }
+    }
+
+    async fn insert(&self, source: NewWrap) -> Result<Wrap, WrapError> {
+        let wrap_doc = WrapDocument::try_from(source).map_err(WrapError::Internal)?;
+        let inserted = match &wrap_doc.alias {
+            Some(alias) => self
+                .db
+                .insert_unique("wraps", &wrap_doc.id, &wrap_doc, |wd: &WrapDocument| {
+                    wd.alias.as_ref() == Some(alias)
+                })
+                .map_err(infrastructure_error)?,
+            None => {
+                self.db
+                    .insert_one("wraps", &wrap_doc.id, &wrap_doc)
+                    .map_err(infrastructure_error)?;
+                true
+            }
+        };
+        if !inserted {
+            return Err(WrapError::AliasTaken);
+        }
+
+        match self
+            .db
+            .find_one::<WrapDocument>("wraps", &wrap_doc.id)
+            .map_err(infrastructure_error)?
+        {
+            Some(wd) => Ok(wd.try_into()?),
+            None => Err(WrapError::NotFound),
+        }
+    }
+
+    async fn resolve_alias(&self, alias: &WrapAlias) -> Result<Option<Id<Wrap>>, WrapError> {
+        let wds = self
+            .db
+            .find_many("wraps", |wd: &WrapDocument| {
+                wd.alias.as_deref() == Some(alias.as_str())
+            })
+            .map_err(infrastructure_error)?;
+        match wds.into_iter().next() {
+            Some(wd) => Ok(Some(Id::try_from(wd.id).map_err(WrapError::Corrupted)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn find(
+        &self,
+        id: &Id<Wrap>,
+        password: &str,
+        credential_id: Option<&Id<WrapCredential>>,
+    ) -> Result<VerifiedWrap, WrapError> {
+        let credential_id = credential_id.map(|v| v.value.to_string());
+        let now = Utc::now();
+        let id = id.value.to_string();
+
+        let wd = match self
+            .db
+            .find_one::<WrapDocument>("wraps", &id)
+            .map_err(infrastructure_error)?
+        {
+            Some(wd) => wd,
+            None => return Err(WrapError::NotFound),
+        };
+
+        if let Some(locked) = wd.active_lock(now) {
+            return Err(locked);
+        }
+
+        // the lock is checked again under the store lock, so a failure or success
+        // racing with the failure that locked the wrap does not get through
+        let matched = match wd.verify_password(password, credential_id.as_deref(), now) {
+            Ok(matched) => matched,
+            Err(err) => {
+                let mut locked = None;
+                let updated = self
+                    .db
+                    .update_one("wraps", &id, |wd: &mut WrapDocument| {
+                        if let Some(active) = wd.active_lock(now) {
+                            locked = Some(active);
+                            return;
+                        }
+                        let (failed_attempts, locked_until) = wd.after_failure(now);
+                        wd.failed_attempts = failed_attempts;
+                        wd.locked_until = locked_until.map(bson::DateTime::from_chrono);
+                        locked =
+                            locked_until.map(|locked_until| WrapError::Locked { locked_until });
+                    })
+                    .map_err(infrastructure_error)?;
+
+                return match updated {
+                    Some(_) => Err(locked.unwrap_or(err)),
+                    None => Err(WrapError::NotFound),
+                };
+            }
+        };
+
+        let rehashed_password = match matched {
+            PasswordMatch::Password => wd.rehash_password(password).map_err(WrapError::Internal)?,
+            _ => None,
+        };
+        let mut locked = None;
+        let mut used = true;
+        let updated = self
+            .db
+            .update_one("wraps", &id, |stored: &mut WrapDocument| {
+                if let Some(active) = stored.active_lock(now) {
+                    locked = Some(active);
+                    return;
+                }
+                match &matched {
+                    PasswordMatch::Password => {}
+                    PasswordMatch::Totp(step) => used = stored.use_totp_step(*step),
+                    PasswordMatch::Credential(credential_id) => {
+                        used = stored.use_credential(credential_id, now)
+                    }
+                }
+                stored.failed_attempts = 0;
+                stored.locked_until = None;
+                // unless the password was changed meanwhile
+                if let Some(rehashed_password) = rehashed_password {
+                    if stored.password == wd.password {
+                        stored.password = rehashed_password;
+                    }
+                }
+            })
+            .map_err(infrastructure_error)?;
+
+        if let Some(locked) = locked {
+            return Err(locked);
+        }
+        match updated {
+            // replayed, revoked or used up since it was verified
+            Some(_) if !used => Err(WrapError::InvalidCredentials),
+            Some(wd) => Ok(VerifiedWrap::new(
+                wd.open(password)?,
+                matched.credential_id()?,
+            )),
+            None => Err(WrapError::NotFound),
+        }
+    }
+
+    async fn consume_view(&self, id: &Id<Wrap>) -> Result<Wrap, WrapError> {
+        let mut consumed = false;
+        let updated = self
+            .db
+            .update_one(
+                "wraps",
+                &id.value.to_string(),
+                |wd: &mut WrapDocument| match wd.remaining_views {
+                    Some(remaining_views) if remaining_views > 0 => {
+                        wd.remaining_views = Some(remaining_views - 1)
+                    }
+                    _ => consumed = true,
+                },
+            )
+            .map_err(infrastructure_error)?;
+
+        match updated {
+            Some(_) if consumed => Err(WrapError::Consumed),
+            Some(wd) => wd.try_into(),
+            None => Err(WrapError::NotFound),
+        }
+    }
+
+    async fn update(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+        source: WrapUpdate,
+    ) -> Result<Wrap, WrapError> {
+        let id = id.value.to_string();
+
+        let wd = match self
+            .db
+            .find_one::<WrapDocument>("wraps", &id)
+            .map_err(infrastructure_error)?
+        {
+            Some(wd) => wd,
+            None => return Err(WrapError::NotFound),
+        };
+        wd.verify_management_token(management_token)?;
+
+        let update = WrapDocumentUpdate::new(source, wd.redirect_url_kdf.is_some())?;
+        match self
+            .db
+            .update_one("wraps", &id, |wd: &mut WrapDocument| update.apply(wd))
+            .map_err(infrastructure_error)?
+        {
+            Some(wd) => wd.try_into(),
+            None => Err(WrapError::NotFound),
+        }
+    }
+
+    async fn delete(&self, id: &Id<Wrap>, management_token: &str) -> Result<(), WrapError> {
+        let id = id.value.to_string();
+
+        match self
+            .db
+            .find_one::<WrapDocument>("wraps", &id)
+            .map_err(infrastructure_error)?
+        {
+            Some(wd) => wd.verify_management_token(management_token)?,
+            None => return Err(WrapError::NotFound),
+        }
+
+        if self
+            .db
+            .delete_one("wraps", &id)
+            .map_err(infrastructure_error)?
+        {
+            Ok(())
+        } else {
+            Err(WrapError::NotFound)
+        }
+    }
+
+    async fn list_credentials(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+    ) -> Result<Vec<WrapCredential>, WrapError> {
+        let wd = match self
+            .db
+            .find_one::<WrapDocument>("wraps", &id.value.to_string())
+            .map_err(infrastructure_error)?
+        {
+            Some(wd) => wd,
+            None => return Err(WrapError::NotFound),
+        };
+        wd.verify_management_token(management_token)?;
+
+        let credentials = wd
+            .credentials
+            .into_iter()
+            .map(WrapCredential::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(WrapError::Corrupted)?;
+        Ok(credentials)
+    }
+
+    async fn add_credential(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+        source: NewWrapCredential,
+    ) -> Result<WrapCredential, WrapError> {
+        let id = id.value.to_string();
+
+        match self
+            .db
+            .find_one::<WrapDocument>("wraps", &id)
+            .map_err(infrastructure_error)?
+        {
+            Some(wd) => wd.verify_management_token(management_token)?,
+            None => return Err(WrapError::NotFound),
+        }
+
+        let credential_id = source.id.value.to_string();
+        let mut credential =
+            Some(WrapCredentialDocument::try_from(source).map_err(WrapError::Internal)?);
+        let updated = self
+            .db
+            .update_one("wraps", &id, |wd: &mut WrapDocument| {
+                if wd.credentials.len() < MAX_CREDENTIALS_PER_WRAP {
+                    wd.credentials.extend(credential.take());
+                }
+            })
+            .map_err(infrastructure_error)?;
+        if credential.is_some() {
+            return Err(WrapError::InvalidInput(format!(
+                "A wrap holds at most {} credentials.",
+                MAX_CREDENTIALS_PER_WRAP
+            )));
+        }
+
+        let credential = updated
+            .and_then(|wd| wd.credentials.into_iter().find(|v| v.id == credential_id))
+            .ok_or(WrapError::NotFound)?;
+        credential.try_into().map_err(WrapError::Corrupted)
+    }
+
+    async fn revoke_credential(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+        credential_id: &Id<WrapCredential>,
+    ) -> Result<(), WrapError> {
+        let id = id.value.to_string();
+        let credential_id = credential_id.value.to_string();
+
+        match self
+            .db
+            .find_one::<WrapDocument>("wraps", &id)
+            .map_err(infrastructure_error)?
+        {
+            Some(wd) => wd.verify_management_token(management_token)?,
+            None => return Err(WrapError::NotFound),
+        }
+
+        let mut revoked = false;
+        self.db
+            .update_one("wraps", &id, |wd: &mut WrapDocument| {
+                let before = wd.credentials.len();
+                wd.credentials.retain(|v| v.id != credential_id);
+                revoked = wd.credentials.len() < before;
+            })
+            .map_err(infrastructure_error)?;
+        if revoked {
+            Ok(())
+        } else {
+            Err(WrapError::NotFound)
+        }
+    }
+
+    async fn list(&self, query: &WrapListQuery) -> Result<Page<Wrap>, WrapError> {
+        let owner_id = query.owner_id.value.to_string();
+        let cursor = query.cursor.as_ref().map(|v| v.value.to_string());
+
+        let wds = self
+            .db
+            .find_many("wraps", |wd: &WrapDocument| {
+                wd.owner_id.as_deref() == Some(owner_id.as_str())
+                    && cursor.as_ref().is_none_or(|cursor| wd.id < *cursor)
+                    && query.matches(wd.expiration_at.map(bson::DateTime::to_chrono), &wd.comment)
+            })
+            .map_err(infrastructure_error)?;
+        // newest first, like the database backends
+        let wraps = wds
+            .into_iter()
+            .rev()
+            .take(query.limit as usize + 1)
+            .map(Wrap::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Page::new(wraps, query.limit, |v| &v.id))
+    }
+
+    async fn delete_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, WrapError> {
+        let ids: HashSet<String> = self
+            .db
+            .find_many("wraps", |wd: &WrapDocument| {
+                wd.expiration_at
+                    .is_some_and(|v| v.to_chrono() <= expired_before)
+            })
+            .map_err(infrastructure_error)?
+            .into_iter()
+            .map(|wd| wd.id)
+            .collect();
+
+        // the access log goes first, so no event outlives its wrap
+        self.db
+            .delete_many("wrap_access_events", |ad: &AccessEventDocument| {
+                ids.contains(&ad.wrap_id)
+            })
+            .map_err(infrastructure_error)?;
+        let deleted = self
+            .db
+            .delete_many("wraps", |wd: &WrapDocument| ids.contains(&wd.id))
+            .map_err(infrastructure_error)?;
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::in_memory::InMemoryDb;
+    use crate::testing::{new_wrap, outdated_hash, PASSWORD};
+    use chrono::Duration;
+    use url_wrap_kernel::model::access_event::{AccessOutcome, NewAccessEvent};
+    use url_wrap_kernel::model::wrap::ManagementToken;
+
+    async fn wrap_with_outdated_hash(repository: &InMemoryRepositoryImpl<Wrap>) -> Id<Wrap> {
+        let wrap = repository
+            .insert(new_wrap(&ManagementToken::gen()))
+            .await
+            .unwrap();
+        repository
+            .db
+            .update_one(
+                "wraps",
+                &wrap.id.value.to_string(),
+                |wd: &mut WrapDocument| wd.password = outdated_hash(),
+            )
+            .unwrap();
+        wrap.id
+    }
+
+    fn stored_password(repository: &InMemoryRepositoryImpl<Wrap>, id: &Id<Wrap>) -> String {
+        repository
+            .db
+            .find_one::<WrapDocument>("wraps", &id.value.to_string())
+            .unwrap()
+            .unwrap()
+            .password
+    }
+
+    #[tokio::test]
+    async fn outdated_hash_is_rehashed_after_success() {
+        let repository = InMemoryRepositoryImpl::new(InMemoryDb::new());
+        let id = wrap_with_outdated_hash(&repository).await;
+
+        repository.find(&id, PASSWORD, None).await.unwrap();
+
+        let rehashed = stored_password(&repository, &id);
+        assert!(
+            rehashed.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"),
+            "{}",
+            rehashed
+        );
+        // and the new hash opens the wrap as well
+        repository.find(&id, PASSWORD, None).await.unwrap();
+        assert_eq!(stored_password(&repository, &id), rehashed);
+    }
+
+    #[tokio::test]
+    async fn outdated_hash_is_kept_after_failure() {
+        let repository = InMemoryRepositoryImpl::new(InMemoryDb::new());
+        let id = wrap_with_outdated_hash(&repository).await;
+        let outdated = stored_password(&repository, &id);
+
+        let res = repository.find(&id, "wrong", None).await;
+
+        assert!(matches!(res, Err(WrapError::InvalidCredentials)));
+        assert_eq!(stored_password(&repository, &id), outdated);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_failures_cannot_pass_the_lock() {
+        let repository = InMemoryRepositoryImpl::new(InMemoryDb::new());
+        let wrap = repository
+            .insert(new_wrap(&ManagementToken::gen()))
+            .await
+            .unwrap();
+
+        let guesses = (0..12).map(|_| repository.find(&wrap.id, "wrong", None));
+        let results = futures::future::join_all(guesses).await;
+
+        // the third failure locks the wrap, so only two are reported as wrong
+        let wrong = results
+            .iter()
+            .filter(|v| matches!(v, Err(WrapError::InvalidCredentials)))
+            .count();
+        let locked = results
+            .iter()
+            .filter(|v| matches!(v, Err(WrapError::Locked { .. })))
+            .count();
+        assert_eq!((wrong, locked), (2, 10));
+        let stored = repository
+            .db
+            .find_one::<WrapDocument>("wraps", &wrap.id.value.to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.failed_attempts, 3);
+
+        let res = repository.find(&wrap.id, PASSWORD, None).await;
+        assert!(matches!(res, Err(WrapError::Locked { .. })));
+    }
+
+    #[tokio::test]
+    async fn expired_wraps_are_deleted_with_their_access_events() {
+        let repository = InMemoryRepositoryImpl::new(InMemoryDb::new());
+        let expired = repository
+            .insert(new_wrap(&ManagementToken::gen()))
+            .await
+            .unwrap();
+        let kept = repository
+            .insert(new_wrap(&ManagementToken::gen()))
+            .await
+            .unwrap();
+        let now = Utc::now();
+        repository
+            .db
+            .update_one(
+                "wraps",
+                &expired.id.value.to_string(),
+                |wd: &mut WrapDocument| {
+                    wd.expiration_at = Some(bson::DateTime::from_chrono(now - Duration::hours(2)))
+                },
+            )
+            .unwrap();
+        for wrap in [&expired, &kept] {
+            let event = NewAccessEvent::new(
+                Id::gen(),
+                wrap.id.value.to_string(),
+                AccessOutcome::Success,
+                None,
+                None,
+                None,
+                now,
+            );
+            let ad = AccessEventDocument::from(event);
+            repository
+                .db
+                .insert_one("wrap_access_events", &ad.id, &ad)
+                .unwrap();
+        }
+
+        // the grace period has not passed yet
+        let deleted = repository
+            .delete_expired(now - Duration::hours(3))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 0);
+
+        let deleted = repository
+            .delete_expired(now - Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+        assert!(repository.get(&expired.id).await.unwrap().is_none());
+        assert!(repository.get(&kept.id).await.unwrap().is_some());
+        let remaining: Vec<AccessEventDocument> = repository
+            .db
+            .find_many("wrap_access_events", |_: &AccessEventDocument| true)
+            .unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].wrap_id, kept.id.value.to_string());
+    }
+}
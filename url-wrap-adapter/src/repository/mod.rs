@@ -1,19 +1,13 @@
 pub mod health_check;
-pub mod wrap;
+pub mod in_memory;
+#[cfg(feature = "mongodb")]
+pub mod mongodb;
+#[cfg(feature = "sql")]
+pub mod sql;
 
-use crate::persistence::mongodb::Db;
-use std::marker::PhantomData;
+use url_wrap_kernel::model::wrap::error::WrapError;
 
-pub struct MongoDBRepositoryImpl<T> {
-    db: Db,
-    _marker: PhantomData<T>,
-}
-
-impl<T> MongoDBRepositoryImpl<T> {
-    pub fn new(db: Db) -> Self {
-        Self {
-            db,
-            _marker: PhantomData,
-        }
-    }
+/// Reports a failure of the database or store, the only errors that are worth retrying.
+pub(crate) fn infrastructure_error<E: Into<anyhow::Error>>(err: E) -> WrapError {
+    WrapError::Infrastructure(err.into())
 }
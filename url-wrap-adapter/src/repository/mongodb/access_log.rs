@@ -0,0 +1,159 @@
+use crate::model::access_event::AccessEventDocument;
+use crate::repository::mongodb::MongoDBRepositoryImpl;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bson::{doc, Document};
+use futures::TryStreamExt;
+use mongodb::options::FindOptions;
+use serde::Deserialize;
+use url_wrap_kernel::model::access_event::stats::{
+    AccessBucket, AccessStats, AccessStatsQuery, Granularity,
+};
+use url_wrap_kernel::model::access_event::{AccessEvent, AccessEventListQuery, NewAccessEvent};
+use url_wrap_kernel::model::page::Page;
+use url_wrap_kernel::repository::access_log::AccessLogRepository;
+
+/// Output of the `$facet` stage in `stats`.
+#[derive(Deserialize)]
+struct StatsFacets {
+    summary: Vec<SummaryRow>,
+    unique: Vec<UniqueRow>,
+    hourly: Vec<BucketRow>,
+    daily: Vec<BucketRow>,
+}
+
+#[derive(Deserialize)]
+struct SummaryRow {
+    total_opens: i64,
+    failed_attempts: i64,
+    first_access_at: bson::DateTime,
+    last_access_at: bson::DateTime,
+}
+
+#[derive(Deserialize)]
+struct UniqueRow {
+    count: i64,
+}
+
+#[derive(Deserialize)]
+struct BucketRow {
+    #[serde(rename = "_id")]
+    start: bson::DateTime,
+    opens: i64,
+    failures: i64,
+}
+
+impl From<BucketRow> for AccessBucket {
+    fn from(row: BucketRow) -> Self {
+        AccessBucket::new(row.start.to_chrono(), row.opens as u64, row.failures as u64)
+    }
+}
+
+/// Counts successful opens and failed attempts per bucket of `granularity` since `since`.
+fn bucket_pipeline(granularity: Granularity, since: bson::DateTime) -> Vec<Document> {
+    vec![
+        doc! {"$match": {"occurred_at": {"$gte": since}}},
+        doc! {"$group": {
+            // subtracting milliseconds from a date yields a date
+            "_id": {"$subtract": [
+                "$occurred_at",
+                {"$mod": [{"$toLong": "$occurred_at"}, granularity.millis()]},
+            ]},
+            "opens": {"$sum": {"$cond": [{"$eq": ["$outcome", "success"]}, 1, 0]}},
+            "failures": {"$sum": {"$cond": [{"$eq": ["$outcome", "success"]}, 0, 1]}},
+        }},
+        doc! {"$sort": {"_id": 1}},
+    ]
+}
+
+#[async_trait]
+impl AccessLogRepository for MongoDBRepositoryImpl<AccessEvent> {
+    async fn insert(&self, source: NewAccessEvent) -> anyhow::Result<()> {
+        let event_doc: AccessEventDocument = source.into();
+
+        let collection = self
+            .db
+            .0
+            .collection::<AccessEventDocument>("wrap_access_events");
+        collection.insert_one(event_doc, None).await?;
+        Ok(())
+    }
+
+    async fn list(&self, query: &AccessEventListQuery) -> anyhow::Result<Page<AccessEvent>> {
+        let collection = self
+            .db
+            .0
+            .collection::<AccessEventDocument>("wrap_access_events");
+
+        let mut filter = doc! {"wrap_id": query.wrap_id.value.to_string()};
+        if let Some(cursor) = &query.cursor {
+            filter.insert("_id", doc! {"$lt": cursor.value.to_string()});
+        }
+
+        let options = FindOptions::builder()
+            .sort(doc! {"_id": -1})
+            .limit(query.limit as i64 + 1)
+            .build();
+        let ads: Vec<AccessEventDocument> = collection
+            .find(filter, options)
+            .await?
+            .try_collect()
+            .await?;
+
+        let events = ads
+            .into_iter()
+            .map(AccessEvent::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Page::new(events, query.limit, |v| &v.id))
+    }
+
+    async fn stats(&self, query: &AccessStatsQuery) -> anyhow::Result<AccessStats> {
+        let collection = self.db.0.collection::<Document>("wrap_access_events");
+
+        let pipeline = vec![
+            doc! {"$match": {"wrap_id": query.wrap_id.value.to_string()}},
+            doc! {"$facet": {
+                "summary": [{"$group": {
+                    "_id": null,
+                    "total_opens": {"$sum": {"$cond": [{"$eq": ["$outcome", "success"]}, 1, 0]}},
+                    "failed_attempts": {
+                        "$sum": {"$cond": [{"$eq": ["$outcome", "success"]}, 0, 1]}
+                    },
+                    "first_access_at": {"$min": "$occurred_at"},
+                    "last_access_at": {"$max": "$occurred_at"},
+                }}],
+                "unique": [
+                    {"$match": {"outcome": "success", "client_ip": {"$ne": null}}},
+                    {"$group": {"_id": "$client_ip"}},
+                    {"$count": "count"},
+                ],
+                "hourly": bucket_pipeline(
+                    Granularity::Hour,
+                    bson::DateTime::from_chrono(query.hourly_since),
+                ),
+                "daily": bucket_pipeline(
+                    Granularity::Day,
+                    bson::DateTime::from_chrono(query.daily_since),
+                ),
+            }},
+        ];
+        let document = collection
+            .aggregate(pipeline, None)
+            .await?
+            .try_next()
+            .await?
+            .ok_or_else(|| anyhow!("`$facet` returned no document."))?;
+        let facets: StatsFacets = bson::from_document(document)?;
+
+        let summary = facets.summary.into_iter().next();
+        Ok(AccessStats::new(
+            summary.as_ref().map_or(0, |v| v.total_opens as u64),
+            facets.unique.first().map_or(0, |v| v.count as u64),
+            summary.as_ref().map_or(0, |v| v.failed_attempts as u64),
+            summary.as_ref().map(|v| v.first_access_at.to_chrono()),
+            summary.as_ref().map(|v| v.last_access_at.to_chrono()),
+            facets.hourly.into_iter().map(AccessBucket::from).collect(),
+            facets.daily.into_iter().map(AccessBucket::from).collect(),
+        ))
+    }
+}
@@ -0,0 +1,20 @@
+use crate::model::owner::ApiKeyDocument;
+use crate::repository::mongodb::MongoDBRepositoryImpl;
+use async_trait::async_trait;
+use bson::doc;
+use url_wrap_kernel::model::owner::{ApiKey, Owner};
+use url_wrap_kernel::model::Id;
+use url_wrap_kernel::repository::api_key::ApiKeyRepository;
+
+#[async_trait]
+impl ApiKeyRepository for MongoDBRepositoryImpl<ApiKey> {
+    async fn find_owner_id(&self, api_key: &str) -> anyhow::Result<Option<Id<Owner>>> {
+        let collection = self.db.0.collection::<ApiKeyDocument>("api_keys");
+
+        let filter = doc! {"_id": ApiKeyDocument::hash(api_key)};
+        match collection.find_one(filter, None).await? {
+            Some(akd) => Ok(Some(akd.owner_id.try_into()?)),
+            None => Ok(None),
+        }
+    }
+}
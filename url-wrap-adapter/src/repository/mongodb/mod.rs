@@ -0,0 +1,22 @@
+pub mod access_log;
+pub mod api_key;
+pub mod owner;
+pub mod rate_limit;
+pub mod wrap;
+
+use crate::persistence::mongodb::Db;
+use std::marker::PhantomData;
+
+pub struct MongoDBRepositoryImpl<T> {
+    db: Db,
+    _marker: PhantomData<T>,
+}
+
+impl<T> MongoDBRepositoryImpl<T> {
+    pub fn new(db: Db) -> Self {
+        Self {
+            db,
+            _marker: PhantomData,
+        }
+    }
+}
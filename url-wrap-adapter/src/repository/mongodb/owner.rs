@@ -0,0 +1,47 @@
+use crate::model::owner::{ApiKeyDocument, OwnerDocument};
+use crate::repository::mongodb::MongoDBRepositoryImpl;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bson::doc;
+use url_wrap_kernel::model::owner::{NewApiKey, NewOwner, Owner};
+use url_wrap_kernel::model::Id;
+use url_wrap_kernel::repository::owner::OwnerRepository;
+
+#[async_trait]
+impl OwnerRepository for MongoDBRepositoryImpl<Owner> {
+    async fn get(&self, id: &Id<Owner>) -> anyhow::Result<Option<Owner>> {
+        let collection = self.db.0.collection::<OwnerDocument>("owners");
+
+        let filter = doc! {"_id": id.value.to_string()};
+        match collection.find_one(filter, None).await? {
+            Some(od) => Ok(Some(od.try_into()?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn insert(&self, source: NewOwner, api_key: NewApiKey) -> anyhow::Result<Owner> {
+        let owner_doc: OwnerDocument = source.into();
+        let api_key_doc: ApiKeyDocument = api_key.into();
+
+        let collection = self.db.0.collection::<OwnerDocument>("owners");
+        let insert_one_result = collection.insert_one(owner_doc, None).await?;
+
+        let id = insert_one_result
+            .inserted_id
+            .as_str()
+            .ok_or(anyhow!("MongoDB `_id` is None."))?;
+
+        // standalone servers have no multi-document transactions, so the owner is removed again
+        let api_keys = self.db.0.collection::<ApiKeyDocument>("api_keys");
+        if let Err(e) = api_keys.insert_one(api_key_doc, None).await {
+            collection.delete_one(doc! {"_id": id}, None).await?;
+            return Err(e.into());
+        }
+
+        let filter = doc! {"_id": id};
+        match collection.find_one(filter, None).await? {
+            Some(od) => Ok(od.try_into()?),
+            None => Err(anyhow!("Inserted owner is not found.")),
+        }
+    }
+}
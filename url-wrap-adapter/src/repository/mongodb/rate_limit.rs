@@ -0,0 +1,47 @@
+use crate::model::rate_limit::RateLimitDocument;
+use crate::repository::mongodb::MongoDBRepositoryImpl;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bson::doc;
+use chrono::{DateTime, Utc};
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument};
+use url_wrap_kernel::model::rate_limit::RateLimit;
+use url_wrap_kernel::repository::rate_limit::RateLimitRepository;
+
+#[async_trait]
+impl RateLimitRepository for MongoDBRepositoryImpl<RateLimit> {
+    async fn hit(&self, key: &str, reset_at: DateTime<Utc>) -> anyhow::Result<RateLimit> {
+        let collection = self.db.0.collection::<RateLimitDocument>("rate_limits");
+
+        let filter = doc! {"_id": key};
+        let update = doc! {
+            "$inc": {"count": 1i64},
+            "$setOnInsert": {"reset_at": bson::DateTime::from_chrono(reset_at)},
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        match collection
+            .find_one_and_update(filter, update, options)
+            .await?
+        {
+            Some(rld) => Ok(rld.try_into()?),
+            None => Err(anyhow!("notting rate limit.")),
+        }
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> anyhow::Result<u64> {
+        // the TTL index removes them as well, this only catches up with its monitor
+        let res = self
+            .db
+            .0
+            .collection::<RateLimitDocument>("rate_limits")
+            .delete_many(
+                doc! {"reset_at": {"$lte": bson::DateTime::from_chrono(now)}},
+                None,
+            )
+            .await?;
+        Ok(res.deleted_count)
+    }
+}
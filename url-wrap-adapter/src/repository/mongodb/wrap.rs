@@ -0,0 +1,584 @@
+use crate::model::wrap::credential::WrapCredentialDocument;
+use crate::model::wrap::schema;
+use crate::model::wrap::{PasswordMatch, WrapDocument, WrapDocumentUpdate};
+use crate::repository::infrastructure_error;
+use crate::repository::mongodb::MongoDBRepositoryImpl;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bson::{doc, Bson, Document};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::options::{FindOneAndUpdateOptions, FindOneOptions, FindOptions, ReturnDocument};
+use mongodb::Collection;
+use url_wrap_kernel::model::page::Page;
+use url_wrap_kernel::model::wrap::alias::WrapAlias;
+use url_wrap_kernel::model::wrap::credential::{
+    NewWrapCredential, VerifiedWrap, WrapCredential, MAX_CREDENTIALS_PER_WRAP,
+};
+use url_wrap_kernel::model::wrap::error::WrapError;
+use url_wrap_kernel::model::wrap::query::{WrapListQuery, WrapStatus};
+use url_wrap_kernel::model::wrap::{NewWrap, Wrap, WrapUpdate};
+use url_wrap_kernel::model::Id;
+use url_wrap_kernel::repository::wrap::WrapRepository;
+
+impl MongoDBRepositoryImpl<Wrap> {
+    /// Wraps are read as raw documents, so that older schema versions can be upgraded first.
+    fn collection(&self) -> Collection<Document> {
+        self.db.0.collection::<Document>("wraps")
+    }
+
+    /// Upgrades a stored wrap to the current schema and deserializes it.
+    /// The upgrade is written back, so every document is upgraded only once.
+    async fn decode(&self, mut document: Document) -> Result<WrapDocument, WrapError> {
+        if let Some(upgrade) = schema::upgrade(&mut document).map_err(WrapError::Corrupted)? {
+            self.collection()
+                .update_one(upgrade.filter, upgrade.update, None)
+                .await
+                .map_err(infrastructure_error)?;
+        }
+        bson::from_document(document).map_err(|e| WrapError::Corrupted(e.into()))
+    }
+
+    async fn find_document(&self, id: &str) -> Result<Option<WrapDocument>, WrapError> {
+        match self
+            .collection()
+            .find_one(doc! {"_id": id}, None)
+            .await
+            .map_err(infrastructure_error)?
+        {
+            Some(document) => Ok(Some(self.decode(document).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Counts a wrong password unless the wrap was locked meanwhile. The count and the lock
+    /// it triggers are stored together, only while the read `failed_attempts` are unchanged.
+    async fn count_failure(
+        &self,
+        mut wd: WrapDocument,
+        now: DateTime<Utc>,
+        err: WrapError,
+    ) -> WrapError {
+        loop {
+            let (failed_attempts, locked_until) = wd.after_failure(now);
+            let read_attempts = match wd.failed_attempts {
+                // documents from before the lockout have no count yet
+                0 => Bson::Document(doc! {"$in": [0, Bson::Null]}),
+                n => Bson::Int64(n as i64),
+            };
+            let filter = doc! {
+                "_id": &wd.id,
+                "failed_attempts": read_attempts,
+                "locked_until": {"$not": {"$gt": bson::DateTime::from_chrono(now)}},
+            };
+            let update = doc! {"$set": {
+                "failed_attempts": failed_attempts as i64,
+                "locked_until": locked_until.map(bson::DateTime::from_chrono),
+            }};
+            match self.collection().update_one(filter, update, None).await {
+                Ok(counted) if counted.matched_count > 0 => {
+                    return match locked_until {
+                        Some(locked_until) => WrapError::Locked { locked_until },
+                        None => err,
+                    };
+                }
+                Ok(_) => {}
+                Err(e) => return infrastructure_error(e),
+            }
+
+            // another failure was counted first, read its count and lock
+            wd = match self.find_document(&wd.id).await {
+                Ok(Some(wd)) => wd,
+                Ok(None) => return WrapError::NotFound,
+                Err(e) => return e,
+            };
+            if let Some(locked) = wd.active_lock(now) {
+                return locked;
+            }
+        }
+    }
+
+    /// Why a write conditional on the wrap not being locked matched no document.
+    async fn refusal(&self, id: &str, now: DateTime<Utc>, otherwise: WrapError) -> WrapError {
+        match self.find_document(id).await {
+            Ok(Some(wd)) => wd.active_lock(now).unwrap_or(otherwise),
+            Ok(None) => WrapError::NotFound,
+            Err(e) => e,
+        }
+    }
+
+    /// Reads an existing wrap after verifying its management token.
+    async fn find_managed(
+        &self,
+        id: &str,
+        management_token: &str,
+    ) -> Result<WrapDocument, WrapError> {
+        let wd = match self
+            .collection()
+            .find_one(doc! {"_id": id}, None)
+            .await
+            .map_err(infrastructure_error)?
+        {
+            Some(document) => self.decode(document).await?,
+            None => return Err(WrapError::NotFound),
+        };
+        wd.verify_management_token(management_token)?;
+        Ok(wd)
+    }
+}
+
+#[async_trait]
+impl WrapRepository for MongoDBRepositoryImpl<Wrap> {
+    async fn get(&self, id: &Id<Wrap>) -> Result<Option<Wrap>, WrapError> {
+        let collection = self.collection();
+
+        let filter = doc! {"_id": id.value.to_string()};
+        match collection
+            .find_one(filter, None)
+            .await
+            .map_err(infrastructure_error)?
+        {
+            Some(document) => Ok(Some(self.decode(document).await?.try_into()?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_managed(&self, id: &Id<Wrap>, management_token: &str) -> Result<Wrap, WrapError> {
+        self.find_managed(&id.value.to_string(), management_token)
+            .await?
+            .try_into()
+    }
+
+    async fn insert(&self, source: NewWrap) -> Result<Wrap, WrapError> {
+        let wrap_doc = WrapDocument::try_from(source).map_err(WrapError::Internal)?;
+
+        let collection = self.collection();
+        let insert_one_result = collection
+            .insert_one(
+                bson::to_document(&wrap_doc).map_err(|e| WrapError::Internal(e.into()))?,
+                None,
+            )
+            .await
+            .map_err(|e| {
+                // ids are random, so only the unique alias index can reject the wrap
+                if is_duplicate_key(&e) && wrap_doc.alias.is_some() {
+                    WrapError::AliasTaken
+                } else {
+                    WrapError::Infrastructure(e.into())
+                }
+            })?;
+
+        let id = insert_one_result
+            .inserted_id
+            .as_str()
+            .ok_or_else(|| infrastructure_error(anyhow!("MongoDB `_id` is None.")))?;
+
+        let filter = doc! {"_id": id};
+        match collection
+            .find_one(filter, None)
+            .await
+            .map_err(infrastructure_error)?
+        {
+            Some(document) => Ok(self.decode(document).await?.try_into()?),
+            None => Err(WrapError::NotFound),
+        }
+    }
+
+    async fn resolve_alias(&self, alias: &WrapAlias) -> Result<Option<Id<Wrap>>, WrapError> {
+        let options = FindOneOptions::builder()
+            .projection(doc! {"_id": 1})
+            .build();
+        let document = self
+            .collection()
+            .find_one(doc! {"alias": alias.as_str()}, options)
+            .await
+            .map_err(infrastructure_error)?;
+        match document {
+            Some(document) => {
+                let id = document
+                    .get_str("_id")
+                    .map_err(|e| WrapError::Corrupted(e.into()))?;
+                Ok(Some(
+                    Id::try_from(id.to_string()).map_err(WrapError::Corrupted)?,
+                ))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn find(
+        &self,
+        id: &Id<Wrap>,
+        password: &str,
+        credential_id: Option<&Id<WrapCredential>>,
+    ) -> Result<VerifiedWrap, WrapError> {
+        let credential_id = credential_id.map(|v| v.value.to_string());
+        let collection = self.collection();
+        let now = Utc::now();
+
+        let wd = match self.find_document(&id.value.to_string()).await? {
+            Some(wd) => wd,
+            None => return Err(WrapError::NotFound),
+        };
+
+        if let Some(locked) = wd.active_lock(now) {
+            return Err(locked);
+        }
+
+        let matched = match wd.verify_password(password, credential_id.as_deref(), now) {
+            Ok(matched) => matched,
+            Err(err) => return Err(self.count_failure(wd, now, err).await),
+        };
+
+        // every write below is conditional on the wrap not being locked meanwhile
+        let unlocked = doc! {"$not": {"$gt": bson::DateTime::from_chrono(now)}};
+        let mut update = doc! {};
+        if wd.failed_attempts > 0 || wd.locked_until.is_some() {
+            update.insert("failed_attempts", 0);
+            update.insert("locked_until", Bson::Null);
+        }
+        match &matched {
+            PasswordMatch::Credential(credential_id) => {
+                // the `$lt` condition makes concurrent authorizations race on the last use
+                let mut matcher = doc! {"id": credential_id};
+                let max_uses = wd
+                    .credentials
+                    .iter()
+                    .find(|v| &v.id == credential_id)
+                    .and_then(|v| v.max_uses);
+                if let Some(max_uses) = max_uses {
+                    matcher.insert("uses", doc! {"$lt": max_uses});
+                }
+                let credential_filter = doc! {
+                    "_id": &wd.id,
+                    "locked_until": unlocked,
+                    "credentials": {"$elemMatch": matcher},
+                };
+
+                let mut credential_update = doc! {"$inc": {"credentials.$.uses": 1}};
+                if !update.is_empty() {
+                    credential_update.insert("$set", update);
+                }
+                let used = collection
+                    .update_one(credential_filter, credential_update, None)
+                    .await
+                    .map_err(infrastructure_error)?;
+                if used.matched_count == 0 {
+                    return Err(self
+                        .refusal(&wd.id, now, WrapError::InvalidCredentials)
+                        .await);
+                }
+            }
+            PasswordMatch::Totp(step) => {
+                // the `$lt` condition lets only one of concurrent replays through
+                let totp_filter = doc! {
+                    "_id": &wd.id,
+                    "locked_until": unlocked,
+                    "$or": [
+                        {"totp_last_step": Bson::Null},
+                        {"totp_last_step": {"$lt": step}},
+                    ],
+                };
+                update.insert("totp_last_step", step);
+                let used = collection
+                    .update_one(totp_filter, doc! {"$set": update}, None)
+                    .await
+                    .map_err(infrastructure_error)?;
+                if used.matched_count == 0 {
+                    return Err(self
+                        .refusal(&wd.id, now, WrapError::InvalidCredentials)
+                        .await);
+                }
+            }
+            PasswordMatch::Password => {
+                if let Some(rehashed_password) =
+                    wd.rehash_password(password).map_err(WrapError::Internal)?
+                {
+                    // unless the password was changed meanwhile
+                    let filter = doc! {"_id": &wd.id, "password": &wd.password};
+                    let rehash = doc! {"$set": {"password": rehashed_password}};
+                    collection
+                        .update_one(filter, rehash, None)
+                        .await
+                        .map_err(infrastructure_error)?;
+                }
+                if update.is_empty() {
+                    // nothing to store, but a racing failure may have locked the wrap
+                    if let Some(locked) = self
+                        .find_document(&wd.id)
+                        .await?
+                        .and_then(|v| v.active_lock(now))
+                    {
+                        return Err(locked);
+                    }
+                } else {
+                    let filter = doc! {"_id": &wd.id, "locked_until": unlocked};
+                    let reset = collection
+                        .update_one(filter, doc! {"$set": update}, None)
+                        .await
+                        .map_err(infrastructure_error)?;
+                    if reset.matched_count == 0 {
+                        return Err(self.refusal(&wd.id, now, WrapError::NotFound).await);
+                    }
+                }
+            }
+        }
+
+        Ok(VerifiedWrap::new(
+            wd.open(password)?,
+            matched.credential_id()?,
+        ))
+    }
+
+    async fn consume_view(&self, id: &Id<Wrap>) -> Result<Wrap, WrapError> {
+        let collection = self.collection();
+
+        // the `$gt` condition makes concurrent authorizations race on the same document
+        let filter = doc! {"_id": id.value.to_string(), "remaining_views": {"$gt": 0}};
+        let update = doc! {"$inc": {"remaining_views": -1}};
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        let updated = collection
+            .find_one_and_update(filter, update, options)
+            .await
+            .map_err(infrastructure_error)?;
+
+        match updated {
+            Some(document) => self.decode(document).await?.try_into(),
+            None => match self.get(id).await? {
+                Some(_) => Err(WrapError::Consumed),
+                None => Err(WrapError::NotFound),
+            },
+        }
+    }
+
+    async fn update(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+        source: WrapUpdate,
+    ) -> Result<Wrap, WrapError> {
+        let collection = self.collection();
+
+        let wd = self
+            .find_managed(&id.value.to_string(), management_token)
+            .await?;
+
+        let filter = doc! {"_id": id.value.to_string()};
+        let update = WrapDocumentUpdate::new(source, wd.redirect_url_kdf.is_some())?;
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        match collection
+            .find_one_and_update(filter, doc! {"$set": update.to_set_document()}, options)
+            .await
+            .map_err(infrastructure_error)?
+        {
+            Some(document) => self.decode(document).await?.try_into(),
+            None => Err(WrapError::NotFound),
+        }
+    }
+
+    async fn delete(&self, id: &Id<Wrap>, management_token: &str) -> Result<(), WrapError> {
+        let collection = self.collection();
+
+        let filter = doc! {"_id": id.value.to_string()};
+        match collection
+            .find_one(filter.clone(), None)
+            .await
+            .map_err(infrastructure_error)?
+        {
+            Some(document) => self
+                .decode(document)
+                .await?
+                .verify_management_token(management_token)?,
+            None => return Err(WrapError::NotFound),
+        }
+
+        let delete_result = collection
+            .delete_one(filter, None)
+            .await
+            .map_err(infrastructure_error)?;
+        if delete_result.deleted_count > 0 {
+            Ok(())
+        } else {
+            Err(WrapError::NotFound)
+        }
+    }
+
+    async fn list_credentials(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+    ) -> Result<Vec<WrapCredential>, WrapError> {
+        let wd = self
+            .find_managed(&id.value.to_string(), management_token)
+            .await?;
+
+        let credentials = wd
+            .credentials
+            .into_iter()
+            .map(WrapCredential::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(WrapError::Corrupted)?;
+        Ok(credentials)
+    }
+
+    async fn add_credential(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+        source: NewWrapCredential,
+    ) -> Result<WrapCredential, WrapError> {
+        let id = id.value.to_string();
+        self.find_managed(&id, management_token).await?;
+
+        let cd = WrapCredentialDocument::try_from(source).map_err(WrapError::Internal)?;
+        // matches only while the array has room for one more credential
+        let filter = doc! {
+            "_id": &id,
+            format!("credentials.{}", MAX_CREDENTIALS_PER_WRAP - 1): {"$exists": false},
+        };
+        let credential = bson::to_bson(&cd).map_err(|e| WrapError::Internal(e.into()))?;
+        let pushed = self
+            .collection()
+            .update_one(filter, doc! {"$push": {"credentials": credential}}, None)
+            .await
+            .map_err(infrastructure_error)?;
+        if pushed.matched_count == 0 {
+            return Err(WrapError::InvalidInput(format!(
+                "A wrap holds at most {} credentials.",
+                MAX_CREDENTIALS_PER_WRAP
+            )));
+        }
+
+        cd.try_into().map_err(WrapError::Corrupted)
+    }
+
+    async fn revoke_credential(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+        credential_id: &Id<WrapCredential>,
+    ) -> Result<(), WrapError> {
+        let id = id.value.to_string();
+        self.find_managed(&id, management_token).await?;
+
+        let update = doc! {"$pull": {"credentials": {"id": credential_id.value.to_string()}}};
+        let pulled = self
+            .collection()
+            .update_one(doc! {"_id": &id}, update, None)
+            .await
+            .map_err(infrastructure_error)?;
+        if pulled.modified_count > 0 {
+            Ok(())
+        } else {
+            Err(WrapError::NotFound)
+        }
+    }
+
+    async fn list(&self, query: &WrapListQuery) -> Result<Page<Wrap>, WrapError> {
+        let collection = self.collection();
+        let now = bson::DateTime::from_chrono(query.now);
+
+        let mut filter = doc! {"owner_id": query.owner_id.value.to_string()};
+        if let Some(cursor) = &query.cursor {
+            filter.insert("_id", doc! {"$lt": cursor.value.to_string()});
+        }
+        match query.status {
+            // `null` also matches documents without the field
+            Some(WrapStatus::Active) => {
+                filter.insert(
+                    "$or",
+                    vec![
+                        doc! {"expiration_at": Bson::Null},
+                        doc! {"expiration_at": {"$gt": now}},
+                    ],
+                );
+            }
+            Some(WrapStatus::Expired) => {
+                filter.insert("expiration_at", doc! {"$lte": now});
+            }
+            None => {}
+        }
+        if let Some(comment) = &query.comment {
+            filter.insert(
+                "comment",
+                doc! {"$regex": escape_regex(comment), "$options": "i"},
+            );
+        }
+
+        let options = FindOptions::builder()
+            .sort(doc! {"_id": -1})
+            .limit(query.limit as i64 + 1)
+            .build();
+        let documents: Vec<Document> = collection
+            .find(filter, options)
+            .await
+            .map_err(infrastructure_error)?
+            .try_collect()
+            .await
+            .map_err(infrastructure_error)?;
+
+        let mut wraps = Vec::with_capacity(documents.len());
+        for document in documents {
+            wraps.push(self.decode(document).await?.try_into()?);
+        }
+        Ok(Page::new(wraps, query.limit, |v| &v.id))
+    }
+
+    async fn delete_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, WrapError> {
+        let collection = self.collection();
+
+        // never-expiring wraps have a `null` expiration and never match `$lte`
+        let filter = doc! {"expiration_at": {"$lte": bson::DateTime::from_chrono(expired_before)}};
+        let options = FindOptions::builder().projection(doc! {"_id": 1}).build();
+        let ids: Vec<Bson> = collection
+            .find(filter, options)
+            .await
+            .map_err(infrastructure_error)?
+            .try_collect::<Vec<Document>>()
+            .await
+            .map_err(infrastructure_error)?
+            .into_iter()
+            .filter_map(|mut d| d.remove("_id"))
+            .collect();
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        // the access log goes first, so no event outlives its wrap
+        self.db
+            .0
+            .collection::<Document>("wrap_access_events")
+            .delete_many(doc! {"wrap_id": {"$in": &ids}}, None)
+            .await
+            .map_err(infrastructure_error)?;
+        let delete_result = collection
+            .delete_many(doc! {"_id": {"$in": ids}}, None)
+            .await
+            .map_err(infrastructure_error)?;
+        Ok(delete_result.deleted_count)
+    }
+}
+
+fn is_duplicate_key(err: &mongodb::error::Error) -> bool {
+    matches!(
+        err.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(e)) if e.code == 11000
+    )
+}
+
+/// Escapes `value` so that `$regex` matches it literally.
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,181 @@
+use crate::model::access_event::AccessEventDocument;
+use crate::repository::sql::SqlRepositoryImpl;
+use async_trait::async_trait;
+use url_wrap_kernel::model::access_event::stats::{
+    AccessBucket, AccessStats, AccessStatsQuery, Granularity,
+};
+use url_wrap_kernel::model::access_event::{AccessEvent, AccessEventListQuery, NewAccessEvent};
+use url_wrap_kernel::model::page::Page;
+use url_wrap_kernel::repository::access_log::AccessLogRepository;
+
+const ACCESS_EVENT_COLUMNS: &str =
+    "id, wrap_id, outcome, credential_id, client_ip, user_agent, occurred_at";
+
+/// Row of the `wrap_access_events` table. Timestamps are unix epoch milliseconds.
+#[derive(sqlx::FromRow)]
+struct AccessEventRow {
+    id: String,
+    wrap_id: String,
+    outcome: String,
+    credential_id: Option<String>,
+    client_ip: Option<String>,
+    user_agent: Option<String>,
+    occurred_at: i64,
+}
+
+impl From<AccessEventRow> for AccessEventDocument {
+    fn from(row: AccessEventRow) -> Self {
+        Self {
+            id: row.id,
+            wrap_id: row.wrap_id,
+            outcome: row.outcome,
+            credential_id: row.credential_id,
+            client_ip: row.client_ip,
+            user_agent: row.user_agent,
+            occurred_at: bson::DateTime::from_millis(row.occurred_at),
+        }
+    }
+}
+
+/// Aggregates over all events of a wrap. `MIN`/`MAX` are `NULL` when there is none.
+#[derive(sqlx::FromRow)]
+struct SummaryRow {
+    total_opens: i64,
+    unique_opens: i64,
+    failed_attempts: i64,
+    first_access_at: Option<i64>,
+    last_access_at: Option<i64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct BucketRow {
+    start: i64,
+    opens: i64,
+    failures: i64,
+}
+
+impl From<BucketRow> for AccessBucket {
+    fn from(row: BucketRow) -> Self {
+        let start = bson::DateTime::from_millis(row.start).to_chrono();
+        AccessBucket::new(start, row.opens as u64, row.failures as u64)
+    }
+}
+
+impl SqlRepositoryImpl<AccessEvent> {
+    async fn buckets(
+        &self,
+        wrap_id: &str,
+        granularity: Granularity,
+        since: i64,
+    ) -> anyhow::Result<Vec<AccessBucket>> {
+        // integer division truncates the timestamps to the start of their bucket
+        let rows = sqlx::query_as::<_, BucketRow>(
+            "SELECT (occurred_at / $1) * $1 AS start, \
+             COALESCE(SUM(CASE WHEN outcome = 'success' THEN 1 ELSE 0 END), 0) AS opens, \
+             COALESCE(SUM(CASE WHEN outcome = 'success' THEN 0 ELSE 1 END), 0) AS failures \
+             FROM wrap_access_events WHERE wrap_id = $2 AND occurred_at >= $3 \
+             GROUP BY (occurred_at / $1) * $1 ORDER BY start",
+        )
+        .bind(granularity.millis())
+        .bind(wrap_id)
+        .bind(since)
+        .fetch_all(self.db.0.as_ref())
+        .await?;
+        Ok(rows.into_iter().map(AccessBucket::from).collect())
+    }
+}
+
+#[async_trait]
+impl AccessLogRepository for SqlRepositoryImpl<AccessEvent> {
+    async fn insert(&self, source: NewAccessEvent) -> anyhow::Result<()> {
+        let ad: AccessEventDocument = source.into();
+
+        sqlx::query(
+            "INSERT INTO wrap_access_events (id, wrap_id, outcome, credential_id, client_ip, \
+             user_agent, occurred_at) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(&ad.id)
+        .bind(&ad.wrap_id)
+        .bind(&ad.outcome)
+        .bind(&ad.credential_id)
+        .bind(&ad.client_ip)
+        .bind(&ad.user_agent)
+        .bind(ad.occurred_at.timestamp_millis())
+        .execute(self.db.0.as_ref())
+        .await?;
+        Ok(())
+    }
+
+    async fn list(&self, query: &AccessEventListQuery) -> anyhow::Result<Page<AccessEvent>> {
+        let sql = match query.cursor {
+            Some(_) => format!(
+                "SELECT {} FROM wrap_access_events WHERE wrap_id = $1 AND id < $2 \
+                 ORDER BY id DESC LIMIT $3",
+                ACCESS_EVENT_COLUMNS
+            ),
+            None => format!(
+                "SELECT {} FROM wrap_access_events WHERE wrap_id = $1 \
+                 ORDER BY id DESC LIMIT $2",
+                ACCESS_EVENT_COLUMNS
+            ),
+        };
+
+        let mut rows =
+            sqlx::query_as::<_, AccessEventRow>(&sql).bind(query.wrap_id.value.to_string());
+        if let Some(cursor) = &query.cursor {
+            rows = rows.bind(cursor.value.to_string());
+        }
+        let rows = rows
+            .bind(query.limit as i64 + 1)
+            .fetch_all(self.db.0.as_ref())
+            .await?;
+
+        let events = rows
+            .into_iter()
+            .map(|row| AccessEvent::try_from(AccessEventDocument::from(row)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Page::new(events, query.limit, |v| &v.id))
+    }
+
+    async fn stats(&self, query: &AccessStatsQuery) -> anyhow::Result<AccessStats> {
+        let wrap_id = query.wrap_id.value.to_string();
+
+        let summary = sqlx::query_as::<_, SummaryRow>(
+            "SELECT \
+             COALESCE(SUM(CASE WHEN outcome = 'success' THEN 1 ELSE 0 END), 0) AS total_opens, \
+             COUNT(DISTINCT CASE WHEN outcome = 'success' THEN client_ip END) AS unique_opens, \
+             COALESCE(SUM(CASE WHEN outcome = 'success' THEN 0 ELSE 1 END), 0) \
+             AS failed_attempts, \
+             MIN(occurred_at) AS first_access_at, MAX(occurred_at) AS last_access_at \
+             FROM wrap_access_events WHERE wrap_id = $1",
+        )
+        .bind(&wrap_id)
+        .fetch_one(self.db.0.as_ref())
+        .await?;
+        let hourly = self
+            .buckets(
+                &wrap_id,
+                Granularity::Hour,
+                query.hourly_since.timestamp_millis(),
+            )
+            .await?;
+        let daily = self
+            .buckets(
+                &wrap_id,
+                Granularity::Day,
+                query.daily_since.timestamp_millis(),
+            )
+            .await?;
+
+        let to_chrono = |millis: i64| bson::DateTime::from_millis(millis).to_chrono();
+        Ok(AccessStats::new(
+            summary.total_opens as u64,
+            summary.unique_opens as u64,
+            summary.failed_attempts as u64,
+            summary.first_access_at.map(to_chrono),
+            summary.last_access_at.map(to_chrono),
+            hourly,
+            daily,
+        ))
+    }
+}
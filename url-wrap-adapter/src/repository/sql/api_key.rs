@@ -0,0 +1,22 @@
+use crate::model::owner::ApiKeyDocument;
+use crate::repository::sql::SqlRepositoryImpl;
+use async_trait::async_trait;
+use url_wrap_kernel::model::owner::{ApiKey, Owner};
+use url_wrap_kernel::model::Id;
+use url_wrap_kernel::repository::api_key::ApiKeyRepository;
+
+#[async_trait]
+impl ApiKeyRepository for SqlRepositoryImpl<ApiKey> {
+    async fn find_owner_id(&self, api_key: &str) -> anyhow::Result<Option<Id<Owner>>> {
+        let owner_id: Option<String> =
+            sqlx::query_scalar("SELECT owner_id FROM api_keys WHERE id = $1")
+                .bind(ApiKeyDocument::hash(api_key))
+                .fetch_optional(self.db.0.as_ref())
+                .await?;
+
+        match owner_id {
+            Some(owner_id) => Ok(Some(owner_id.try_into()?)),
+            None => Ok(None),
+        }
+    }
+}
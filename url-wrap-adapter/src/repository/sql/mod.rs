@@ -0,0 +1,22 @@
+pub mod access_log;
+pub mod api_key;
+pub mod owner;
+pub mod rate_limit;
+pub mod wrap;
+
+use crate::persistence::sql::SqlDb;
+use std::marker::PhantomData;
+
+pub struct SqlRepositoryImpl<T> {
+    db: SqlDb,
+    _marker: PhantomData<T>,
+}
+
+impl<T> SqlRepositoryImpl<T> {
+    pub fn new(db: SqlDb) -> Self {
+        Self {
+            db,
+            _marker: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::model::owner::{ApiKeyDocument, OwnerDocument};
+use crate::repository::sql::SqlRepositoryImpl;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use url_wrap_kernel::model::owner::{NewApiKey, NewOwner, Owner};
+use url_wrap_kernel::model::Id;
+use url_wrap_kernel::repository::owner::OwnerRepository;
+
+/// Row of the `owners` table. Timestamps are unix epoch milliseconds.
+#[derive(sqlx::FromRow)]
+struct OwnerRow {
+    id: String,
+    name: String,
+    created_at: i64,
+}
+
+impl From<OwnerRow> for OwnerDocument {
+    fn from(row: OwnerRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            created_at: bson::DateTime::from_millis(row.created_at),
+        }
+    }
+}
+
+#[async_trait]
+impl OwnerRepository for SqlRepositoryImpl<Owner> {
+    async fn get(&self, id: &Id<Owner>) -> anyhow::Result<Option<Owner>> {
+        let row =
+            sqlx::query_as::<_, OwnerRow>("SELECT id, name, created_at FROM owners WHERE id = $1")
+                .bind(id.value.to_string())
+                .fetch_optional(self.db.0.as_ref())
+                .await?;
+
+        match row {
+            Some(row) => Ok(Some(OwnerDocument::from(row).try_into()?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn insert(&self, source: NewOwner, api_key: NewApiKey) -> anyhow::Result<Owner> {
+        let od: OwnerDocument = source.into();
+        let akd: ApiKeyDocument = api_key.into();
+
+        let mut tx = self.db.0.begin().await?;
+        sqlx::query("INSERT INTO owners (id, name, created_at) VALUES ($1, $2, $3)")
+            .bind(&od.id)
+            .bind(&od.name)
+            .bind(od.created_at.timestamp_millis())
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO api_keys (id, owner_id, created_at) VALUES ($1, $2, $3)")
+            .bind(&akd.id)
+            .bind(&akd.owner_id)
+            .bind(akd.created_at.timestamp_millis())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        let id = od.id.clone().try_into()?;
+        self.get(&id)
+            .await?
+            .ok_or_else(|| anyhow!("Inserted owner is not found."))
+    }
+}
@@ -0,0 +1,43 @@
+use crate::repository::sql::SqlRepositoryImpl;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use url_wrap_kernel::model::rate_limit::RateLimit;
+use url_wrap_kernel::repository::rate_limit::RateLimitRepository;
+
+#[derive(sqlx::FromRow)]
+struct RateLimitRow {
+    id: String,
+    count: i64,
+    reset_at: i64,
+}
+
+#[async_trait]
+impl RateLimitRepository for SqlRepositoryImpl<RateLimit> {
+    async fn hit(&self, key: &str, reset_at: DateTime<Utc>) -> anyhow::Result<RateLimit> {
+        let row = sqlx::query_as::<_, RateLimitRow>(
+            "INSERT INTO rate_limits (id, count, reset_at) VALUES ($1, 1, $2) \
+             ON CONFLICT (id) DO UPDATE SET count = rate_limits.count + 1 \
+             RETURNING id, count, reset_at",
+        )
+        .bind(key)
+        .bind(reset_at.timestamp_millis())
+        .fetch_one(self.db.0.as_ref())
+        .await?;
+
+        let reset_at = Utc
+            .timestamp_millis_opt(row.reset_at)
+            .single()
+            .ok_or_else(|| anyhow!("`reset_at` is invalid timestamp."))?;
+        Ok(RateLimit::new(row.id, row.count as u64, reset_at))
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> anyhow::Result<u64> {
+        // uses the `rate_limits_reset_at` index
+        let res = sqlx::query("DELETE FROM rate_limits WHERE reset_at <= $1")
+            .bind(now.timestamp_millis())
+            .execute(self.db.0.as_ref())
+            .await?;
+        Ok(res.rows_affected())
+    }
+}
@@ -0,0 +1,1001 @@
+use crate::model::wrap::credential::WrapCredentialDocument;
+use crate::model::wrap::schema::WRAP_SCHEMA_VERSION;
+use crate::model::wrap::{PasswordMatch, WrapDocument, WrapDocumentUpdate};
+use crate::repository::infrastructure_error;
+use crate::repository::sql::SqlRepositoryImpl;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use url_wrap_kernel::model::page::Page;
+use url_wrap_kernel::model::wrap::alias::WrapAlias;
+use url_wrap_kernel::model::wrap::credential::{
+    NewWrapCredential, VerifiedWrap, WrapCredential, MAX_CREDENTIALS_PER_WRAP,
+};
+use url_wrap_kernel::model::wrap::error::WrapError;
+use url_wrap_kernel::model::wrap::query::{WrapListQuery, WrapStatus};
+use url_wrap_kernel::model::wrap::{NewWrap, Wrap, WrapUpdate};
+use url_wrap_kernel::model::Id;
+use url_wrap_kernel::repository::wrap::WrapRepository;
+
+const WRAP_COLUMNS: &str = "id, redirect_url, password, auth_type, comment, \
+     expiration_at, created_at, failed_attempts, locked_until, max_views, remaining_views, \
+     management_token, owner_id, alias, totp_secret, totp_last_step, redirect_url_kdf, \
+     payload_kind";
+
+/// Row of the `wraps` table. Timestamps are unix epoch milliseconds.
+#[derive(sqlx::FromRow)]
+struct WrapRow {
+    id: String,
+    redirect_url: String,
+    password: String,
+    auth_type: String,
+    comment: String,
+    expiration_at: Option<i64>,
+    created_at: i64,
+    failed_attempts: i64,
+    locked_until: Option<i64>,
+    max_views: Option<i64>,
+    remaining_views: Option<i64>,
+    management_token: Option<String>,
+    owner_id: Option<String>,
+    alias: Option<String>,
+    totp_secret: Option<String>,
+    totp_last_step: Option<i64>,
+    redirect_url_kdf: Option<String>,
+    payload_kind: String,
+}
+
+impl From<WrapRow> for WrapDocument {
+    fn from(row: WrapRow) -> Self {
+        Self {
+            id: row.id,
+            redirect_url: row.redirect_url,
+            redirect_url_kdf: row.redirect_url_kdf,
+            payload_kind: row.payload_kind,
+            password: row.password,
+            auth_type: row.auth_type,
+            comment: row.comment,
+            expiration_at: row.expiration_at.map(bson::DateTime::from_millis),
+            created_at: bson::DateTime::from_millis(row.created_at),
+            failed_attempts: row.failed_attempts as u32,
+            locked_until: row.locked_until.map(bson::DateTime::from_millis),
+            max_views: row.max_views.map(|v| v as u32),
+            remaining_views: row.remaining_views.map(|v| v as u32),
+            management_token: row.management_token,
+            owner_id: row.owner_id,
+            alias: row.alias,
+            totp_secret: row.totp_secret,
+            totp_last_step: row.totp_last_step,
+            // kept in `wrap_credentials`, loaded only where they are needed
+            credentials: Vec::new(),
+            // the table layout is versioned by the SQL migrations instead
+            schema_version: WRAP_SCHEMA_VERSION,
+        }
+    }
+}
+
+const CREDENTIAL_COLUMNS: &str = "id, name, password, expiration_at, max_uses, uses, created_at";
+
+/// Row of the `wrap_credentials` table. Timestamps are unix epoch milliseconds.
+#[derive(sqlx::FromRow)]
+struct CredentialRow {
+    id: String,
+    name: String,
+    password: String,
+    expiration_at: Option<i64>,
+    max_uses: Option<i64>,
+    uses: i64,
+    created_at: i64,
+}
+
+impl From<CredentialRow> for WrapCredentialDocument {
+    fn from(row: CredentialRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            password: row.password,
+            expiration_at: row.expiration_at.map(bson::DateTime::from_millis),
+            max_uses: row.max_uses.map(|v| v as u32),
+            uses: row.uses as u32,
+            created_at: bson::DateTime::from_millis(row.created_at),
+        }
+    }
+}
+
+impl SqlRepositoryImpl<Wrap> {
+    async fn find_credentials(
+        &self,
+        wrap_id: &str,
+    ) -> Result<Vec<WrapCredentialDocument>, WrapError> {
+        let query = format!(
+            "SELECT {} FROM wrap_credentials WHERE wrap_id = $1 ORDER BY id",
+            CREDENTIAL_COLUMNS
+        );
+        let rows = sqlx::query_as::<_, CredentialRow>(&query)
+            .bind(wrap_id)
+            .fetch_all(self.db.0.as_ref())
+            .await
+            .map_err(infrastructure_error)?;
+        Ok(rows.into_iter().map(WrapCredentialDocument::from).collect())
+    }
+
+    /// Verifies the management token of an existing wrap.
+    async fn authorize_management(
+        &self,
+        id: &str,
+        management_token: &str,
+    ) -> Result<(), WrapError> {
+        match self.find_document(id).await? {
+            Some(wd) => wd.verify_management_token(management_token),
+            None => Err(WrapError::NotFound),
+        }
+    }
+
+    /// Counts a wrong password unless the wrap was locked meanwhile. The count and the lock
+    /// it triggers are stored together, only while the read `failed_attempts` are unchanged.
+    async fn count_failure(
+        &self,
+        mut wd: WrapDocument,
+        now: DateTime<Utc>,
+        err: WrapError,
+    ) -> WrapError {
+        loop {
+            let (failed_attempts, locked_until) = wd.after_failure(now);
+            let counted = sqlx::query(
+                "UPDATE wraps SET failed_attempts = $1, locked_until = $2 \
+                 WHERE id = $3 AND failed_attempts = $4 \
+                 AND (locked_until IS NULL OR locked_until <= $5)",
+            )
+            .bind(failed_attempts as i64)
+            .bind(locked_until.map(|v| v.timestamp_millis()))
+            .bind(&wd.id)
+            .bind(wd.failed_attempts as i64)
+            .bind(now.timestamp_millis())
+            .execute(self.db.0.as_ref())
+            .await;
+            match counted {
+                Ok(counted) if counted.rows_affected() > 0 => {
+                    return match locked_until {
+                        Some(locked_until) => WrapError::Locked { locked_until },
+                        None => err,
+                    };
+                }
+                Ok(_) => {}
+                Err(e) => return infrastructure_error(e),
+            }
+
+            // another failure was counted first, read its count and lock
+            wd = match self.find_document(&wd.id).await {
+                Ok(Some(wd)) => wd,
+                Ok(None) => return WrapError::NotFound,
+                Err(e) => return e,
+            };
+            if let Some(locked) = wd.active_lock(now) {
+                return locked;
+            }
+        }
+    }
+
+    /// Why a write conditional on the wrap not being locked matched no row.
+    async fn refusal(&self, id: &str, now: DateTime<Utc>, otherwise: WrapError) -> WrapError {
+        match self.find_document(id).await {
+            Ok(Some(wd)) => wd.active_lock(now).unwrap_or(otherwise),
+            Ok(None) => WrapError::NotFound,
+            Err(e) => e,
+        }
+    }
+
+    async fn find_document(&self, id: &str) -> Result<Option<WrapDocument>, WrapError> {
+        let query = format!("SELECT {} FROM wraps WHERE id = $1", WRAP_COLUMNS);
+        let row = sqlx::query_as::<_, WrapRow>(&query)
+            .bind(id)
+            .fetch_optional(self.db.0.as_ref())
+            .await
+            .map_err(infrastructure_error)?;
+        Ok(row.map(WrapDocument::from))
+    }
+}
+
+#[async_trait]
+impl WrapRepository for SqlRepositoryImpl<Wrap> {
+    async fn get(&self, id: &Id<Wrap>) -> Result<Option<Wrap>, WrapError> {
+        match self.find_document(&id.value.to_string()).await? {
+            Some(wd) => Ok(Some(wd.try_into()?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_managed(&self, id: &Id<Wrap>, management_token: &str) -> Result<Wrap, WrapError> {
+        match self.find_document(&id.value.to_string()).await? {
+            Some(wd) => {
+                wd.verify_management_token(management_token)?;
+                wd.try_into()
+            }
+            None => Err(WrapError::NotFound),
+        }
+    }
+
+    async fn insert(&self, source: NewWrap) -> Result<Wrap, WrapError> {
+        let wd = WrapDocument::try_from(source).map_err(WrapError::Internal)?;
+
+        sqlx::query(
+            "INSERT INTO wraps (id, redirect_url, password, auth_type, comment, \
+             expiration_at, created_at, failed_attempts, locked_until, max_views, \
+             remaining_views, management_token, owner_id, alias, totp_secret, redirect_url_kdf, \
+             payload_kind) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)",
+        )
+        .bind(&wd.id)
+        .bind(&wd.redirect_url)
+        .bind(&wd.password)
+        .bind(&wd.auth_type)
+        .bind(&wd.comment)
+        .bind(wd.expiration_at.map(|v| v.timestamp_millis()))
+        .bind(wd.created_at.timestamp_millis())
+        .bind(wd.failed_attempts as i64)
+        .bind(wd.locked_until.map(|v| v.timestamp_millis()))
+        .bind(wd.max_views.map(|v| v as i64))
+        .bind(wd.remaining_views.map(|v| v as i64))
+        .bind(&wd.management_token)
+        .bind(&wd.owner_id)
+        .bind(&wd.alias)
+        .bind(&wd.totp_secret)
+        .bind(&wd.redirect_url_kdf)
+        .bind(&wd.payload_kind)
+        .execute(self.db.0.as_ref())
+        .await
+        .map_err(|e| match e.as_database_error() {
+            // ids are random, so only the alias can collide
+            Some(db_err) if db_err.is_unique_violation() && wd.alias.is_some() => {
+                WrapError::AliasTaken
+            }
+            _ => WrapError::Infrastructure(e.into()),
+        })?;
+
+        match self.find_document(&wd.id).await? {
+            Some(wd) => Ok(wd.try_into()?),
+            None => Err(WrapError::NotFound),
+        }
+    }
+
+    async fn resolve_alias(&self, alias: &WrapAlias) -> Result<Option<Id<Wrap>>, WrapError> {
+        let id: Option<String> = sqlx::query_scalar("SELECT id FROM wraps WHERE alias = $1")
+            .bind(alias.as_str())
+            .fetch_optional(self.db.0.as_ref())
+            .await
+            .map_err(infrastructure_error)?;
+        match id {
+            Some(id) => Ok(Some(Id::try_from(id).map_err(WrapError::Corrupted)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn find(
+        &self,
+        id: &Id<Wrap>,
+        password: &str,
+        credential_id: Option<&Id<WrapCredential>>,
+    ) -> Result<VerifiedWrap, WrapError> {
+        let credential_id = credential_id.map(|v| v.value.to_string());
+        let pool = self.db.0.as_ref();
+        let now = Utc::now();
+        let id = id.value.to_string();
+
+        let mut wd = match self.find_document(&id).await? {
+            Some(wd) => wd,
+            None => return Err(WrapError::NotFound),
+        };
+
+        if let Some(locked) = wd.active_lock(now) {
+            return Err(locked);
+        }
+        if credential_id.is_some() {
+            wd.credentials = self.find_credentials(&id).await?;
+        }
+
+        let matched = match wd.verify_password(password, credential_id.as_deref(), now) {
+            Ok(matched) => matched,
+            Err(err) => return Err(self.count_failure(wd, now, err).await),
+        };
+
+        // every write below is conditional on the wrap not being locked meanwhile
+        match &matched {
+            PasswordMatch::Credential(credential_id) => {
+                // the condition makes concurrent authorizations race on the last use
+                let used = sqlx::query(
+                    "UPDATE wrap_credentials SET uses = uses + 1 \
+                     WHERE id = $1 AND wrap_id = $2 AND (max_uses IS NULL OR uses < max_uses) \
+                     AND EXISTS (SELECT 1 FROM wraps WHERE id = $3 \
+                     AND (locked_until IS NULL OR locked_until <= $4))",
+                )
+                .bind(credential_id)
+                .bind(&id)
+                .bind(&id)
+                .bind(now.timestamp_millis())
+                .execute(pool)
+                .await
+                .map_err(infrastructure_error)?;
+                if used.rows_affected() == 0 {
+                    return Err(self.refusal(&id, now, WrapError::InvalidCredentials).await);
+                }
+            }
+            PasswordMatch::Totp(step) => {
+                // the condition lets only one of concurrent replays through
+                let used = sqlx::query(
+                    "UPDATE wraps SET totp_last_step = $1 \
+                     WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $3) \
+                     AND (locked_until IS NULL OR locked_until <= $4)",
+                )
+                .bind(step)
+                .bind(&id)
+                .bind(step)
+                .bind(now.timestamp_millis())
+                .execute(pool)
+                .await
+                .map_err(infrastructure_error)?;
+                if used.rows_affected() == 0 {
+                    return Err(self.refusal(&id, now, WrapError::InvalidCredentials).await);
+                }
+            }
+            PasswordMatch::Password => {
+                if let Some(rehashed_password) =
+                    wd.rehash_password(password).map_err(WrapError::Internal)?
+                {
+                    // unless the password was changed meanwhile
+                    sqlx::query("UPDATE wraps SET password = $1 WHERE id = $2 AND password = $3")
+                        .bind(&rehashed_password)
+                        .bind(&id)
+                        .bind(&wd.password)
+                        .execute(pool)
+                        .await
+                        .map_err(infrastructure_error)?;
+                }
+            }
+        }
+
+        if wd.failed_attempts > 0 || wd.locked_until.is_some() {
+            let reset = sqlx::query(
+                "UPDATE wraps SET failed_attempts = 0, locked_until = NULL \
+                 WHERE id = $1 AND (locked_until IS NULL OR locked_until <= $2)",
+            )
+            .bind(&id)
+            .bind(now.timestamp_millis())
+            .execute(pool)
+            .await
+            .map_err(infrastructure_error)?;
+            if reset.rows_affected() == 0 {
+                return Err(self.refusal(&id, now, WrapError::NotFound).await);
+            }
+        } else if matches!(matched, PasswordMatch::Password) {
+            // nothing to store, but a racing failure may have locked the wrap
+            if let Some(locked) = self
+                .find_document(&id)
+                .await?
+                .and_then(|v| v.active_lock(now))
+            {
+                return Err(locked);
+            }
+        }
+
+        Ok(VerifiedWrap::new(
+            wd.open(password)?,
+            matched.credential_id()?,
+        ))
+    }
+
+    async fn consume_view(&self, id: &Id<Wrap>) -> Result<Wrap, WrapError> {
+        let id = id.value.to_string();
+
+        let query = format!(
+            "UPDATE wraps SET remaining_views = remaining_views - 1 \
+             WHERE id = $1 AND remaining_views > 0 RETURNING {}",
+            WRAP_COLUMNS
+        );
+        let updated = sqlx::query_as::<_, WrapRow>(&query)
+            .bind(&id)
+            .fetch_optional(self.db.0.as_ref())
+            .await
+            .map_err(infrastructure_error)?;
+
+        match updated {
+            Some(row) => WrapDocument::from(row).try_into(),
+            None => match self.find_document(&id).await? {
+                Some(_) => Err(WrapError::Consumed),
+                None => Err(WrapError::NotFound),
+            },
+        }
+    }
+
+    async fn update(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+        source: WrapUpdate,
+    ) -> Result<Wrap, WrapError> {
+        let id = id.value.to_string();
+
+        let mut wd = match self.find_document(&id).await? {
+            Some(wd) => wd,
+            None => return Err(WrapError::NotFound),
+        };
+        wd.verify_management_token(management_token)?;
+
+        let update = WrapDocumentUpdate::new(source, wd.redirect_url_kdf.is_some())?;
+        update.apply(&mut wd);
+
+        let query = format!(
+            "UPDATE wraps SET redirect_url = $1, redirect_url_kdf = $2, password = $3, \
+             comment = $4, expiration_at = $5, failed_attempts = $6, locked_until = $7 \
+             WHERE id = $8 RETURNING {}",
+            WRAP_COLUMNS
+        );
+        let updated = sqlx::query_as::<_, WrapRow>(&query)
+            .bind(&wd.redirect_url)
+            .bind(&wd.redirect_url_kdf)
+            .bind(&wd.password)
+            .bind(&wd.comment)
+            .bind(wd.expiration_at.map(|v| v.timestamp_millis()))
+            .bind(wd.failed_attempts as i64)
+            .bind(wd.locked_until.map(|v| v.timestamp_millis()))
+            .bind(&id)
+            .fetch_optional(self.db.0.as_ref())
+            .await
+            .map_err(infrastructure_error)?;
+
+        match updated {
+            Some(row) => WrapDocument::from(row).try_into(),
+            None => Err(WrapError::NotFound),
+        }
+    }
+
+    async fn delete(&self, id: &Id<Wrap>, management_token: &str) -> Result<(), WrapError> {
+        let id = id.value.to_string();
+
+        match self.find_document(&id).await? {
+            Some(wd) => wd.verify_management_token(management_token)?,
+            None => return Err(WrapError::NotFound),
+        }
+
+        // credentials go in the same transaction, so none outlives its wrap
+        let mut tx = self.db.0.begin().await.map_err(infrastructure_error)?;
+        sqlx::query("DELETE FROM wrap_credentials WHERE wrap_id = $1")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await
+            .map_err(infrastructure_error)?;
+        let result = sqlx::query("DELETE FROM wraps WHERE id = $1")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await
+            .map_err(infrastructure_error)?;
+        tx.commit().await.map_err(infrastructure_error)?;
+
+        if result.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(WrapError::NotFound)
+        }
+    }
+
+    async fn list_credentials(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+    ) -> Result<Vec<WrapCredential>, WrapError> {
+        let id = id.value.to_string();
+        self.authorize_management(&id, management_token).await?;
+
+        let credentials = self
+            .find_credentials(&id)
+            .await?
+            .into_iter()
+            .map(WrapCredential::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(WrapError::Corrupted)?;
+        Ok(credentials)
+    }
+
+    async fn add_credential(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+        source: NewWrapCredential,
+    ) -> Result<WrapCredential, WrapError> {
+        let pool = self.db.0.as_ref();
+        let id = id.value.to_string();
+        self.authorize_management(&id, management_token).await?;
+
+        let cd = WrapCredentialDocument::try_from(source).map_err(WrapError::Internal)?;
+        // counted in the same statement, so concurrent requests cannot exceed the limit
+        let inserted = sqlx::query(
+            "INSERT INTO wrap_credentials (id, wrap_id, name, password, expiration_at, \
+             max_uses, uses, created_at) SELECT $1, $2, $3, $4, $5, $6, $7, $8 \
+             WHERE (SELECT COUNT(*) FROM wrap_credentials WHERE wrap_id = $9) < $10",
+        )
+        .bind(&cd.id)
+        .bind(&id)
+        .bind(&cd.name)
+        .bind(&cd.password)
+        .bind(cd.expiration_at.map(|v| v.timestamp_millis()))
+        .bind(cd.max_uses.map(|v| v as i64))
+        .bind(cd.uses as i64)
+        .bind(cd.created_at.timestamp_millis())
+        .bind(&id)
+        .bind(MAX_CREDENTIALS_PER_WRAP as i64)
+        .execute(pool)
+        .await
+        .map_err(infrastructure_error)?;
+        if inserted.rows_affected() == 0 {
+            return Err(WrapError::InvalidInput(format!(
+                "A wrap holds at most {} credentials.",
+                MAX_CREDENTIALS_PER_WRAP
+            )));
+        }
+
+        cd.try_into().map_err(WrapError::Corrupted)
+    }
+
+    async fn revoke_credential(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+        credential_id: &Id<WrapCredential>,
+    ) -> Result<(), WrapError> {
+        let id = id.value.to_string();
+        self.authorize_management(&id, management_token).await?;
+
+        let result = sqlx::query("DELETE FROM wrap_credentials WHERE id = $1 AND wrap_id = $2")
+            .bind(credential_id.value.to_string())
+            .bind(&id)
+            .execute(self.db.0.as_ref())
+            .await
+            .map_err(infrastructure_error)?;
+        if result.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(WrapError::NotFound)
+        }
+    }
+
+    async fn list(&self, query: &WrapListQuery) -> Result<Page<Wrap>, WrapError> {
+        // placeholders are numbered in the same order as the binds below
+        let mut conditions = vec!["owner_id = $1".to_string()];
+        let mut n = 1;
+        if query.cursor.is_some() {
+            n += 1;
+            conditions.push(format!("id < ${}", n));
+        }
+        match query.status {
+            Some(WrapStatus::Active) => {
+                n += 1;
+                conditions.push(format!("(expiration_at IS NULL OR expiration_at > ${})", n));
+            }
+            Some(WrapStatus::Expired) => {
+                n += 1;
+                conditions.push(format!("expiration_at <= ${}", n));
+            }
+            None => {}
+        }
+        if query.comment.is_some() {
+            n += 1;
+            conditions.push(format!("LOWER(comment) LIKE ${} ESCAPE '\\'", n));
+        }
+        let sql = format!(
+            "SELECT {} FROM wraps WHERE {} ORDER BY id DESC LIMIT ${}",
+            WRAP_COLUMNS,
+            conditions.join(" AND "),
+            n + 1
+        );
+
+        let mut rows = sqlx::query_as::<_, WrapRow>(&sql).bind(query.owner_id.value.to_string());
+        if let Some(cursor) = &query.cursor {
+            rows = rows.bind(cursor.value.to_string());
+        }
+        if query.status.is_some() {
+            rows = rows.bind(query.now.timestamp_millis());
+        }
+        if let Some(comment) = &query.comment {
+            rows = rows.bind(format!("%{}%", escape_like(&comment.to_lowercase())));
+        }
+        let rows = rows
+            .bind(query.limit as i64 + 1)
+            .fetch_all(self.db.0.as_ref())
+            .await
+            .map_err(infrastructure_error)?;
+
+        let wraps = rows
+            .into_iter()
+            .map(|row| WrapDocument::from(row).try_into())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Page::new(wraps, query.limit, |v| &v.id))
+    }
+
+    async fn delete_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, WrapError> {
+        let mut tx = self.db.0.begin().await.map_err(infrastructure_error)?;
+        for table in ["wrap_credentials", "wrap_access_events"] {
+            let query = format!(
+                "DELETE FROM {} WHERE wrap_id IN (SELECT id FROM wraps \
+                 WHERE expiration_at IS NOT NULL AND expiration_at <= $1)",
+                table
+            );
+            sqlx::query(&query)
+                .bind(expired_before.timestamp_millis())
+                .execute(&mut *tx)
+                .await
+                .map_err(infrastructure_error)?;
+        }
+
+        let res = sqlx::query(
+            "DELETE FROM wraps WHERE expiration_at IS NOT NULL AND expiration_at <= $1",
+        )
+        .bind(expired_before.timestamp_millis())
+        .execute(&mut *tx)
+        .await
+        .map_err(infrastructure_error)?;
+        tx.commit().await.map_err(infrastructure_error)?;
+        Ok(res.rows_affected())
+    }
+}
+
+/// Escapes the `LIKE` wildcards in `value` with a backslash.
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::sql::SqlDb;
+    use crate::testing::{
+        init_env, new_wrap, new_wrap_with, outdated_hash, PASSWORD, REDIRECT_URL,
+    };
+    use data_encoding::BASE32_NOPAD;
+    use sqlx::any::{install_default_drivers, AnyPoolOptions};
+    use std::sync::Arc;
+    use totp_rs::{Algorithm, TOTP};
+    use url_wrap_kernel::model::wrap::auth_type::WrapAuthType;
+    use url_wrap_kernel::model::wrap::ManagementToken;
+
+    /// Repository on a fresh in-memory SQLite database. A single connection keeps the
+    /// database alive, while the statements of concurrent calls still interleave.
+    async fn repository() -> SqlRepositoryImpl<Wrap> {
+        init_env();
+        install_default_drivers();
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let db = SqlDb(Arc::new(pool));
+        db.migrate().await.unwrap();
+        SqlRepositoryImpl::new(db)
+    }
+
+    async fn insert(repository: &SqlRepositoryImpl<Wrap>, token: &ManagementToken) -> Wrap {
+        repository.insert(new_wrap(token)).await.unwrap()
+    }
+
+    async fn stored(repository: &SqlRepositoryImpl<Wrap>, id: &Id<Wrap>) -> WrapDocument {
+        repository
+            .find_document(&id.value.to_string())
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn inserted_wrap_is_read_back() {
+        let repository = repository().await;
+        let token = ManagementToken::gen();
+        let wrap = insert(&repository, &token).await;
+
+        let found = repository.get(&wrap.id).await.unwrap().unwrap();
+        assert_eq!(found.comment, "test");
+
+        let managed = repository.get_managed(&wrap.id, &token.0).await.unwrap();
+        assert_eq!(managed.id.value, wrap.id.value);
+        let res = repository.get_managed(&wrap.id, "wrong").await;
+        assert!(matches!(res, Err(WrapError::InvalidCredentials)));
+
+        assert!(repository.get(&Id::gen()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn find_reveals_the_url_and_resets_failures() {
+        let repository = repository().await;
+        let wrap = insert(&repository, &ManagementToken::gen()).await;
+
+        let res = repository.find(&wrap.id, "wrong", None).await;
+        assert!(matches!(res, Err(WrapError::InvalidCredentials)));
+        assert_eq!(stored(&repository, &wrap.id).await.failed_attempts, 1);
+
+        let verified = repository.find(&wrap.id, PASSWORD, None).await.unwrap();
+        assert_eq!(verified.wrap.redirect_url.as_deref(), Some(REDIRECT_URL));
+        assert_eq!(stored(&repository, &wrap.id).await.failed_attempts, 0);
+
+        let res = repository.find(&Id::gen(), PASSWORD, None).await;
+        assert!(matches!(res, Err(WrapError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn repeated_failures_lock_the_wrap() {
+        let repository = repository().await;
+        let wrap = insert(&repository, &ManagementToken::gen()).await;
+
+        for _ in 0..2 {
+            let res = repository.find(&wrap.id, "wrong", None).await;
+            assert!(matches!(res, Err(WrapError::InvalidCredentials)));
+        }
+        let res = repository.find(&wrap.id, "wrong", None).await;
+        assert!(matches!(res, Err(WrapError::Locked { .. })));
+
+        let res = repository.find(&wrap.id, PASSWORD, None).await;
+        assert!(matches!(res, Err(WrapError::Locked { .. })));
+        let wd = stored(&repository, &wrap.id).await;
+        assert_eq!(wd.failed_attempts, 3);
+        assert!(wd.locked_until.is_some());
+    }
+
+    #[tokio::test]
+    async fn concurrent_failures_cannot_pass_the_lock() {
+        let repository = repository().await;
+        let wrap = insert(&repository, &ManagementToken::gen()).await;
+
+        let guesses = (0..8).map(|_| repository.find(&wrap.id, "wrong", None));
+        let results = futures::future::join_all(guesses).await;
+
+        let wrong = results
+            .iter()
+            .filter(|v| matches!(v, Err(WrapError::InvalidCredentials)))
+            .count();
+        let locked = results
+            .iter()
+            .filter(|v| matches!(v, Err(WrapError::Locked { .. })))
+            .count();
+        assert_eq!((wrong, locked), (2, 6));
+        assert_eq!(stored(&repository, &wrap.id).await.failed_attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn views_are_consumed() {
+        let repository = repository().await;
+        let mut source = new_wrap(&ManagementToken::gen());
+        source.max_views = Some(1);
+        let wrap = repository.insert(source).await.unwrap();
+        assert_eq!(wrap.remaining_views, Some(1));
+
+        let wrap = repository.consume_view(&wrap.id).await.unwrap();
+        assert_eq!(wrap.remaining_views, Some(0));
+        let res = repository.consume_view(&wrap.id).await;
+        assert!(matches!(res, Err(WrapError::Consumed)));
+        let res = repository.consume_view(&Id::gen()).await;
+        assert!(matches!(res, Err(WrapError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn update_replaces_the_password() {
+        let repository = repository().await;
+        let token = ManagementToken::gen();
+        let wrap = insert(&repository, &token).await;
+
+        let source = WrapUpdate::new(
+            None,
+            Some("battery staple".to_string()),
+            Some("updated".to_string()),
+            None,
+        );
+        let res = repository.update(&wrap.id, "wrong", source).await;
+        assert!(matches!(res, Err(WrapError::InvalidCredentials)));
+
+        let source = WrapUpdate::new(
+            None,
+            Some("battery staple".to_string()),
+            Some("updated".to_string()),
+            None,
+        );
+        let updated = repository.update(&wrap.id, &token.0, source).await.unwrap();
+        assert_eq!(updated.comment, "updated");
+
+        let res = repository.find(&wrap.id, PASSWORD, None).await;
+        assert!(matches!(res, Err(WrapError::InvalidCredentials)));
+        repository
+            .find(&wrap.id, "battery staple", None)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn delete_removes_the_wrap_and_its_credentials() {
+        let repository = repository().await;
+        let token = ManagementToken::gen();
+        let wrap = insert(&repository, &token).await;
+        let credential = NewWrapCredential::new(
+            Id::gen(),
+            "guest".to_string(),
+            "s3cret".to_string(),
+            None,
+            None,
+        )
+        .unwrap();
+        repository
+            .add_credential(&wrap.id, &token.0, credential)
+            .await
+            .unwrap();
+
+        let res = repository.delete(&wrap.id, "wrong").await;
+        assert!(matches!(res, Err(WrapError::InvalidCredentials)));
+
+        repository.delete(&wrap.id, &token.0).await.unwrap();
+        assert!(repository.get(&wrap.id).await.unwrap().is_none());
+        let wrap_id = wrap.id.value.to_string();
+        assert!(repository
+            .find_credentials(&wrap_id)
+            .await
+            .unwrap()
+            .is_empty());
+        let res = repository.delete(&wrap.id, &token.0).await;
+        assert!(matches!(res, Err(WrapError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn credential_is_used_up_and_revoked() {
+        let repository = repository().await;
+        let token = ManagementToken::gen();
+        let wrap = insert(&repository, &token).await;
+
+        let source = NewWrapCredential::new(
+            Id::gen(),
+            "once".to_string(),
+            "s3cret".to_string(),
+            None,
+            Some(1),
+        )
+        .unwrap();
+        let credential = repository
+            .add_credential(&wrap.id, &token.0, source)
+            .await
+            .unwrap();
+        let listed = repository
+            .list_credentials(&wrap.id, &token.0)
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 1);
+
+        // only the credential's own password is accepted with it
+        let res = repository
+            .find(&wrap.id, PASSWORD, Some(&credential.id))
+            .await;
+        assert!(matches!(res, Err(WrapError::InvalidCredentials)));
+        let verified = repository
+            .find(&wrap.id, "s3cret", Some(&credential.id))
+            .await
+            .unwrap();
+        assert_eq!(
+            verified.credential_id.map(|v| v.value),
+            Some(credential.id.value)
+        );
+        let res = repository
+            .find(&wrap.id, "s3cret", Some(&credential.id))
+            .await;
+        assert!(matches!(res, Err(WrapError::InvalidCredentials)));
+
+        repository
+            .revoke_credential(&wrap.id, &token.0, &credential.id)
+            .await
+            .unwrap();
+        let listed = repository
+            .list_credentials(&wrap.id, &token.0)
+            .await
+            .unwrap();
+        assert!(listed.is_empty());
+        let res = repository
+            .revoke_credential(&wrap.id, &token.0, &credential.id)
+            .await;
+        assert!(matches!(res, Err(WrapError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn totp_code_is_accepted_once() {
+        let repository = repository().await;
+        let source = new_wrap_with(WrapAuthType::Totp, None, &ManagementToken::gen());
+        let uri = source.totp_provisioning_uri().unwrap();
+        let wrap = repository.insert(source).await.unwrap();
+
+        let secret = uri
+            .split(['?', '&'])
+            .find_map(|v| v.strip_prefix("secret="))
+            .unwrap();
+        let secret = BASE32_NOPAD.decode(secret.as_bytes()).unwrap();
+        let code = TOTP::new(Algorithm::SHA1, 6, 0, 30, secret)
+            .unwrap()
+            .generate_current()
+            .unwrap();
+
+        repository.find(&wrap.id, &code, None).await.unwrap();
+        assert!(stored(&repository, &wrap.id).await.totp_last_step.is_some());
+        let res = repository.find(&wrap.id, &code, None).await;
+        assert!(matches!(res, Err(WrapError::InvalidCredentials)));
+    }
+
+    #[tokio::test]
+    async fn outdated_hash_is_rehashed_after_success() {
+        let repository = repository().await;
+        let wrap = insert(&repository, &ManagementToken::gen()).await;
+        sqlx::query("UPDATE wraps SET password = $1 WHERE id = $2")
+            .bind(outdated_hash())
+            .bind(wrap.id.value.to_string())
+            .execute(repository.db.0.as_ref())
+            .await
+            .unwrap();
+
+        let res = repository.find(&wrap.id, "wrong", None).await;
+        assert!(matches!(res, Err(WrapError::InvalidCredentials)));
+        assert!(stored(&repository, &wrap.id)
+            .await
+            .password
+            .starts_with("$argon2i$v=16$m=512,t=1,p=1$"));
+
+        repository.find(&wrap.id, PASSWORD, None).await.unwrap();
+        let rehashed = stored(&repository, &wrap.id).await.password;
+        assert!(
+            rehashed.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"),
+            "{}",
+            rehashed
+        );
+        repository.find(&wrap.id, PASSWORD, None).await.unwrap();
+        assert_eq!(stored(&repository, &wrap.id).await.password, rehashed);
+    }
+
+    #[tokio::test]
+    async fn expired_wraps_are_deleted_with_their_access_events() {
+        let repository = repository().await;
+        let pool = repository.db.0.clone();
+        let expired = insert(&repository, &ManagementToken::gen()).await;
+        let kept = insert(&repository, &ManagementToken::gen()).await;
+        let now = Utc::now();
+        sqlx::query("UPDATE wraps SET expiration_at = $1 WHERE id = $2")
+            .bind((now - chrono::Duration::hours(2)).timestamp_millis())
+            .bind(expired.id.value.to_string())
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        for wrap in [&expired, &kept] {
+            sqlx::query(
+                "INSERT INTO wrap_access_events (id, wrap_id, outcome, occurred_at) \
+                 VALUES ($1, $2, 'success', $3)",
+            )
+            .bind(Id::<Wrap>::gen().value.to_string())
+            .bind(wrap.id.value.to_string())
+            .bind(now.timestamp_millis())
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        }
+
+        let deleted = repository
+            .delete_expired(now - chrono::Duration::hours(3))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 0);
+
+        let deleted = repository
+            .delete_expired(now - chrono::Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+        assert!(repository.get(&expired.id).await.unwrap().is_none());
+        let wrap_ids: Vec<String> = sqlx::query_scalar("SELECT wrap_id FROM wrap_access_events")
+            .fetch_all(pool.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(wrap_ids, [kept.id.value.to_string()]);
+    }
+}
@@ -0,0 +1,76 @@
+//! Fixtures shared by the repository tests.
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::SaltString;
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
+use std::env;
+use std::sync::Once;
+use url_wrap_kernel::model::wrap::auth_type::WrapAuthType;
+use url_wrap_kernel::model::wrap::payload::WrapPayloadKind;
+use url_wrap_kernel::model::wrap::{ManagementToken, NewWrap};
+use url_wrap_kernel::model::Id;
+
+pub const PASSWORD: &str = "correct horse";
+pub const REDIRECT_URL: &str = "https://example.com/secret";
+
+static INIT: Once = Once::new();
+
+/// Sets the variables the models read their policies from, once per test binary.
+pub fn init_env() {
+    INIT.call_once(|| {
+        let vars = [
+            ("ARGON2_PHC_VARIANT", "argon2id"),
+            ("ARGON2_PHC_VERSION", "19"),
+            ("ARGON2_PHC_TIME_COST", "1"),
+            ("ARGON2_PHC_MEMORY_COST", "1024"),
+            ("ARGON2_PHC_PARALLELISM_COST", "1"),
+            ("AES_GCM_SALT", "salt_string_32_bytes_for_tests__"),
+            ("WRAP_LOCKOUT_THRESHOLD", "3"),
+            ("WRAP_LOCKOUT_BACKOFF_SECONDS", "30"),
+        ];
+        for (key, value) in vars {
+            env::set_var(key, value);
+        }
+    });
+}
+
+/// A never-expiring URL wrap opened with `PASSWORD`.
+pub fn new_wrap(management_token: &ManagementToken) -> NewWrap {
+    new_wrap_with(
+        WrapAuthType::Text,
+        Some(PASSWORD.to_string()),
+        management_token,
+    )
+}
+
+pub fn new_wrap_with(
+    auth_type: WrapAuthType,
+    password: Option<String>,
+    management_token: &ManagementToken,
+) -> NewWrap {
+    init_env();
+    NewWrap::new(
+        Id::gen(),
+        REDIRECT_URL.to_string(),
+        WrapPayloadKind::Url,
+        password,
+        auth_type,
+        "test".to_string(),
+        None,
+        None,
+        ManagementToken(management_token.0.clone()),
+        None,
+        None,
+        false,
+    )
+    .unwrap()
+}
+
+/// A hash of `PASSWORD` with weaker parameters than the test policy.
+pub fn outdated_hash() -> String {
+    let params = Params::new(512, 1, 1, None).unwrap();
+    Argon2::new(Algorithm::Argon2i, Version::V0x10, params)
+        .hash_password(PASSWORD.as_bytes(), &SaltString::generate(&mut OsRng))
+        .unwrap()
+        .to_string()
+}
@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use url_wrap_kernel::model::access_event::stats::{AccessBucket, AccessStats};
+use url_wrap_kernel::model::access_event::AccessEvent;
+use url_wrap_kernel::model::page::Page;
+
+/// Who is trying to open a wrap, as far as the request tells.
+pub struct AccessClient {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl AccessClient {
+    pub fn new(ip: Option<String>, user_agent: Option<String>) -> Self {
+        Self { ip, user_agent }
+    }
+}
+
+#[derive(Debug)]
+pub struct AccessEventView {
+    pub id: String,
+    pub outcome: String,
+    pub credential_id: Option<String>,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl From<AccessEvent> for AccessEventView {
+    fn from(ae: AccessEvent) -> Self {
+        Self {
+            id: ae.id.value.to_string(),
+            outcome: ae.outcome.as_str().to_string(),
+            credential_id: ae.credential_id,
+            client_ip: ae.client_ip,
+            user_agent: ae.user_agent,
+            occurred_at: ae.occurred_at,
+        }
+    }
+}
+
+pub struct AccessEventListView {
+    pub events: Vec<AccessEventView>,
+    pub next_cursor: Option<String>,
+}
+
+impl From<Page<AccessEvent>> for AccessEventListView {
+    fn from(ap: Page<AccessEvent>) -> Self {
+        Self {
+            events: ap.items.into_iter().map(AccessEventView::from).collect(),
+            next_cursor: ap.next_cursor.map(|v| v.value.to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AccessBucketView {
+    pub start: DateTime<Utc>,
+    pub opens: u64,
+    pub failures: u64,
+}
+
+impl From<AccessBucket> for AccessBucketView {
+    fn from(ab: AccessBucket) -> Self {
+        Self {
+            start: ab.start,
+            opens: ab.opens,
+            failures: ab.failures,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AccessStatsView {
+    pub total_opens: u64,
+    pub unique_opens: u64,
+    pub failed_attempts: u64,
+    pub first_access_at: Option<DateTime<Utc>>,
+    pub last_access_at: Option<DateTime<Utc>>,
+    pub hourly: Vec<AccessBucketView>,
+    pub daily: Vec<AccessBucketView>,
+}
+
+impl From<AccessStats> for AccessStatsView {
+    fn from(s: AccessStats) -> Self {
+        Self {
+            total_opens: s.total_opens,
+            unique_opens: s.unique_opens,
+            failed_attempts: s.failed_attempts,
+            first_access_at: s.first_access_at,
+            last_access_at: s.last_access_at,
+            hourly: s.hourly.into_iter().map(AccessBucketView::from).collect(),
+            daily: s.daily.into_iter().map(AccessBucketView::from).collect(),
+        }
+    }
+}
+
+pub struct SearchAccessEvents {
+    pub cursor: Option<String>,
+    pub limit: u32,
+}
+
+impl SearchAccessEvents {
+    pub fn new(cursor: Option<String>, limit: u32) -> Self {
+        Self { cursor, limit }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::model::wrap::WrapExpiration;
+use chrono::{DateTime, Utc};
+use url_wrap_kernel::model::wrap::credential::{NewWrapCredential, WrapCredential};
+use url_wrap_kernel::model::wrap::error::WrapError;
+use url_wrap_kernel::model::Id;
+
+#[derive(Debug)]
+pub struct CredentialView {
+    pub id: String,
+    pub name: String,
+    pub expiration_at: Option<DateTime<Utc>>,
+    pub max_uses: Option<u32>,
+    pub uses: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<WrapCredential> for CredentialView {
+    fn from(c: WrapCredential) -> Self {
+        Self {
+            id: c.id.value.to_string(),
+            name: c.name,
+            expiration_at: c.expiration_at,
+            max_uses: c.max_uses,
+            uses: c.uses,
+            created_at: c.created_at,
+        }
+    }
+}
+
+pub struct CreateCredential {
+    pub name: String,
+    pub password: String,
+    /// `None` when the credential never expires.
+    pub expiration: Option<WrapExpiration>,
+    pub max_uses: Option<u32>,
+}
+
+impl CreateCredential {
+    pub fn new(
+        name: String,
+        password: String,
+        expiration: Option<WrapExpiration>,
+        max_uses: Option<u32>,
+    ) -> Self {
+        Self {
+            name,
+            password,
+            expiration,
+            max_uses,
+        }
+    }
+}
+
+impl TryFrom<CreateCredential> for NewWrapCredential {
+    type Error = WrapError;
+
+    fn try_from(cc: CreateCredential) -> Result<Self, Self::Error> {
+        let expiration_at = match cc.expiration {
+            Some(expiration) => expiration.resolve(Utc::now())?,
+            None => None,
+        };
+
+        NewWrapCredential::new(Id::gen(), cc.name, cc.password, expiration_at, cc.max_uses)
+    }
+}
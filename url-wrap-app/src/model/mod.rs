@@ -1 +1,5 @@
+pub mod access_event;
+pub mod credential;
+pub mod owner;
+pub mod rate_limit;
 pub mod wrap;
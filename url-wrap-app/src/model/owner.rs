@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use url_wrap_kernel::model::owner::Owner;
+
+#[derive(Debug)]
+pub struct OwnerView {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Owner> for OwnerView {
+    fn from(o: Owner) -> Self {
+        Self {
+            id: o.id.value.to_string(),
+            name: o.name,
+            created_at: o.created_at,
+        }
+    }
+}
+
+/// Returned only once, when the owner is registered.
+pub struct RegisteredOwnerView {
+    pub owner: OwnerView,
+    pub api_key: String,
+}
+
+pub struct CreateOwner {
+    pub name: String,
+}
+
+impl CreateOwner {
+    pub fn new(name: String) -> Self {
+        Self { name }
+    }
+}
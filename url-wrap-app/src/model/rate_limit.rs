@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use url_wrap_kernel::model::seconds_until;
+
+/// Number of requests a client may send per fixed window.
+#[derive(Debug, Clone)]
+pub struct RateLimitQuota {
+    pub max_requests: u64,
+    pub window_seconds: i64,
+}
+
+impl RateLimitQuota {
+    pub fn new(max_requests: u64, window_seconds: i64) -> Self {
+        Self {
+            max_requests,
+            window_seconds,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RateLimitStatus {
+    pub limit: u64,
+    pub count: u64,
+    pub reset_at: DateTime<Utc>,
+}
+
+impl RateLimitStatus {
+    pub fn is_exceeded(&self) -> bool {
+        self.count > self.limit
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.count)
+    }
+
+    /// Seconds until the window resets, rounded up.
+    pub fn retry_after(&self, now: DateTime<Utc>) -> i64 {
+        seconds_until(self.reset_at, now)
+    }
+}
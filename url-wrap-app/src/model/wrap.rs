@@ -1,15 +1,29 @@
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, Duration, Utc};
+use url_wrap_kernel::model::page::Page;
+use url_wrap_kernel::model::wrap::alias::WrapAlias;
 use url_wrap_kernel::model::wrap::auth_type::WrapAuthType;
-use url_wrap_kernel::model::wrap::{NewWrap, Wrap};
+use url_wrap_kernel::model::wrap::error::WrapError;
+use url_wrap_kernel::model::wrap::payload::WrapPayloadKind;
+use url_wrap_kernel::model::wrap::query::WrapStatus;
+use url_wrap_kernel::model::wrap::{ManagementToken, NewWrap, Wrap, WrapUpdate};
 use url_wrap_kernel::model::Id;
 
 #[derive(Debug)]
 pub struct WrapView {
     pub id: String,
-    pub redirect_url: String,
+    /// The URL, or the note of text wraps. `None` for zero-knowledge wraps,
+    /// except right after their password was verified.
+    pub redirect_url: Option<String>,
+    pub payload_kind: WrapPayloadKind,
     pub auth_type: u32,
+    /// Number of digits for PIN wraps.
+    pub pin_length: Option<u32>,
     pub comment: String,
-    pub expiration_at: DateTime<Utc>,
+    pub expiration_at: Option<DateTime<Utc>>,
+    pub max_views: Option<u32>,
+    pub remaining_views: Option<u32>,
+    pub alias: Option<String>,
+    pub zero_knowledge: bool,
 }
 
 impl From<Wrap> for WrapView {
@@ -17,58 +31,227 @@ impl From<Wrap> for WrapView {
         Self {
             id: w.id.value.to_string(),
             redirect_url: w.redirect_url,
+            payload_kind: w.payload_kind,
             auth_type: w.auth_type.id(),
+            pin_length: w.auth_type.pin_length(),
             comment: w.comment,
             expiration_at: w.expiration_at,
+            max_views: w.max_views,
+            remaining_views: w.remaining_views,
+            alias: w.alias,
+            zero_knowledge: w.zero_knowledge,
+        }
+    }
+}
+
+/// Returned only once, when the wrap is created.
+pub struct RegisteredWrapView {
+    pub wrap: WrapView,
+    pub management_token: String,
+    /// `otpauth://` URI of TOTP wraps.
+    pub totp_uri: Option<String>,
+}
+
+pub struct WrapListView {
+    pub wraps: Vec<WrapView>,
+    pub next_cursor: Option<String>,
+}
+
+impl From<Page<Wrap>> for WrapListView {
+    fn from(wp: Page<Wrap>) -> Self {
+        Self {
+            wraps: wp.items.into_iter().map(WrapView::from).collect(),
+            next_cursor: wp.next_cursor.map(|v| v.value.to_string()),
+        }
+    }
+}
+
+pub struct SearchWraps {
+    pub cursor: Option<String>,
+    pub limit: u32,
+    pub status: Option<WrapStatus>,
+    pub comment: Option<String>,
+}
+
+impl SearchWraps {
+    pub fn new(
+        cursor: Option<String>,
+        limit: u32,
+        status: Option<WrapStatus>,
+        comment: Option<String>,
+    ) -> Self {
+        Self {
+            cursor,
+            limit,
+            status,
+            comment,
         }
     }
 }
 
 pub struct CreateWrap {
+    /// The URL, or the note of text wraps.
     pub redirect_url: String,
-    pub password: String,
+    pub payload_kind: WrapPayloadKind,
+    /// `None` for TOTP wraps.
+    pub password: Option<String>,
     pub auth_type: u32,
+    /// Number of digits for PIN wraps, four when not given.
+    pub pin_length: Option<u32>,
     pub comment: String,
-    pub expiration_at: u32,
+    pub expiration: WrapExpiration,
+    pub max_views: Option<u32>,
+    /// Id of the authenticated owner, `None` for anonymous wraps.
+    pub owner_id: Option<String>,
+    pub alias: Option<String>,
+    /// Encrypts the redirect URL with a key derived from the password instead of the server key.
+    pub zero_knowledge: bool,
 }
 
 impl CreateWrap {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         redirect_url: String,
-        password: String,
+        payload_kind: WrapPayloadKind,
+        password: Option<String>,
         auth_type: u32,
+        pin_length: Option<u32>,
         comment: String,
-        expiration_at: u32,
+        expiration: WrapExpiration,
+        max_views: Option<u32>,
+        owner_id: Option<String>,
+        alias: Option<String>,
+        zero_knowledge: bool,
     ) -> Self {
         Self {
             redirect_url,
+            payload_kind,
             password,
             auth_type,
+            pin_length,
             comment,
-            expiration_at,
+            expiration,
+            max_views,
+            owner_id,
+            alias,
+            zero_knowledge,
+        }
+    }
+}
+
+pub enum WrapExpiration {
+    At(DateTime<Utc>),
+    After(Duration),
+    Never,
+}
+
+impl WrapExpiration {
+    /// Resolves the expiration date relative to `now`, rejecting dates that are not in the future.
+    pub fn resolve(&self, now: DateTime<Utc>) -> Result<Option<DateTime<Utc>>, WrapError> {
+        let expiration_at = match self {
+            WrapExpiration::At(expiration_at) => *expiration_at,
+            WrapExpiration::After(duration) => now
+                .checked_add_signed(*duration)
+                .ok_or_else(|| WrapError::InvalidInput("`expiresIn` is too long.".to_string()))?,
+            WrapExpiration::Never => return Ok(None),
+        };
+
+        if expiration_at <= now {
+            return Err(WrapError::InvalidInput(
+                "Expiration date is in the past.".to_string(),
+            ));
         }
+        Ok(Some(expiration_at))
     }
 }
 
 impl TryFrom<CreateWrap> for NewWrap {
-    type Error = anyhow::Error;
+    type Error = WrapError;
 
     fn try_from(cw: CreateWrap) -> Result<Self, Self::Error> {
         let wrap_id = Id::gen();
-        let auth_type = WrapAuthType::try_from(cw.auth_type)?;
-        let expiration_at = Utc.timestamp(cw.expiration_at as i64, 0u32);
+        let auth_type = WrapAuthType::new(cw.auth_type, cw.pin_length)?;
+        let expiration_at = cw.expiration.resolve(Utc::now())?;
+        let owner_id = cw
+            .owner_id
+            .map(Id::try_from)
+            .transpose()
+            .map_err(WrapError::Corrupted)?;
+        let alias = cw.alias.map(WrapAlias::try_from).transpose()?;
 
-        Ok(NewWrap::new(
+        NewWrap::new(
             wrap_id,
             cw.redirect_url,
+            cw.payload_kind,
             cw.password,
             auth_type,
             cw.comment,
             expiration_at,
+            cw.max_views,
+            ManagementToken::gen(),
+            owner_id,
+            alias,
+            cw.zero_knowledge,
+        )
+    }
+}
+
+pub struct UpdateWrap {
+    pub redirect_url: Option<String>,
+    /// New note of a text wrap, at most one of `redirect_url` and `text` is set.
+    pub text: Option<String>,
+    pub password: Option<String>,
+    pub comment: Option<String>,
+    pub expiration: Option<WrapExpiration>,
+}
+
+impl UpdateWrap {
+    pub fn new(
+        redirect_url: Option<String>,
+        text: Option<String>,
+        password: Option<String>,
+        comment: Option<String>,
+        expiration: Option<WrapExpiration>,
+    ) -> Self {
+        Self {
+            redirect_url,
+            text,
+            password,
+            comment,
+            expiration,
+        }
+    }
+
+    /// Kind of the new payload, `None` when the payload is left as it is.
+    pub fn payload_kind(&self) -> Option<WrapPayloadKind> {
+        match (&self.redirect_url, &self.text) {
+            (Some(_), _) => Some(WrapPayloadKind::Url),
+            (None, Some(_)) => Some(WrapPayloadKind::Text),
+            (None, None) => None,
+        }
+    }
+}
+
+impl TryFrom<UpdateWrap> for WrapUpdate {
+    type Error = WrapError;
+
+    fn try_from(uw: UpdateWrap) -> Result<Self, Self::Error> {
+        let expiration_at = match uw.expiration {
+            Some(expiration) => Some(expiration.resolve(Utc::now())?),
+            None => None,
+        };
+
+        Ok(WrapUpdate::new(
+            uw.redirect_url.or(uw.text),
+            uw.password,
+            uw.comment,
+            expiration_at,
         ))
     }
 }
 
 pub struct AuthorizeWrap {
     pub password: String,
+    /// `None` for the password of the wrap itself.
+    pub credential_id: Option<String>,
 }
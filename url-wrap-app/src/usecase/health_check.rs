@@ -15,4 +15,8 @@ impl HealthCheckUseCase {
     pub async fn diagnose_mongo_db_conn(&self) -> anyhow::Result<()> {
         self.repository.check_mongo_db().await
     }
+
+    pub async fn diagnose_sql_db_conn(&self) -> anyhow::Result<()> {
+        self.repository.check_sql_db().await
+    }
 }
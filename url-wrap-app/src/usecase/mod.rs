@@ -1,2 +1,4 @@
 pub mod health_check;
+pub mod owner;
+pub mod rate_limit;
 pub mod wrap;
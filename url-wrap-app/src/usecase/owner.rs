@@ -0,0 +1,53 @@
+use crate::model::owner::{CreateOwner, OwnerView, RegisteredOwnerView};
+use std::sync::Arc;
+use url_wrap_adapter::modules::RepositoriesModuleExt;
+use url_wrap_kernel::model::owner::{ApiKey, NewApiKey, NewOwner};
+use url_wrap_kernel::model::Id;
+use url_wrap_kernel::repository::api_key::ApiKeyRepository;
+use url_wrap_kernel::repository::owner::OwnerRepository;
+
+pub struct OwnerUseCase<R: RepositoriesModuleExt> {
+    repositories: Arc<R>,
+}
+
+impl<R: RepositoriesModuleExt> OwnerUseCase<R> {
+    pub fn new(repositories: Arc<R>) -> Self {
+        Self { repositories }
+    }
+
+    /// Registers an owner together with its first API key.
+    pub async fn register_owner(&self, source: CreateOwner) -> anyhow::Result<RegisteredOwnerView> {
+        let owner_id = Id::gen();
+        let api_key = ApiKey::gen();
+        let issued_api_key = api_key.0.clone();
+        let owner = self
+            .repositories
+            .owner_repository()
+            .insert(
+                NewOwner::new(Id::new(owner_id.value), source.name),
+                NewApiKey::new(owner_id, api_key),
+            )
+            .await?;
+
+        Ok(RegisteredOwnerView {
+            owner: owner.into(),
+            api_key: issued_api_key,
+        })
+    }
+
+    /// Returns the owner of `api_key`, or `None` when the key is unknown.
+    pub async fn authenticate(&self, api_key: &str) -> anyhow::Result<Option<OwnerView>> {
+        let owner_id = match self
+            .repositories
+            .api_key_repository()
+            .find_owner_id(api_key)
+            .await?
+        {
+            Some(owner_id) => owner_id,
+            None => return Ok(None),
+        };
+
+        let owner = self.repositories.owner_repository().get(&owner_id).await?;
+        Ok(owner.map(OwnerView::from))
+    }
+}
@@ -0,0 +1,54 @@
+use crate::model::rate_limit::{RateLimitQuota, RateLimitStatus};
+use anyhow::anyhow;
+use chrono::{Duration, TimeZone, Utc};
+use std::sync::Arc;
+use url_wrap_adapter::modules::RepositoriesModuleExt;
+use url_wrap_kernel::repository::rate_limit::RateLimitRepository;
+
+pub struct RateLimitUseCase<R: RepositoriesModuleExt> {
+    repositories: Arc<R>,
+}
+
+impl<R: RepositoriesModuleExt> RateLimitUseCase<R> {
+    pub fn new(repositories: Arc<R>) -> Self {
+        Self { repositories }
+    }
+
+    /// Counts a request of `client` against the `bucket` quota in the current fixed window.
+    pub async fn hit(
+        &self,
+        bucket: &str,
+        client: &str,
+        quota: &RateLimitQuota,
+    ) -> anyhow::Result<RateLimitStatus> {
+        let now = Utc::now().timestamp();
+        let window_seconds = quota.window_seconds.max(1);
+        let window_start = now - now.rem_euclid(window_seconds);
+        let reset_at = Utc
+            .timestamp_opt(window_start, 0)
+            .single()
+            .ok_or_else(|| anyhow!("Rate limit window is invalid timestamp."))?
+            + Duration::seconds(window_seconds);
+
+        let key = format!("{}:{}:{}", bucket, client, window_start);
+        let rate_limit = self
+            .repositories
+            .rate_limit_repository()
+            .hit(&key, reset_at)
+            .await?;
+
+        Ok(RateLimitStatus {
+            limit: quota.max_requests,
+            count: rate_limit.count,
+            reset_at: rate_limit.reset_at,
+        })
+    }
+
+    /// Deletes the windows that have already reset, they can never be hit again.
+    pub async fn purge_expired_windows(&self) -> anyhow::Result<u64> {
+        self.repositories
+            .rate_limit_repository()
+            .delete_expired(Utc::now())
+            .await
+    }
+}
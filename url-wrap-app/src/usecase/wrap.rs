@@ -1,7 +1,24 @@
-use crate::model::wrap::{CreateWrap, WrapView};
+use crate::model::access_event::{
+    AccessClient, AccessEventListView, AccessStatsView, SearchAccessEvents,
+};
+use crate::model::credential::{CreateCredential, CredentialView};
+use crate::model::wrap::{
+    AuthorizeWrap, CreateWrap, RegisteredWrapView, SearchWraps, UpdateWrap, WrapListView, WrapView,
+};
 use chrono::{Duration, Utc};
 use std::sync::Arc;
+use tracing::error;
 use url_wrap_adapter::modules::RepositoriesModuleExt;
+use url_wrap_kernel::model::access_event::stats::AccessStatsQuery;
+use url_wrap_kernel::model::access_event::{AccessEventListQuery, AccessOutcome, NewAccessEvent};
+use url_wrap_kernel::model::wrap::alias::WrapRef;
+use url_wrap_kernel::model::wrap::credential::{NewWrapCredential, VerifiedWrap, WrapCredential};
+use url_wrap_kernel::model::wrap::error::WrapError;
+use url_wrap_kernel::model::wrap::payload::WrapPayloadKind;
+use url_wrap_kernel::model::wrap::query::WrapListQuery;
+use url_wrap_kernel::model::wrap::{NewWrap, Wrap, WrapUpdate};
+use url_wrap_kernel::model::Id;
+use url_wrap_kernel::repository::access_log::AccessLogRepository;
 use url_wrap_kernel::repository::wrap::WrapRepository;
 
 pub struct WrapUseCase<R: RepositoriesModuleExt> {
@@ -13,45 +30,329 @@ impl<R: RepositoriesModuleExt> WrapUseCase<R> {
         Self { repositories }
     }
 
-    pub async fn get_wrap(&self, id: String) -> anyhow::Result<Option<WrapView>> {
+    pub async fn get_wrap(&self, id: String) -> Result<WrapView, WrapError> {
         let res = self
             .repositories
             .wrap_repository()
-            .get(&id.try_into()?)
+            .get(&self.resolve_id(id).await?)
             .await?;
         match res {
-            Some(wrap) => Ok(Some(wrap.into())),
-            None => Ok(None),
+            Some(wrap) => Ok(wrap.into()),
+            None => Err(WrapError::NotFound),
         }
     }
 
-    pub async fn register_wrap(&self, source: CreateWrap) -> anyhow::Result<WrapView> {
+    pub async fn register_wrap(&self, source: CreateWrap) -> Result<RegisteredWrapView, WrapError> {
+        let new_wrap: NewWrap = source.try_into()?;
+        let management_token = new_wrap.management_token.0.clone();
+        let totp_uri = new_wrap.totp_provisioning_uri();
+
+        let wrap = self.repositories.wrap_repository().insert(new_wrap).await?;
+        Ok(RegisteredWrapView {
+            wrap: wrap.into(),
+            management_token,
+            totp_uri,
+        })
+    }
+
+    pub async fn list_wraps(
+        &self,
+        owner_id: String,
+        source: SearchWraps,
+    ) -> Result<WrapListView, WrapError> {
+        let query = WrapListQuery::new(
+            owner_id.try_into().map_err(WrapError::Corrupted)?,
+            parse_cursor(source.cursor)?,
+            source.limit,
+            source.status,
+            source.comment,
+            Utc::now(),
+        );
+
+        let page = self.repositories.wrap_repository().list(&query).await?;
+        Ok(page.into())
+    }
+
+    pub async fn update_wrap(
+        &self,
+        id: String,
+        management_token: String,
+        source: UpdateWrap,
+    ) -> Result<WrapView, WrapError> {
+        let id = self.resolve_id(id).await?;
+        let payload_kind = source.payload_kind();
+        let update: WrapUpdate = source.try_into()?;
+
+        if update.password.is_some() || update.redirect_url.is_some() {
+            // the token is checked first, so its absence does not reveal the password policy
+            let wrap = self
+                .repositories
+                .wrap_repository()
+                .get_managed(&id, &management_token)
+                .await?;
+            if let Some(password) = &update.password {
+                wrap.auth_type.validate_password(password)?;
+            }
+            if let (Some(payload_kind), Some(payload)) = (payload_kind, &update.redirect_url) {
+                match (wrap.payload_kind, payload_kind) {
+                    (WrapPayloadKind::Url, WrapPayloadKind::Text) => {
+                        return Err(WrapError::InvalidInput(
+                            "`text` cannot be set on URL wraps.".to_string(),
+                        ))
+                    }
+                    (WrapPayloadKind::Text, WrapPayloadKind::Url) => {
+                        return Err(WrapError::InvalidInput(
+                            "`redirectUrl` cannot be set on text wraps.".to_string(),
+                        ))
+                    }
+                    _ => payload_kind.validate(payload)?,
+                }
+            }
+            // the URL is sealed with a key derived from the password
+            if wrap.zero_knowledge && update.password.is_some() != update.redirect_url.is_some() {
+                return Err(WrapError::InvalidInput(
+                    "Zero-knowledge wraps need `password` changed together with `redirectUrl` or `text`."
+                        .to_string(),
+                ));
+            }
+        }
+
         let wrap = self
             .repositories
             .wrap_repository()
-            .insert(source.try_into()?)
+            .update(&id, &management_token, update)
             .await?;
         Ok(wrap.into())
     }
 
+    pub async fn list_credentials(
+        &self,
+        id: String,
+        management_token: String,
+    ) -> Result<Vec<CredentialView>, WrapError> {
+        let credentials = self
+            .repositories
+            .wrap_repository()
+            .list_credentials(&self.resolve_id(id).await?, &management_token)
+            .await?;
+        Ok(credentials.into_iter().map(CredentialView::from).collect())
+    }
+
+    /// Adds a named credential, checked against the password policy of the wrap.
+    pub async fn add_credential(
+        &self,
+        id: String,
+        management_token: String,
+        source: CreateCredential,
+    ) -> Result<CredentialView, WrapError> {
+        let id = self.resolve_id(id).await?;
+        let new_credential: NewWrapCredential = source.try_into()?;
+
+        // the token is checked first, so its absence does not reveal the password policy
+        let wrap = self
+            .repositories
+            .wrap_repository()
+            .get_managed(&id, &management_token)
+            .await?;
+        // the URL can only be opened with the primary password
+        if wrap.zero_knowledge {
+            return Err(WrapError::InvalidInput(
+                "Zero-knowledge wraps cannot have credentials.".to_string(),
+            ));
+        }
+        wrap.auth_type.validate_password(&new_credential.password)?;
+
+        let credential = self
+            .repositories
+            .wrap_repository()
+            .add_credential(&id, &management_token, new_credential)
+            .await?;
+        Ok(credential.into())
+    }
+
+    pub async fn revoke_credential(
+        &self,
+        id: String,
+        management_token: String,
+        credential_id: String,
+    ) -> Result<(), WrapError> {
+        let id = self.resolve_id(id).await?;
+        let credential_id = Id::try_from(credential_id).map_err(|_| WrapError::NotFound)?;
+
+        self.repositories
+            .wrap_repository()
+            .revoke_credential(&id, &management_token, &credential_id)
+            .await
+    }
+
+    pub async fn delete_wrap(&self, id: String, management_token: String) -> Result<(), WrapError> {
+        self.repositories
+            .wrap_repository()
+            .delete(&self.resolve_id(id).await?, &management_token)
+            .await
+    }
+
+    /// Lists the access events of a wrap. Wraps of other owners are reported as not found.
+    pub async fn list_wrap_events(
+        &self,
+        owner_id: String,
+        id: String,
+        source: SearchAccessEvents,
+    ) -> Result<AccessEventListView, WrapError> {
+        let id = self.owned_wrap_id(&owner_id, id).await?;
+
+        let query = AccessEventListQuery::new(id, parse_cursor(source.cursor)?, source.limit);
+        let page = self
+            .repositories
+            .access_log_repository()
+            .list(&query)
+            .await
+            .map_err(WrapError::Infrastructure)?;
+        Ok(page.into())
+    }
+
+    /// Aggregates the access events of a wrap. Wraps of other owners are reported as not found.
+    pub async fn get_wrap_stats(
+        &self,
+        owner_id: String,
+        id: String,
+    ) -> Result<AccessStatsView, WrapError> {
+        let id = self.owned_wrap_id(&owner_id, id).await?;
+
+        let query = AccessStatsQuery::new(id, Utc::now());
+        let stats = self
+            .repositories
+            .access_log_repository()
+            .stats(&query)
+            .await
+            .map_err(WrapError::Infrastructure)?;
+        Ok(stats.into())
+    }
+
+    /// Deletes the wraps that expired more than `grace` ago and returns how many.
+    pub async fn purge_expired_wraps(&self, grace: Duration) -> Result<u64, WrapError> {
+        self.repositories
+            .wrap_repository()
+            .delete_expired(Utc::now() - grace)
+            .await
+    }
+
+    /// Verifies the password and records the attempt in the access log, whatever its outcome.
     pub async fn verify_wrap(
         &self,
         id: String,
-        password: String,
-    ) -> anyhow::Result<Option<WrapView>> {
+        source: AuthorizeWrap,
+        client: AccessClient,
+    ) -> Result<WrapView, WrapError> {
+        // events are listed by wrap id, so an alias is recorded as the id it resolves to
+        let (wrap_id, res) = match self.resolve_id(id.clone()).await {
+            Ok(wrap_id) => {
+                let res = self.authorize_wrap(&wrap_id, source).await;
+                (wrap_id.value.to_string(), res)
+            }
+            Err(err) => (id, Err(err)),
+        };
+        let credential_id = match &res {
+            Ok(verified) => verified.credential_id.as_ref(),
+            Err(_) => None,
+        };
+
+        let event = NewAccessEvent::new(
+            Id::gen(),
+            wrap_id,
+            AccessOutcome::of(&res),
+            credential_id.map(|v| v.value.to_string()),
+            client.ip,
+            client.user_agent,
+            Utc::now(),
+        );
+        // a failure to record must not change the answer given to the client
+        if let Err(err) = self
+            .repositories
+            .access_log_repository()
+            .insert(event)
+            .await
+        {
+            error!("Could not record access event: {:?}", err);
+        }
+        res.map(|verified| verified.wrap.into())
+    }
+
+    /// Resolves a wrap id or alias to the id of the wrap.
+    async fn resolve_id(&self, id: String) -> Result<Id<Wrap>, WrapError> {
+        match WrapRef::try_from(id)? {
+            WrapRef::Id(id) => Ok(id),
+            WrapRef::Alias(alias) => self
+                .repositories
+                .wrap_repository()
+                .resolve_alias(&alias)
+                .await?
+                .ok_or(WrapError::NotFound),
+        }
+    }
+
+    async fn owned_wrap_id(&self, owner_id: &str, id: String) -> Result<Id<Wrap>, WrapError> {
+        let id = self.resolve_id(id).await?;
+        let wrap = self
+            .repositories
+            .wrap_repository()
+            .get(&id)
+            .await?
+            .ok_or(WrapError::NotFound)?;
+        let owned = wrap
+            .owner_id
+            .is_some_and(|v| v.value.to_string() == owner_id);
+        if !owned {
+            return Err(WrapError::NotFound);
+        }
+        Ok(id)
+    }
+
+    async fn authorize_wrap(
+        &self,
+        id: &Id<Wrap>,
+        source: AuthorizeWrap,
+    ) -> Result<VerifiedWrap, WrapError> {
         let now = Utc::now();
+        let credential_id: Option<Id<WrapCredential>> = source
+            .credential_id
+            .map(Id::try_from)
+            .transpose()
+            .map_err(|_| WrapError::InvalidInput("`credentialId` is invalid.".to_string()))?;
 
+        // refused before the secret is checked, so they cost no attempts, uses or TOTP steps
         let wrap = self
             .repositories
             .wrap_repository()
-            .find(&id.try_into()?, &password)
+            .get(id)
+            .await?
+            .ok_or(WrapError::NotFound)?;
+        if wrap.expiration_at.is_some_and(|v| now > v) {
+            return Err(WrapError::Expired);
+        }
+        if wrap.remaining_views == Some(0) {
+            return Err(WrapError::Consumed);
+        }
+
+        let verified = self
+            .repositories
+            .wrap_repository()
+            .find(id, &source.password, credential_id.as_ref())
             .await?;
 
-        let duration: Duration = now - wrap.expiration_at;
-        if duration.num_seconds() > 0 {
-            Ok(None)
-        } else {
-            Ok(Some(wrap.into()))
+        if verified.wrap.max_views.is_none() {
+            return Ok(verified);
         }
+        let mut wrap = self.repositories.wrap_repository().consume_view(id).await?;
+        // only the verified wrap carries the URL of zero-knowledge wraps
+        wrap.redirect_url = verified.wrap.redirect_url;
+        Ok(VerifiedWrap::new(wrap, verified.credential_id))
     }
 }
+
+fn parse_cursor<T>(cursor: Option<String>) -> Result<Option<Id<T>>, WrapError> {
+    cursor
+        .map(Id::try_from)
+        .transpose()
+        .map_err(|_| WrapError::InvalidInput("`cursor` is invalid.".to_string()))
+}
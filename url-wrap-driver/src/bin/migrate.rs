@@ -0,0 +1,12 @@
+use url_wrap_driver::module::Modules;
+use url_wrap_driver::startup::init_app;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    init_app();
+
+    Modules::migrate().await?;
+    tracing::info!("Database is up to date.");
+
+    Ok(())
+}
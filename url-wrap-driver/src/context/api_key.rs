@@ -0,0 +1,17 @@
+use url_wrap_app::model::owner::OwnerView;
+
+pub const ADMIN_API_KEY: &str = "ADMIN_API_KEY";
+pub const WRAP_REQUIRE_API_KEY: &str = "WRAP_REQUIRE_API_KEY";
+
+/// Owner authenticated by the API key in the `Authorization: Bearer` header.
+#[derive(Debug)]
+pub struct AuthenticatedOwner(pub OwnerView);
+
+/// Like `AuthenticatedOwner`, but anonymous requests are let through as `None`
+/// unless `WRAP_REQUIRE_API_KEY` is `true`. An invalid key is always rejected.
+#[derive(Debug)]
+pub struct OptionalOwner(pub Option<OwnerView>);
+
+/// Request authorized by the `ADMIN_API_KEY` bearer token.
+#[derive(Debug)]
+pub struct AdminAuthorization;
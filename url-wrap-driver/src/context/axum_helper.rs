@@ -1,13 +1,27 @@
+use crate::context::api_key::{
+    AdminAuthorization, AuthenticatedOwner, OptionalOwner, ADMIN_API_KEY, WRAP_REQUIRE_API_KEY,
+};
+use crate::context::client::{ClientInfo, TrustedProxies};
 use crate::context::errors::AppError;
-use crate::context::validate::ValidatedRequest;
+use crate::context::management_token::{ManagementTokenHeader, MANAGEMENT_TOKEN_HEADER};
+use crate::context::rate_limit::client_ip;
+use crate::context::validate::{ValidatedQuery, ValidatedRequest};
+use crate::module::{Modules, ModulesExt};
 use axum::async_trait;
-use axum::extract::{FromRequest, RequestParts};
-use axum::http::StatusCode;
+use axum::extract::{FromRequest, Query, RequestParts};
+use axum::http::{header, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::{BoxError, Json};
+use chrono::Utc;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::convert::Infallible;
+use std::env;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
 use tracing::log::error;
+use url_wrap_app::model::owner::OwnerView;
+use url_wrap_kernel::model::wrap::error::WrapError;
 use validator::Validate;
 
 #[derive(Serialize)]
@@ -21,6 +35,16 @@ impl JsonErrorResponse {
     pub(crate) fn new(error_code: String, errors: Vec<String>) -> Self {
         Self { error_code, errors }
     }
+
+    /// Builds a response that tells the client when to retry via the `Retry-After` header.
+    pub(crate) fn with_retry_after(self, status: StatusCode, retry_after: i64) -> Response {
+        (
+            status,
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            Json(self),
+        )
+            .into_response()
+    }
 }
 
 impl IntoResponse for AppError {
@@ -32,7 +56,7 @@ impl IntoResponse for AppError {
                 let mut messages: Vec<String> = Vec::new();
                 let errors = validation_errors.field_errors();
                 for (_, v) in errors.iter() {
-                    for validation_error in v.to_owned() {
+                    for validation_error in v.iter() {
                         if let Some(msg) = validation_error.clone().message {
                             messages.push(msg.to_string());
                         }
@@ -46,6 +70,7 @@ impl IntoResponse for AppError {
                         messages,
                     )),
                 )
+                    .into_response()
             }
             AppError::JsonRejection(rejection) => {
                 error!("{:?}", rejection);
@@ -58,9 +83,76 @@ impl IntoResponse for AppError {
                         messages,
                     )),
                 )
+                    .into_response()
+            }
+            AppError::QueryRejection(rejection) => {
+                error!("{:?}", rejection);
+
+                let messages = vec![rejection.to_string()];
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(JsonErrorResponse::new(
+                        "invalid_request".to_string(),
+                        messages,
+                    )),
+                )
+                    .into_response()
+            }
+            AppError::Wrap(wrap_error) => wrap_error_response(wrap_error),
+            AppError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                Json(JsonErrorResponse::new(
+                    "unauthorized".to_string(),
+                    vec![self.to_string()],
+                )),
+            )
+                .into_response(),
+            AppError::Internal(err) => {
+                error!("Unexpected error: {:?}", err);
+
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(JsonErrorResponse::new(
+                        "internal_error".to_string(),
+                        vec!["Internal error.".to_string()],
+                    )),
+                )
+                    .into_response()
             }
         }
-        .into_response()
+    }
+}
+
+fn wrap_error_response(wrap_error: WrapError) -> Response {
+    let (status, error_code) = match &wrap_error {
+        WrapError::InvalidInput(_) => (StatusCode::BAD_REQUEST, "invalid_request"),
+        WrapError::NotFound => (StatusCode::NOT_FOUND, "not_found"),
+        WrapError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "authentication_failed"),
+        WrapError::Expired => (StatusCode::FORBIDDEN, "expired"),
+        WrapError::Consumed => (StatusCode::GONE, "consumed"),
+        WrapError::Locked { .. } => (StatusCode::LOCKED, "locked"),
+        WrapError::AliasTaken => (StatusCode::CONFLICT, "alias_taken"),
+        WrapError::Corrupted(_) => (StatusCode::INTERNAL_SERVER_ERROR, "corrupted"),
+        WrapError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        WrapError::Infrastructure(_) => (StatusCode::SERVICE_UNAVAILABLE, "unavailable"),
+    };
+
+    // internal details are logged but never returned to the client
+    let message = match &wrap_error {
+        WrapError::Corrupted(_) | WrapError::Internal(_) | WrapError::Infrastructure(_) => {
+            error!("Unexpected error: {:?}", wrap_error);
+            "Internal error.".to_string()
+        }
+        _ => {
+            error!("{}", wrap_error);
+            wrap_error.to_string()
+        }
+    };
+
+    let json = JsonErrorResponse::new(error_code.to_string(), vec![message]);
+    match wrap_error.retry_after(Utc::now()) {
+        Some(retry_after) => json.with_retry_after(status, retry_after),
+        None => (status, Json(json)).into_response(),
     }
 }
 
@@ -80,3 +172,149 @@ where
         Ok(ValidatedRequest(value))
     }
 }
+
+#[async_trait]
+impl<T, B> FromRequest<B> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    B: Send,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request(req).await?;
+        value.validate()?;
+        Ok(ValidatedQuery(value))
+    }
+}
+
+#[async_trait]
+impl<B> FromRequest<B> for ManagementTokenHeader
+where
+    B: Send,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        req.headers()
+            .get(MANAGEMENT_TOKEN_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| !v.is_empty())
+            .map(|v| ManagementTokenHeader(v.to_string()))
+            .ok_or(AppError::Wrap(WrapError::InvalidCredentials))
+    }
+}
+
+#[async_trait]
+impl<B> FromRequest<B> for AuthenticatedOwner
+where
+    B: Send,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        match authenticate_owner(api_key_credential(req)).await? {
+            Some(owner) => Ok(AuthenticatedOwner(owner)),
+            None => Err(AppError::Unauthorized),
+        }
+    }
+}
+
+#[async_trait]
+impl<B> FromRequest<B> for OptionalOwner
+where
+    B: Send,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let owner = authenticate_owner(api_key_credential(req)).await?;
+
+        let required = env::var(WRAP_REQUIRE_API_KEY).is_ok_and(|v| v == "true");
+        if owner.is_none() && required {
+            return Err(AppError::Unauthorized);
+        }
+        Ok(OptionalOwner(owner))
+    }
+}
+
+#[async_trait]
+impl<B> FromRequest<B> for AdminAuthorization
+where
+    B: Send,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        // owner registration is disabled unless an admin key is configured
+        let admin_api_key = env::var(ADMIN_API_KEY).unwrap_or_default();
+        match bearer_token(req) {
+            Some(token)
+                if !admin_api_key.is_empty()
+                    && bool::from(token.as_bytes().ct_eq(admin_api_key.as_bytes())) =>
+            {
+                Ok(AdminAuthorization)
+            }
+            _ => Err(AppError::Unauthorized),
+        }
+    }
+}
+
+#[async_trait]
+impl<B> FromRequest<B> for ClientInfo
+where
+    B: Send,
+{
+    type Rejection = Infallible;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let trusted_proxies = req
+            .extensions()
+            .get::<TrustedProxies>()
+            .map(|TrustedProxies(v)| v.clone())
+            .unwrap_or_default();
+        let ip = client_ip(req.headers(), req.extensions(), &trusted_proxies);
+        let user_agent = req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| !v.is_empty())
+            .map(str::to_string);
+        Ok(ClientInfo { ip, user_agent })
+    }
+}
+
+/// The bearer token and the modules to check it with, taken out of the request
+/// so that the authentication future does not borrow it.
+fn api_key_credential<B>(req: &RequestParts<B>) -> Option<(String, Arc<Modules>)> {
+    let api_key = bearer_token(req)?.to_string();
+    let modules = req
+        .extensions()
+        .get::<Arc<Modules>>()
+        .expect("Modules extension is missing.")
+        .clone();
+    Some((api_key, modules))
+}
+
+/// Returns `None` without a bearer token and rejects a token that matches no API key.
+async fn authenticate_owner(
+    credential: Option<(String, Arc<Modules>)>,
+) -> Result<Option<OwnerView>, AppError> {
+    let (api_key, modules) = match credential {
+        Some(credential) => credential,
+        None => return Ok(None),
+    };
+
+    match modules.owner_use_case().authenticate(&api_key).await? {
+        Some(owner) => Ok(Some(owner)),
+        None => Err(AppError::Unauthorized),
+    }
+}
+
+fn bearer_token<B>(req: &RequestParts<B>) -> Option<&str> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .filter(|v| !v.is_empty())
+}
@@ -0,0 +1,14 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Address and user agent of the caller. The address honours `X-Forwarded-For`
+/// the same way the rate limiter does.
+#[derive(Debug)]
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+/// Proxies whose `X-Forwarded-For` header is trusted, shared with the handlers as an extension.
+#[derive(Clone)]
+pub struct TrustedProxies(pub Arc<Vec<IpAddr>>);
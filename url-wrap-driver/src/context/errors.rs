@@ -1,4 +1,5 @@
 use thiserror::Error;
+use url_wrap_kernel::model::wrap::error::WrapError;
 
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -6,4 +7,12 @@ pub enum AppError {
     Validation(#[from] validator::ValidationErrors),
     #[error(transparent)]
     JsonRejection(#[from] axum::extract::rejection::JsonRejection),
+    #[error(transparent)]
+    QueryRejection(#[from] axum::extract::rejection::QueryRejection),
+    #[error(transparent)]
+    Wrap(#[from] WrapError),
+    #[error("API key is missing or invalid.")]
+    Unauthorized,
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
 }
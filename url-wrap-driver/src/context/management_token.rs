@@ -0,0 +1,5 @@
+pub const MANAGEMENT_TOKEN_HEADER: &str = "x-management-token";
+
+/// Token from the `X-Management-Token` header, issued when the wrap was created.
+#[derive(Debug)]
+pub struct ManagementTokenHeader(pub String);
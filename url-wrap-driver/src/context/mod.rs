@@ -1,3 +1,7 @@
+pub mod api_key;
 pub mod axum_helper;
+pub mod client;
 pub mod errors;
+pub mod management_token;
+pub mod rate_limit;
 pub mod validate;
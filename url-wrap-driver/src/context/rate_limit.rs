@@ -0,0 +1,208 @@
+use crate::context::axum_helper::JsonErrorResponse;
+use crate::module::{Modules, ModulesExt};
+use axum::extract::ConnectInfo;
+use axum::http::{Extensions, HeaderMap, HeaderValue, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::Utc;
+use std::env;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tracing::error;
+use tracing::log::warn;
+use url_wrap_app::model::rate_limit::RateLimitQuota;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_RATELIMIT_REMAINING: &str = "x-ratelimit-remaining";
+
+/// Budget of one rate limited route.
+#[derive(Clone)]
+pub struct RateLimitBudget {
+    bucket: &'static str,
+    quota: RateLimitQuota,
+    trusted_proxies: Arc<Vec<IpAddr>>,
+}
+
+impl RateLimitBudget {
+    /// Reads the quota from `env_key` formatted as `<max requests>/<window seconds>`.
+    pub fn init(
+        bucket: &'static str,
+        env_key: &str,
+        default: RateLimitQuota,
+        trusted_proxies: Arc<Vec<IpAddr>>,
+    ) -> Self {
+        let quota = match env::var(env_key) {
+            Ok(value) => {
+                parse_quota(&value).unwrap_or_else(|| panic!("{} is invalid value.", env_key))
+            }
+            Err(_) => default,
+        };
+
+        Self {
+            bucket,
+            quota,
+            trusted_proxies,
+        }
+    }
+}
+
+/// Proxies whose `X-Forwarded-For` header is trusted, from `RATE_LIMIT_TRUSTED_PROXIES`.
+pub fn init_trusted_proxies() -> Arc<Vec<IpAddr>> {
+    let proxies = env::var("RATE_LIMIT_TRUSTED_PROXIES")
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(|v| {
+                    v.parse::<IpAddr>()
+                        .expect("RATE_LIMIT_TRUSTED_PROXIES is invalid value.")
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Arc::new(proxies)
+}
+
+pub async fn rate_limit<B>(req: Request<B>, next: Next<B>, budget: RateLimitBudget) -> Response {
+    let client = match client_ip(req.headers(), req.extensions(), &budget.trusted_proxies) {
+        Some(client) => client,
+        None => {
+            warn!("Client address is unknown, rate limit is skipped.");
+            return next.run(req).await;
+        }
+    };
+    let modules = match req.extensions().get::<Arc<Modules>>() {
+        Some(modules) => modules.clone(),
+        None => return next.run(req).await,
+    };
+
+    let res = modules
+        .rate_limit_use_case()
+        .hit(budget.bucket, &client.to_string(), &budget.quota)
+        .await;
+    let status = match res {
+        Ok(status) => status,
+        Err(err) => {
+            // the store being unavailable must not take the wrap endpoints down with it
+            error!("Unexpected error: {:?}", err);
+            return next.run(req).await;
+        }
+    };
+
+    let mut res = if status.is_exceeded() {
+        warn!("Rate limit exceeded: {} {}", budget.bucket, client);
+        let retry_after = status.retry_after(Utc::now());
+        let errors = vec!["Too many requests.".to_string()];
+        let json = JsonErrorResponse::new("too_many_requests".to_string(), errors);
+        json.with_retry_after(StatusCode::TOO_MANY_REQUESTS, retry_after)
+    } else {
+        next.run(req).await.into_response()
+    };
+    res.headers_mut()
+        .insert(X_RATELIMIT_REMAINING, HeaderValue::from(status.remaining()));
+    res
+}
+
+/// Resolves the client address. `X-Forwarded-For` is only honoured when the peer is a
+/// trusted proxy, and then the right-most untrusted hop is taken as the client.
+pub(crate) fn client_ip(
+    headers: &HeaderMap,
+    extensions: &Extensions,
+    trusted_proxies: &[IpAddr],
+) -> Option<IpAddr> {
+    let peer = extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())?;
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    let forwarded: Vec<IpAddr> = headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|v| v.trim().parse::<IpAddr>().ok())
+        .collect();
+
+    forwarded
+        .iter()
+        .rev()
+        .find(|ip| !trusted_proxies.contains(ip))
+        .or_else(|| forwarded.first())
+        .copied()
+        .or(Some(peer))
+}
+
+fn parse_quota(value: &str) -> Option<RateLimitQuota> {
+    let (max_requests, window_seconds) = value.split_once('/')?;
+    let max_requests = max_requests.trim().parse::<u64>().ok()?;
+    let window_seconds = window_seconds.trim().parse::<i64>().ok()?;
+    if window_seconds <= 0 {
+        return None;
+    }
+
+    Some(RateLimitQuota::new(max_requests, window_seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROXY: &str = "10.0.0.1";
+
+    fn client(peer: &str, forwarded_for: Option<&str>, trusted_proxies: &[&str]) -> IpAddr {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = forwarded_for {
+            headers.insert(X_FORWARDED_FOR, value.parse().unwrap());
+        }
+        let mut extensions = Extensions::new();
+        let peer = SocketAddr::new(peer.parse().unwrap(), 443);
+        extensions.insert(ConnectInfo(peer));
+        let trusted_proxies: Vec<IpAddr> =
+            trusted_proxies.iter().map(|v| v.parse().unwrap()).collect();
+
+        client_ip(&headers, &extensions, &trusted_proxies).unwrap()
+    }
+
+    #[test]
+    fn header_of_untrusted_peer_is_ignored() {
+        let ip = client("203.0.113.7", Some("198.51.100.1"), &[PROXY]);
+        assert_eq!(ip, "203.0.113.7".parse::<IpAddr>().unwrap());
+
+        let ip = client("203.0.113.7", Some("198.51.100.1"), &[]);
+        assert_eq!(ip, "203.0.113.7".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn right_most_untrusted_hop_is_the_client() {
+        // the left-most entries are whatever the client chose to send
+        let ip = client(
+            PROXY,
+            Some("1.2.3.4, 198.51.100.1, 10.0.0.2"),
+            &[PROXY, "10.0.0.2"],
+        );
+        assert_eq!(ip, "198.51.100.1".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn trusted_proxy_without_header_is_the_client() {
+        let ip = client(PROXY, None, &[PROXY]);
+        assert_eq!(ip, PROXY.parse::<IpAddr>().unwrap());
+
+        let ip = client(PROXY, Some("not an address"), &[PROXY]);
+        assert_eq!(ip, PROXY.parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn parses_quota() {
+        let quota = parse_quota("5/60").unwrap();
+        assert_eq!((quota.max_requests, quota.window_seconds), (5, 60));
+
+        assert!(parse_quota("5").is_none());
+        assert!(parse_quota("5/0").is_none());
+        assert!(parse_quota("x/60").is_none());
+    }
+}
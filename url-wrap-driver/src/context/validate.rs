@@ -1,2 +1,5 @@
 #[derive(Debug)]
 pub struct ValidatedRequest<T>(pub T);
+
+#[derive(Debug)]
+pub struct ValidatedQuery<T>(pub T);
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use url_wrap_app::model::access_event::{
+    AccessBucketView, AccessEventListView, AccessEventView, AccessStatsView, SearchAccessEvents,
+};
+use validator::Validate;
+
+const DEFAULT_LIST_LIMIT: u32 = 50;
+
+#[derive(Debug, Serialize)]
+pub struct JsonAccessEventView {
+    pub id: String,
+    pub outcome: String,
+    pub credential_id: Option<String>,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub occurred_at: String,
+}
+
+impl From<AccessEventView> for JsonAccessEventView {
+    fn from(av: AccessEventView) -> Self {
+        Self {
+            id: av.id,
+            outcome: av.outcome,
+            credential_id: av.credential_id,
+            client_ip: av.client_ip,
+            user_agent: av.user_agent,
+            occurred_at: av.occurred_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonAccessEventListView {
+    pub items: Vec<JsonAccessEventView>,
+    /// `nextCursor` like the wrap listing, so that both are paged the same way.
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+}
+
+impl From<AccessEventListView> for JsonAccessEventListView {
+    fn from(alv: AccessEventListView) -> Self {
+        Self {
+            items: alv
+                .events
+                .into_iter()
+                .map(JsonAccessEventView::from)
+                .collect(),
+            next_cursor: alv.next_cursor,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonAccessBucketView {
+    pub start: String,
+    pub opens: u64,
+    pub failures: u64,
+}
+
+impl From<AccessBucketView> for JsonAccessBucketView {
+    fn from(bv: AccessBucketView) -> Self {
+        Self {
+            start: bv.start.to_rfc3339(),
+            opens: bv.opens,
+            failures: bv.failures,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonAccessStatsView {
+    pub total_opens: u64,
+    pub unique_opens: u64,
+    pub failed_attempts: u64,
+    pub first_access_at: Option<String>,
+    pub last_access_at: Option<String>,
+    pub hourly: Vec<JsonAccessBucketView>,
+    pub daily: Vec<JsonAccessBucketView>,
+}
+
+impl From<AccessStatsView> for JsonAccessStatsView {
+    fn from(sv: AccessStatsView) -> Self {
+        Self {
+            total_opens: sv.total_opens,
+            unique_opens: sv.unique_opens,
+            failed_attempts: sv.failed_attempts,
+            first_access_at: sv.first_access_at.map(|v| v.to_rfc3339()),
+            last_access_at: sv.last_access_at.map(|v| v.to_rfc3339()),
+            hourly: sv
+                .hourly
+                .into_iter()
+                .map(JsonAccessBucketView::from)
+                .collect(),
+            daily: sv
+                .daily
+                .into_iter()
+                .map(JsonAccessBucketView::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct JsonListAccessEventsQuery {
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = 100, message = "`limit` is minimum 1 and maximum 100."))]
+    pub limit: Option<u32>,
+}
+
+impl From<JsonListAccessEventsQuery> for SearchAccessEvents {
+    fn from(jq: JsonListAccessEventsQuery) -> Self {
+        SearchAccessEvents::new(
+            jq.cursor.filter(|v| !v.is_empty()),
+            jq.limit.unwrap_or(DEFAULT_LIST_LIMIT),
+        )
+    }
+}
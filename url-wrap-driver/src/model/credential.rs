@@ -0,0 +1,86 @@
+use crate::model::wrap::{parse_expiration, validation_error, JsonExpirationAt};
+use serde::{Deserialize, Serialize};
+use url_wrap_app::model::credential::{CreateCredential, CredentialView};
+use url_wrap_app::model::wrap::WrapExpiration;
+use validator::{Validate, ValidationError};
+
+#[derive(Debug, Serialize)]
+pub struct JsonCredentialView {
+    pub id: String,
+    pub name: String,
+    pub expiration_at: Option<String>,
+    pub max_uses: Option<u32>,
+    pub uses: u32,
+    pub created_at: String,
+}
+
+impl From<CredentialView> for JsonCredentialView {
+    fn from(cv: CredentialView) -> Self {
+        Self {
+            id: cv.id,
+            name: cv.name,
+            expiration_at: cv.expiration_at.map(|v| v.to_rfc3339()),
+            max_uses: cv.max_uses,
+            uses: cv.uses,
+            created_at: cv.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonCredentialListView {
+    pub items: Vec<JsonCredentialView>,
+}
+
+impl From<Vec<CredentialView>> for JsonCredentialListView {
+    fn from(cvs: Vec<CredentialView>) -> Self {
+        Self {
+            items: cvs.into_iter().map(JsonCredentialView::from).collect(),
+        }
+    }
+}
+
+/// Credentials without `expirationAt` or `expiresIn` never expire.
+#[derive(Deserialize, Debug, Validate)]
+#[validate(schema(function = "validate_credential_expiration"))]
+pub struct JsonCreateCredential {
+    /// Checked by the kernel, see `NewWrapCredential`.
+    #[validate(required(message = "`name` is null."))]
+    pub name: Option<String>,
+    #[validate(
+        length(min = 1, message = "`password` is empty."),
+        required(message = "`password` is null.")
+    )]
+    pub password: Option<String>,
+    #[serde(rename = "expirationAt")]
+    pub expiration_at: Option<JsonExpirationAt>,
+    #[serde(rename = "expiresIn")]
+    pub expires_in: Option<String>,
+    #[validate(range(min = 1, message = "`maxUses` is minimum 1."))]
+    #[serde(rename = "maxUses")]
+    pub max_uses: Option<u32>,
+}
+
+impl JsonCreateCredential {
+    fn expiration(&self) -> Result<Option<WrapExpiration>, &'static str> {
+        parse_expiration(&self.expiration_at, &self.expires_in, false)
+    }
+}
+
+fn validate_credential_expiration(jc: &JsonCreateCredential) -> Result<(), ValidationError> {
+    jc.expiration()
+        .map(|_| ())
+        .map_err(|e| validation_error("expiration", e))
+}
+
+impl From<JsonCreateCredential> for CreateCredential {
+    fn from(jc: JsonCreateCredential) -> Self {
+        let expiration = jc.expiration().unwrap();
+        CreateCredential::new(
+            jc.name.unwrap(),
+            jc.password.unwrap(),
+            expiration,
+            jc.max_uses,
+        )
+    }
+}
@@ -0,0 +1,133 @@
+use chrono::Duration;
+
+/// Parses a relative duration written either as a shorthand such as `7d` or `1h30m`,
+/// or as an ISO 8601 duration such as `P7D` or `PT1H30M`.
+/// Years and months are not accepted because their length is ambiguous.
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let seconds = match value.strip_prefix('P') {
+        Some(iso8601) => parse_iso8601(iso8601)?,
+        None if value.is_empty() => return None,
+        None => sum_units(value, |unit| match unit {
+            'w' => Some(604_800),
+            'd' => Some(86_400),
+            'h' => Some(3_600),
+            'm' => Some(60),
+            's' => Some(1),
+            _ => None,
+        })?,
+    };
+    Duration::try_seconds(seconds)
+}
+
+fn parse_iso8601(value: &str) -> Option<i64> {
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) if !time.is_empty() => (date, time),
+        Some(_) => return None,
+        None => (value, ""),
+    };
+    if date.is_empty() && time.is_empty() {
+        return None;
+    }
+
+    let date = sum_units(date, |unit| match unit {
+        'W' => Some(604_800),
+        'D' => Some(86_400),
+        _ => None,
+    })?;
+    let time = sum_units(time, |unit| match unit {
+        'H' => Some(3_600),
+        'M' => Some(60),
+        'S' => Some(1),
+        _ => None,
+    })?;
+    date.checked_add(time)
+}
+
+/// Sums `<number><unit>` pairs such as `1h30m`; an empty string is zero.
+fn sum_units(value: &str, unit_seconds: impl Fn(char) -> Option<i64>) -> Option<i64> {
+    let mut total: i64 = 0;
+    let mut number: Option<i64> = None;
+
+    for c in value.chars() {
+        match c.to_digit(10) {
+            Some(digit) => {
+                number = Some(
+                    number
+                        .unwrap_or(0)
+                        .checked_mul(10)?
+                        .checked_add(digit as i64)?,
+                );
+            }
+            None => {
+                let seconds = number.take()?.checked_mul(unit_seconds(c)?)?;
+                total = total.checked_add(seconds)?;
+            }
+        }
+    }
+
+    match number {
+        Some(_) => None,
+        None => Some(total),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_valid_units() {
+        let cases = [
+            ("30s", 30),
+            ("15m", 900),
+            ("2h", 7_200),
+            ("7d", 604_800),
+            ("1w", 604_800),
+            ("1h30m", 5_400),
+            ("1d12h", 129_600),
+            ("PT45S", 45),
+            ("PT1H30M", 5_400),
+            ("P7D", 604_800),
+            ("P2W", 1_209_600),
+            ("P1DT1H", 90_000),
+        ];
+        for (value, seconds) in cases {
+            assert_eq!(
+                parse_duration(value),
+                Some(Duration::seconds(seconds)),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_empty_input() {
+        for value in ["", "P", "PT", "P1DT"] {
+            assert_eq!(parse_duration(value), None, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_suffixes() {
+        for value in [
+            "7", "7x", "1y", "3M", "d", "1h30", "P1Y", "P1M", "PT1D", "P1H", "7 d",
+        ] {
+            assert_eq!(parse_duration(value), None, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        for value in [
+            "99999999999999999999s",
+            "9223372036854775807w",
+            "9223372036854775807s1s",
+            "P9223372036854775807D",
+            "P1DT9223372036854775807S",
+            // fits in i64 seconds but not in a chrono duration
+            "9223372036854776s",
+        ] {
+            assert_eq!(parse_duration(value), None, "{value:?}");
+        }
+    }
+}
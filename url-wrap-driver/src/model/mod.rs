@@ -1 +1,5 @@
+pub mod access_event;
+pub mod credential;
+mod duration;
+pub mod owner;
 pub mod wrap;
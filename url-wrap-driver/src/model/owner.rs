@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use url_wrap_app::model::owner::{CreateOwner, OwnerView, RegisteredOwnerView};
+use validator::Validate;
+
+#[derive(Debug, Serialize)]
+pub struct JsonOwnerView {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+}
+
+impl From<OwnerView> for JsonOwnerView {
+    fn from(ov: OwnerView) -> Self {
+        Self {
+            id: ov.id,
+            name: ov.name,
+            created_at: ov.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonRegisteredOwnerView {
+    #[serde(flatten)]
+    pub owner: JsonOwnerView,
+    pub api_key: String,
+}
+
+impl From<RegisteredOwnerView> for JsonRegisteredOwnerView {
+    fn from(rv: RegisteredOwnerView) -> Self {
+        Self {
+            owner: rv.owner.into(),
+            api_key: rv.api_key,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct JsonCreateOwner {
+    #[validate(
+        length(min = 1, max = 100, message = "`name` is 1 to 100 characters."),
+        required(message = "`name` is null.")
+    )]
+    pub name: Option<String>,
+}
+
+impl From<JsonCreateOwner> for CreateOwner {
+    fn from(jc: JsonCreateOwner) -> Self {
+        CreateOwner::new(jc.name.unwrap())
+    }
+}
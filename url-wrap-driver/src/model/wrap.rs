@@ -1,64 +1,335 @@
+use crate::model::duration::parse_duration;
+use anyhow::anyhow;
+use chrono::{DateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
-use url_wrap_app::model::wrap::{AuthorizeWrap, CreateWrap, WrapView};
-use validator::Validate;
+use url_wrap_app::model::wrap::{
+    AuthorizeWrap, CreateWrap, RegisteredWrapView, SearchWraps, UpdateWrap, WrapExpiration,
+    WrapListView, WrapView,
+};
+use url_wrap_kernel::model::wrap::error::WrapError;
+use url_wrap_kernel::model::wrap::payload::WrapPayloadKind;
+use url_wrap_kernel::model::wrap::query::WrapStatus;
+use validator::{Validate, ValidationError};
 
 const MIN_VALUE: i64 = u32::MIN as i64; // 0
-const MAX_VALUE: i64 = u32::MAX as i64; // 4_294_967_295
+const MAX_VALUE: i64 = 253_402_300_799; // 9999-12-31T23:59:59Z
+const DEFAULT_LIST_LIMIT: u32 = 20;
 
 #[derive(Debug, Serialize)]
 pub struct JsonWrapView {
     pub id: String,
+    pub payload_kind: JsonPayloadKind,
     pub auth_type: u32,
+    pub pin_length: Option<u32>,
     pub comment: String,
-    pub expiration_at: String,
+    pub expiration_at: Option<String>,
+    pub max_views: Option<u32>,
+    pub remaining_views: Option<u32>,
+    pub alias: Option<String>,
+    pub zero_knowledge: bool,
 }
 
 impl From<WrapView> for JsonWrapView {
     fn from(wv: WrapView) -> Self {
         Self {
             id: wv.id,
+            payload_kind: wv.payload_kind.into(),
             auth_type: wv.auth_type,
+            pin_length: wv.pin_length,
             comment: wv.comment,
-            expiration_at: wv.expiration_at.to_rfc3339(),
+            expiration_at: wv.expiration_at.map(|v| v.to_rfc3339()),
+            max_views: wv.max_views,
+            remaining_views: wv.remaining_views,
+            alias: wv.alias,
+            zero_knowledge: wv.zero_knowledge,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonPayloadKind {
+    #[default]
+    Url,
+    Text,
+}
+
+impl From<JsonPayloadKind> for WrapPayloadKind {
+    fn from(kind: JsonPayloadKind) -> Self {
+        match kind {
+            JsonPayloadKind::Url => WrapPayloadKind::Url,
+            JsonPayloadKind::Text => WrapPayloadKind::Text,
+        }
+    }
+}
+
+impl From<WrapPayloadKind> for JsonPayloadKind {
+    fn from(kind: WrapPayloadKind) -> Self {
+        match kind {
+            WrapPayloadKind::Url => JsonPayloadKind::Url,
+            WrapPayloadKind::Text => JsonPayloadKind::Text,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonWrapListView {
+    pub items: Vec<JsonWrapView>,
+    /// Sent as `nextCursor`, the name clients of the listing were given, although the
+    /// items are snake_case like the other views. Passed back as `cursor` for the next page.
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+}
+
+impl From<WrapListView> for JsonWrapListView {
+    fn from(wlv: WrapListView) -> Self {
+        Self {
+            items: wlv.wraps.into_iter().map(JsonWrapView::from).collect(),
+            next_cursor: wlv.next_cursor,
         }
     }
 }
 
 #[derive(Deserialize, Debug, Validate)]
+pub struct JsonListWrapsQuery {
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = 100, message = "`limit` is minimum 1 and maximum 100."))]
+    pub limit: Option<u32>,
+    pub status: Option<JsonWrapStatus>,
+    /// Substring of `comment`, case-insensitive.
+    pub q: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonWrapStatus {
+    Active,
+    Expired,
+}
+
+impl From<JsonListWrapsQuery> for SearchWraps {
+    fn from(jq: JsonListWrapsQuery) -> Self {
+        let status = jq.status.map(|v| match v {
+            JsonWrapStatus::Active => WrapStatus::Active,
+            JsonWrapStatus::Expired => WrapStatus::Expired,
+        });
+        SearchWraps::new(
+            jq.cursor.filter(|v| !v.is_empty()),
+            jq.limit.unwrap_or(DEFAULT_LIST_LIMIT),
+            status,
+            jq.q.filter(|v| !v.is_empty()),
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonRegisteredWrapView {
+    #[serde(flatten)]
+    pub wrap: JsonWrapView,
+    pub management_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub totp_uri: Option<String>,
+}
+
+impl From<RegisteredWrapView> for JsonRegisteredWrapView {
+    fn from(rv: RegisteredWrapView) -> Self {
+        Self {
+            wrap: rv.wrap.into(),
+            management_token: rv.management_token,
+            totp_uri: rv.totp_uri,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Validate)]
+#[validate(schema(function = "validate_create"))]
 pub struct JsonCreateWrap {
-    #[validate(
-        url(message = "`redirectUrl` is invalid URL format."),
-        required(message = "`redirectUrl` is null.")
-    )]
+    #[serde(rename = "payloadKind", default)]
+    pub payload_kind: JsonPayloadKind,
+    /// Required for URL wraps.
+    #[validate(url(message = "`redirectUrl` is invalid URL format."))]
     #[serde(rename = "redirectUrl")]
     pub redirect_url: Option<String>,
-    #[validate(
-        length(min = 1, message = "`password` is empty."),
-        required(message = "`password` is null.")
-    )]
+    /// Required for text wraps, its size is checked by the kernel.
+    pub text: Option<String>,
+    /// Required unless `authType` is TOTP, see `WrapAuthType`.
+    #[validate(length(min = 1, message = "`password` is empty."))]
     pub password: Option<String>,
-    #[validate(range(min = 1, max = 2, message = "`authType` is 1 or 2."))]
+    #[validate(range(min = 1, max = 3, message = "`authType` is 1, 2 or 3."))]
     #[serde(rename = "authType")]
     pub auth_type: i64,
+    /// Checked by the kernel together with `password`, see `WrapAuthType`.
+    #[serde(rename = "pinLength")]
+    pub pin_length: Option<u32>,
     #[validate(required(message = "`comment` is null."))]
     pub comment: Option<String>,
-    #[validate(range(
-        min = "MIN_VALUE",
-        max = "MAX_VALUE",
-        message = "`expirationAt` is minimum 0 and maximum 4294967295."
-    ))]
     #[serde(rename = "expirationAt")]
-    pub expiration_at: i64,
+    pub expiration_at: Option<JsonExpirationAt>,
+    #[serde(rename = "expiresIn")]
+    pub expires_in: Option<String>,
+    #[serde(rename = "neverExpires", default)]
+    pub never_expires: bool,
+    #[validate(range(min = 1, message = "`maxViews` is minimum 1."))]
+    #[serde(rename = "maxViews")]
+    pub max_views: Option<u32>,
+    /// Checked by the kernel, see `WrapAlias`.
+    pub alias: Option<String>,
+    /// The redirect URL can then only be read with the password, not recovered by the server.
+    #[serde(rename = "zeroKnowledge", default)]
+    pub zero_knowledge: bool,
+}
+
+impl JsonCreateWrap {
+    fn expiration(&self) -> Result<WrapExpiration, &'static str> {
+        parse_expiration(&self.expiration_at, &self.expires_in, self.never_expires)?
+            .ok_or("Specify one of `expirationAt`, `expiresIn` or `neverExpires`.")
+    }
+}
+
+fn validate_create(jc: &JsonCreateWrap) -> Result<(), ValidationError> {
+    jc.expiration()
+        .map_err(|e| validation_error("expiration", e))?;
+    match (jc.payload_kind, &jc.redirect_url, &jc.text) {
+        (JsonPayloadKind::Url, Some(_), None) | (JsonPayloadKind::Text, None, Some(_)) => Ok(()),
+        (JsonPayloadKind::Url, None, _) => {
+            Err(validation_error("payload", "`redirectUrl` is null."))
+        }
+        (JsonPayloadKind::Url, Some(_), Some(_)) => Err(validation_error(
+            "payload",
+            "`text` is only allowed for text wraps.",
+        )),
+        (JsonPayloadKind::Text, Some(_), _) => Err(validation_error(
+            "payload",
+            "`redirectUrl` is not allowed for text wraps.",
+        )),
+        (JsonPayloadKind::Text, None, None) => Err(validation_error("payload", "`text` is null.")),
+    }
+}
+
+#[derive(Deserialize, Debug, Validate)]
+#[validate(schema(function = "validate_update"))]
+pub struct JsonUpdateWrap {
+    #[validate(url(message = "`redirectUrl` is invalid URL format."))]
+    #[serde(rename = "redirectUrl")]
+    pub redirect_url: Option<String>,
+    /// New note of a text wrap.
+    pub text: Option<String>,
+    #[validate(length(min = 1, message = "`password` is empty."))]
+    pub password: Option<String>,
+    pub comment: Option<String>,
+    #[serde(rename = "expirationAt")]
+    pub expiration_at: Option<JsonExpirationAt>,
+    #[serde(rename = "expiresIn")]
+    pub expires_in: Option<String>,
+    #[serde(rename = "neverExpires", default)]
+    pub never_expires: bool,
+}
+
+impl JsonUpdateWrap {
+    fn expiration(&self) -> Result<Option<WrapExpiration>, &'static str> {
+        parse_expiration(&self.expiration_at, &self.expires_in, self.never_expires)
+    }
+}
+
+fn validate_update(ju: &JsonUpdateWrap) -> Result<(), ValidationError> {
+    let expiration = ju
+        .expiration()
+        .map_err(|e| validation_error("expiration", e))?;
+    if ju.redirect_url.is_some() && ju.text.is_some() {
+        return Err(validation_error(
+            "payload",
+            "Specify only one of `redirectUrl` or `text`.",
+        ));
+    }
+    if ju.redirect_url.is_none()
+        && ju.text.is_none()
+        && ju.password.is_none()
+        && ju.comment.is_none()
+        && expiration.is_none()
+    {
+        return Err(validation_error("update", "Nothing to update."));
+    }
+    Ok(())
+}
+
+impl From<JsonUpdateWrap> for UpdateWrap {
+    fn from(ju: JsonUpdateWrap) -> Self {
+        let expiration = ju.expiration().unwrap();
+        UpdateWrap::new(
+            ju.redirect_url,
+            ju.text,
+            ju.password,
+            ju.comment,
+            expiration,
+        )
+    }
+}
+
+/// Returns `None` when no expiration field is given.
+pub(crate) fn parse_expiration(
+    expiration_at: &Option<JsonExpirationAt>,
+    expires_in: &Option<String>,
+    never_expires: bool,
+) -> Result<Option<WrapExpiration>, &'static str> {
+    match (expiration_at, expires_in, never_expires) {
+        (None, None, false) => Ok(None),
+        (Some(expiration_at), None, false) => expiration_at
+            .to_datetime()
+            .map(|v| Some(WrapExpiration::At(v))),
+        (None, Some(expires_in), false) => parse_duration(expires_in)
+            .map(|v| Some(WrapExpiration::After(v)))
+            .ok_or("`expiresIn` is invalid duration. e.g. `7d`, `1h30m`, `P7D`"),
+        (None, None, true) => Ok(Some(WrapExpiration::Never)),
+        _ => Err("Specify only one of `expirationAt`, `expiresIn` or `neverExpires`."),
+    }
+}
+
+pub(crate) fn validation_error(code: &'static str, message: &'static str) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(message.into());
+    error
+}
+
+/// `expirationAt` is either unix time in seconds or an RFC 3339 date-time.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum JsonExpirationAt {
+    UnixTime(i64),
+    Rfc3339(String),
+}
+
+impl JsonExpirationAt {
+    fn to_datetime(&self) -> Result<DateTime<Utc>, &'static str> {
+        match self {
+            JsonExpirationAt::UnixTime(time) if (MIN_VALUE..=MAX_VALUE).contains(time) => Utc
+                .timestamp_opt(*time, 0)
+                .single()
+                .ok_or("`expirationAt` is invalid timestamp."),
+            JsonExpirationAt::UnixTime(_) => {
+                Err("`expirationAt` is minimum 0 and maximum 253402300799.")
+            }
+            JsonExpirationAt::Rfc3339(value) => DateTime::parse_from_rfc3339(value)
+                .map(|v| v.with_timezone(&Utc))
+                .map_err(|_| "`expirationAt` is invalid RFC 3339 date-time."),
+        }
+    }
 }
 
 impl From<JsonCreateWrap> for CreateWrap {
     fn from(jc: JsonCreateWrap) -> Self {
+        let expiration = jc.expiration().unwrap();
         CreateWrap {
-            redirect_url: jc.redirect_url.unwrap(),
-            password: jc.password.unwrap(),
+            redirect_url: jc.redirect_url.or(jc.text).unwrap(),
+            payload_kind: jc.payload_kind.into(),
+            password: jc.password,
             auth_type: jc.auth_type as u32,
+            pin_length: jc.pin_length,
             comment: jc.comment.unwrap(),
-            expiration_at: jc.expiration_at as u32,
+            expiration,
+            max_views: jc.max_views,
+            owner_id: None,
+            alias: jc.alias,
+            zero_knowledge: jc.zero_knowledge,
         }
     }
 }
@@ -70,29 +341,50 @@ pub struct JsonAuthorizeWrap {
         required(message = "`password` is null.")
     )]
     pub password: Option<String>,
+    /// Credential the password belongs to, omitted for the password of the wrap itself.
+    #[serde(rename = "credentialId")]
+    pub credential_id: Option<String>,
 }
 
 impl From<JsonAuthorizeWrap> for AuthorizeWrap {
     fn from(aw: JsonAuthorizeWrap) -> Self {
         Self {
             password: aw.password.unwrap(),
+            credential_id: aw.credential_id.filter(|v| !v.is_empty()),
         }
     }
 }
 
+/// Carries `redirect_url` for URL wraps and `text` for text wraps.
 #[derive(Debug, Serialize)]
 pub struct JsonAuthorizedWrapView {
     pub id: String,
-    pub redirect_url: String,
-    pub expiration_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    pub expiration_at: Option<String>,
+    pub remaining_views: Option<u32>,
 }
 
-impl From<WrapView> for JsonAuthorizedWrapView {
-    fn from(wv: WrapView) -> Self {
-        Self {
+impl TryFrom<WrapView> for JsonAuthorizedWrapView {
+    type Error = WrapError;
+
+    fn try_from(wv: WrapView) -> Result<Self, Self::Error> {
+        // always present once the password was verified
+        let payload = wv.redirect_url.ok_or_else(|| {
+            WrapError::Corrupted(anyhow!("Wrap {} has no payload to reveal.", wv.id))
+        })?;
+        let (redirect_url, text) = match wv.payload_kind {
+            WrapPayloadKind::Url => (Some(payload), None),
+            WrapPayloadKind::Text => (None, Some(payload)),
+        };
+        Ok(Self {
             id: wv.id,
-            redirect_url: wv.redirect_url,
-            expiration_at: wv.expiration_at.to_rfc3339(),
-        }
+            redirect_url,
+            text,
+            expiration_at: wv.expiration_at.map(|v| v.to_rfc3339()),
+            remaining_views: wv.remaining_views,
+        })
     }
 }
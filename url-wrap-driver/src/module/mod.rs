@@ -1,13 +1,22 @@
+use std::env;
 use std::sync::Arc;
 use url_wrap_adapter::modules::{RepositoriesModule, RepositoriesModuleExt};
+use url_wrap_adapter::persistence::in_memory::InMemoryDb;
+#[cfg(feature = "mongodb")]
 use url_wrap_adapter::persistence::mongodb::Db;
+#[cfg(feature = "sql")]
+use url_wrap_adapter::persistence::sql::SqlDb;
 use url_wrap_adapter::repository::health_check::HealthCheckRepository;
 use url_wrap_app::usecase::health_check::HealthCheckUseCase;
+use url_wrap_app::usecase::owner::OwnerUseCase;
+use url_wrap_app::usecase::rate_limit::RateLimitUseCase;
 use url_wrap_app::usecase::wrap::WrapUseCase;
 
 pub struct Modules {
     health_check_use_case: HealthCheckUseCase,
     wrap_use_case: WrapUseCase<RepositoriesModule>,
+    rate_limit_use_case: RateLimitUseCase<RepositoriesModule>,
+    owner_use_case: OwnerUseCase<RepositoriesModule>,
 }
 
 pub trait ModulesExt {
@@ -15,6 +24,8 @@ pub trait ModulesExt {
 
     fn health_check_use_case(&self) -> &HealthCheckUseCase;
     fn wrap_use_case(&self) -> &WrapUseCase<Self::RepositoriesModule>;
+    fn rate_limit_use_case(&self) -> &RateLimitUseCase<Self::RepositoriesModule>;
+    fn owner_use_case(&self) -> &OwnerUseCase<Self::RepositoriesModule>;
 }
 
 impl ModulesExt for Modules {
@@ -27,20 +38,112 @@ impl ModulesExt for Modules {
     fn wrap_use_case(&self) -> &WrapUseCase<Self::RepositoriesModule> {
         &self.wrap_use_case
     }
+
+    fn rate_limit_use_case(&self) -> &RateLimitUseCase<Self::RepositoriesModule> {
+        &self.rate_limit_use_case
+    }
+
+    fn owner_use_case(&self) -> &OwnerUseCase<Self::RepositoriesModule> {
+        &self.owner_use_case
+    }
 }
 
+const DATABASE_BACKEND: &str = "DATABASE_BACKEND";
+#[cfg(any(feature = "mongodb", feature = "sql"))]
+const DATABASE_MIGRATE_ON_STARTUP: &str = "DATABASE_MIGRATE_ON_STARTUP";
+const DEFAULT_DATABASE_BACKEND: &str = if cfg!(feature = "mongodb") {
+    "mongodb"
+} else if cfg!(feature = "sql") {
+    "sql"
+} else {
+    "memory"
+};
+
 impl Modules {
+    /// Builds the modules for the backend selected by `DATABASE_BACKEND`.
+    /// `mongodb` and `sql` are only available when the crate is built with the matching feature.
+    /// The schema is migrated first unless `DATABASE_MIGRATE_ON_STARTUP` is `false`.
     pub async fn new() -> Modules {
-        let db = Db::new().await;
+        match database_backend().as_str() {
+            #[cfg(feature = "mongodb")]
+            "mongodb" => {
+                let db = Db::new().await;
+                if migrate_on_startup() {
+                    db.migrate().await.expect("Could not migrate MongoDB.");
+                }
+                Self::build(
+                    RepositoriesModule::new(db.clone()),
+                    HealthCheckRepository::new(db),
+                )
+            }
+            #[cfg(feature = "sql")]
+            "sql" => {
+                let db = SqlDb::new().await;
+                if migrate_on_startup() {
+                    db.migrate().await.expect("Could not migrate SQL database.");
+                }
+                Self::build(
+                    RepositoriesModule::sql(db.clone()),
+                    HealthCheckRepository::sql(db),
+                )
+            }
+            "memory" => Self::in_memory(),
+            backend => panic!("{} `{}` is invalid value.", DATABASE_BACKEND, backend),
+        }
+    }
+
+    /// Migrates the schema and indexes of the backend selected by `DATABASE_BACKEND`
+    /// without starting the application.
+    pub async fn migrate() -> anyhow::Result<()> {
+        match database_backend().as_str() {
+            #[cfg(feature = "mongodb")]
+            "mongodb" => {
+                let db = Db::new().await;
+                let upgraded = db.upgrade_wraps().await?;
+                tracing::info!("Upgraded {} wrap documents.", upgraded);
+                db.ensure_indexes().await
+            }
+            #[cfg(feature = "sql")]
+            "sql" => SqlDb::new().await.migrate().await,
+            // nothing is persisted, so there is nothing to migrate
+            "memory" => Ok(()),
+            backend => panic!("{} `{}` is invalid value.", DATABASE_BACKEND, backend),
+        }
+    }
+
+    /// Modules backed by process memory only. Every call starts with an empty store.
+    pub fn in_memory() -> Modules {
+        Self::build(
+            RepositoriesModule::in_memory(InMemoryDb::new()),
+            HealthCheckRepository::in_memory(),
+        )
+    }
 
-        let repositories_module = Arc::new(RepositoriesModule::new(db.clone()));
+    fn build(
+        repositories_module: RepositoriesModule,
+        health_check_repository: HealthCheckRepository,
+    ) -> Modules {
+        let repositories_module = Arc::new(repositories_module);
 
-        let health_check_use_case = HealthCheckUseCase::new(HealthCheckRepository::new(db));
+        let health_check_use_case = HealthCheckUseCase::new(health_check_repository);
         let wrap_use_case = WrapUseCase::new(repositories_module.clone());
+        let rate_limit_use_case = RateLimitUseCase::new(repositories_module.clone());
+        let owner_use_case = OwnerUseCase::new(repositories_module.clone());
 
         Self {
             health_check_use_case,
             wrap_use_case,
+            rate_limit_use_case,
+            owner_use_case,
         }
     }
 }
+
+fn database_backend() -> String {
+    env::var(DATABASE_BACKEND).unwrap_or_else(|_| DEFAULT_DATABASE_BACKEND.to_string())
+}
+
+#[cfg(any(feature = "mongodb", feature = "sql"))]
+fn migrate_on_startup() -> bool {
+    env::var(DATABASE_MIGRATE_ON_STARTUP).map_or(true, |v| v != "false")
+}
@@ -26,3 +26,20 @@ pub async fn hc_mongodb(
             StatusCode::SERVICE_UNAVAILABLE
         })
 }
+
+pub async fn hc_sql(
+    Extension(modules): Extension<Arc<Modules>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    modules
+        .health_check_use_case()
+        .diagnose_sql_db_conn()
+        .await
+        .map(|_| {
+            tracing::debug!("Access sql health check endpoint.");
+            StatusCode::NO_CONTENT
+        })
+        .map_err(|err| {
+            error!("{:?}", err);
+            StatusCode::SERVICE_UNAVAILABLE
+        })
+}
@@ -1,2 +1,3 @@
 pub mod health;
+pub mod owner;
 pub mod wrap;
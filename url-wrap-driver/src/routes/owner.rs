@@ -0,0 +1,25 @@
+use crate::context::api_key::AdminAuthorization;
+use crate::context::errors::AppError;
+use crate::context::validate::ValidatedRequest;
+use crate::model::owner::{JsonCreateOwner, JsonRegisteredOwnerView};
+use crate::module::{Modules, ModulesExt};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use std::sync::Arc;
+use tracing::log::info;
+
+pub async fn create_owner(
+    _: AdminAuthorization,
+    ValidatedRequest(source): ValidatedRequest<JsonCreateOwner>,
+    Extension(modules): Extension<Arc<Modules>>,
+) -> Result<impl IntoResponse, AppError> {
+    let rv = modules
+        .owner_use_case()
+        .register_owner(source.into())
+        .await?;
+
+    info!("Created owner: {}", rv.owner.id);
+    let json: JsonRegisteredOwnerView = rv.into();
+    Ok((StatusCode::CREATED, Json(json)))
+}
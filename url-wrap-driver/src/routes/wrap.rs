@@ -1,80 +1,183 @@
-use crate::context::axum_helper::JsonErrorResponse;
-use crate::context::validate::ValidatedRequest;
-use crate::model::wrap::{JsonAuthorizeWrap, JsonAuthorizedWrapView, JsonCreateWrap, JsonWrapView};
+use crate::context::api_key::{AuthenticatedOwner, OptionalOwner};
+use crate::context::client::ClientInfo;
+use crate::context::errors::AppError;
+use crate::context::management_token::ManagementTokenHeader;
+use crate::context::validate::{ValidatedQuery, ValidatedRequest};
+use crate::model::access_event::{
+    JsonAccessEventListView, JsonAccessStatsView, JsonListAccessEventsQuery,
+};
+use crate::model::credential::{JsonCreateCredential, JsonCredentialListView, JsonCredentialView};
+use crate::model::wrap::{
+    JsonAuthorizeWrap, JsonAuthorizedWrapView, JsonCreateWrap, JsonListWrapsQuery,
+    JsonRegisteredWrapView, JsonUpdateWrap, JsonWrapListView, JsonWrapView,
+};
 use crate::module::{Modules, ModulesExt};
 use axum::extract::Path;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::{Extension, Json};
 use std::sync::Arc;
-use tracing::error;
 use tracing::log::info;
-use url_wrap_app::model::wrap::AuthorizeWrap;
+use url_wrap_app::model::access_event::AccessClient;
+use url_wrap_app::model::wrap::{AuthorizeWrap, CreateWrap};
 
 pub async fn create_wrap(
+    OptionalOwner(owner): OptionalOwner,
     ValidatedRequest(source): ValidatedRequest<JsonCreateWrap>,
     Extension(modules): Extension<Arc<Modules>>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let res = modules.wrap_use_case().register_wrap(source.into()).await;
-    res.map(|wv| {
-        info!("Created wrap: {}", wv.id);
-        let json: JsonWrapView = wv.into();
-        (StatusCode::CREATED, Json(json))
-    })
-    .map_err(|err| {
-        error!("{:?}", err);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })
+) -> Result<impl IntoResponse, AppError> {
+    let mut cw: CreateWrap = source.into();
+    cw.owner_id = owner.map(|o| o.id);
+    let rv = modules.wrap_use_case().register_wrap(cw).await?;
+
+    info!("Created wrap: {}", rv.wrap.id);
+    let json: JsonRegisteredWrapView = rv.into();
+    Ok((StatusCode::CREATED, Json(json)))
+}
+
+pub async fn list_wraps(
+    AuthenticatedOwner(owner): AuthenticatedOwner,
+    ValidatedQuery(query): ValidatedQuery<JsonListWrapsQuery>,
+    Extension(modules): Extension<Arc<Modules>>,
+) -> Result<impl IntoResponse, AppError> {
+    let wlv = modules
+        .wrap_use_case()
+        .list_wraps(owner.id, query.into())
+        .await?;
+
+    info!("Listed {} wraps", wlv.wraps.len());
+    let json: JsonWrapListView = wlv.into();
+    Ok((StatusCode::OK, Json(json)))
 }
 
 pub async fn get_wrap(
     Path(id): Path<String>,
     Extension(modules): Extension<Arc<Modules>>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let res = modules.wrap_use_case().get_wrap(id).await;
-    match res {
-        Ok(wv) => wv
-            .map(|wv| {
-                info!("Found: {}", wv.id);
-                let json: JsonWrapView = wv.into();
-                (StatusCode::OK, Json(json))
-            })
-            .ok_or_else(|| {
-                error!("Wrap id is not found.");
-                StatusCode::NOT_FOUND
-            }),
-        Err(err) => {
-            error!("Unexpected error: {:?}", err);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+) -> Result<impl IntoResponse, AppError> {
+    let wv = modules.wrap_use_case().get_wrap(id).await?;
+
+    info!("Found: {}", wv.id);
+    let json: JsonWrapView = wv.into();
+    Ok((StatusCode::OK, Json(json)))
 }
 
 pub async fn auth_wrap(
     Path(id): Path<String>,
+    client: ClientInfo,
     ValidatedRequest(source): ValidatedRequest<JsonAuthorizeWrap>,
     Extension(modules): Extension<Arc<Modules>>,
-) -> Result<impl IntoResponse, impl IntoResponse> {
+) -> Result<impl IntoResponse, AppError> {
     let aw: AuthorizeWrap = source.into();
-    let res = modules.wrap_use_case().verify_wrap(id, aw.password).await;
-    match res {
-        Ok(wv) => wv
-            .map(|wv| {
-                info!("Found: {}", wv.id);
-                let json: JsonAuthorizedWrapView = wv.into();
-                (StatusCode::OK, Json(json))
-            })
-            .ok_or_else(|| {
-                error!("Expiration date has expired.");
-                let errors = vec!["Expiration date has expired.".to_string()];
-                let json = JsonErrorResponse::new("expired".to_string(), errors);
-                (StatusCode::FORBIDDEN, Json(json))
-            }),
-        Err(err) => {
-            error!("Unexpected error: {:?}", err);
-            let errors = vec!["Authentication failed.".to_string()];
-            let json = JsonErrorResponse::new("authentication_failed".to_string(), errors);
-            Err((StatusCode::UNAUTHORIZED, Json(json)))
-        }
-    }
+    let client = AccessClient::new(client.ip.map(|v| v.to_string()), client.user_agent);
+    let wv = modules.wrap_use_case().verify_wrap(id, aw, client).await?;
+
+    info!("Found: {}", wv.id);
+    let json: JsonAuthorizedWrapView = wv.try_into()?;
+    Ok((StatusCode::OK, Json(json)))
+}
+
+pub async fn list_wrap_events(
+    Path(id): Path<String>,
+    AuthenticatedOwner(owner): AuthenticatedOwner,
+    ValidatedQuery(query): ValidatedQuery<JsonListAccessEventsQuery>,
+    Extension(modules): Extension<Arc<Modules>>,
+) -> Result<impl IntoResponse, AppError> {
+    let alv = modules
+        .wrap_use_case()
+        .list_wrap_events(owner.id, id, query.into())
+        .await?;
+
+    info!("Listed {} access events", alv.events.len());
+    let json: JsonAccessEventListView = alv.into();
+    Ok((StatusCode::OK, Json(json)))
+}
+
+pub async fn get_wrap_stats(
+    Path(id): Path<String>,
+    AuthenticatedOwner(owner): AuthenticatedOwner,
+    Extension(modules): Extension<Arc<Modules>>,
+) -> Result<impl IntoResponse, AppError> {
+    let sv = modules
+        .wrap_use_case()
+        .get_wrap_stats(owner.id, id.clone())
+        .await?;
+
+    info!("Aggregated stats of wrap: {}", id);
+    let json: JsonAccessStatsView = sv.into();
+    Ok((StatusCode::OK, Json(json)))
+}
+
+pub async fn update_wrap(
+    Path(id): Path<String>,
+    ManagementTokenHeader(management_token): ManagementTokenHeader,
+    ValidatedRequest(source): ValidatedRequest<JsonUpdateWrap>,
+    Extension(modules): Extension<Arc<Modules>>,
+) -> Result<impl IntoResponse, AppError> {
+    let wv = modules
+        .wrap_use_case()
+        .update_wrap(id, management_token, source.into())
+        .await?;
+
+    info!("Updated wrap: {}", wv.id);
+    let json: JsonWrapView = wv.into();
+    Ok((StatusCode::OK, Json(json)))
+}
+
+pub async fn list_credentials(
+    Path(id): Path<String>,
+    ManagementTokenHeader(management_token): ManagementTokenHeader,
+    Extension(modules): Extension<Arc<Modules>>,
+) -> Result<impl IntoResponse, AppError> {
+    let cvs = modules
+        .wrap_use_case()
+        .list_credentials(id.clone(), management_token)
+        .await?;
+
+    info!("Listed {} credentials of wrap: {}", cvs.len(), id);
+    let json: JsonCredentialListView = cvs.into();
+    Ok((StatusCode::OK, Json(json)))
+}
+
+pub async fn add_credential(
+    Path(id): Path<String>,
+    ManagementTokenHeader(management_token): ManagementTokenHeader,
+    ValidatedRequest(source): ValidatedRequest<JsonCreateCredential>,
+    Extension(modules): Extension<Arc<Modules>>,
+) -> Result<impl IntoResponse, AppError> {
+    let cv = modules
+        .wrap_use_case()
+        .add_credential(id.clone(), management_token, source.into())
+        .await?;
+
+    info!("Added credential {} to wrap: {}", cv.id, id);
+    let json: JsonCredentialView = cv.into();
+    Ok((StatusCode::CREATED, Json(json)))
+}
+
+pub async fn revoke_credential(
+    Path((id, credential_id)): Path<(String, String)>,
+    ManagementTokenHeader(management_token): ManagementTokenHeader,
+    Extension(modules): Extension<Arc<Modules>>,
+) -> Result<impl IntoResponse, AppError> {
+    modules
+        .wrap_use_case()
+        .revoke_credential(id.clone(), management_token, credential_id.clone())
+        .await?;
+
+    info!("Revoked credential {} of wrap: {}", credential_id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete_wrap(
+    Path(id): Path<String>,
+    ManagementTokenHeader(management_token): ManagementTokenHeader,
+    Extension(modules): Extension<Arc<Modules>>,
+) -> Result<impl IntoResponse, AppError> {
+    modules
+        .wrap_use_case()
+        .delete_wrap(id.clone(), management_token)
+        .await?;
+
+    info!("Deleted wrap: {}", id);
+    Ok(StatusCode::NO_CONTENT)
 }
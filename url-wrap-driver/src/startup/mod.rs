@@ -1,36 +1,167 @@
+use crate::context::client::TrustedProxies;
+use crate::context::rate_limit::{init_trusted_proxies, rate_limit, RateLimitBudget};
 use crate::module::Modules;
-use crate::routes::health::{hc, hc_mongodb};
-use crate::routes::wrap::{auth_wrap, create_wrap, get_wrap};
-use axum::routing::{get, post};
+use crate::routes::health::{hc, hc_mongodb, hc_sql};
+use crate::routes::owner::create_owner;
+use crate::routes::wrap::{
+    add_credential, auth_wrap, create_wrap, delete_wrap, get_wrap, get_wrap_stats,
+    list_credentials, list_wrap_events, list_wraps, revoke_credential, update_wrap,
+};
+use crate::startup::sweeper::{init_retention_policy, spawn_sweeper};
+use axum::middleware::from_fn;
+use axum::routing::{delete, get, patch, post};
 use axum::{Extension, Router};
 use dotenv::dotenv;
 use std::env;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
+use url_wrap_app::model::rate_limit::RateLimitQuota;
 
-pub async fn startup(modules: Arc<Modules>) {
-    let hc_router = Router::new()
-        .route("/", get(hc))
-        .route("/mongo", get(hc_mongodb));
+mod sweeper;
 
-    let wrap_router = Router::new()
-        .route("/", post(create_wrap))
-        .route("/:id", get(get_wrap))
-        .route("/:id/authorize", post(auth_wrap));
-
-    let app = Router::new()
-        .nest("/v1/hc", hc_router)
-        .nest("/v1/wraps", wrap_router)
-        .layer(Extension(modules));
+pub async fn startup(modules: Arc<Modules>) {
+    spawn_sweeper(modules.clone(), init_retention_policy());
+    let app = init_router(modules);
 
     let addr = SocketAddr::from(init_addr());
     tracing::info!("Server listening on {}", addr);
 
     axum::Server::bind(&addr)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .unwrap_or_else(|_| panic!("Server cannot launch."));
 }
+
+/// Builds the application router, so it can also be served from integration tests.
+pub fn init_router(modules: Arc<Modules>) -> Router {
+    let hc_router = Router::new()
+        .route("/", get(hc))
+        .route("/mongo", get(hc_mongodb))
+        .route("/sql", get(hc_sql));
+
+    let trusted_proxies = init_trusted_proxies();
+    let create_wrap_budget = RateLimitBudget::init(
+        "create_wrap",
+        "RATE_LIMIT_CREATE_WRAP",
+        RateLimitQuota::new(10, 60),
+        trusted_proxies.clone(),
+    );
+    let list_wraps_budget = RateLimitBudget::init(
+        "list_wraps",
+        "RATE_LIMIT_LIST_WRAPS",
+        RateLimitQuota::new(60, 60),
+        trusted_proxies.clone(),
+    );
+    let get_wrap_budget = RateLimitBudget::init(
+        "get_wrap",
+        "RATE_LIMIT_GET_WRAP",
+        RateLimitQuota::new(120, 60),
+        trusted_proxies.clone(),
+    );
+    let auth_wrap_budget = RateLimitBudget::init(
+        "auth_wrap",
+        "RATE_LIMIT_AUTH_WRAP",
+        RateLimitQuota::new(10, 60),
+        trusted_proxies.clone(),
+    );
+    let manage_wrap_budget = RateLimitBudget::init(
+        "manage_wrap",
+        "RATE_LIMIT_MANAGE_WRAP",
+        RateLimitQuota::new(30, 60),
+        trusted_proxies.clone(),
+    );
+    // credentials are managed with the management token too, so they share its budget
+    let manage_credentials_budget = manage_wrap_budget.clone();
+    let revoke_credential_budget = manage_wrap_budget.clone();
+    let list_wrap_events_budget = RateLimitBudget::init(
+        "list_wrap_events",
+        "RATE_LIMIT_LIST_WRAP_EVENTS",
+        RateLimitQuota::new(60, 60),
+        trusted_proxies.clone(),
+    );
+    let wrap_stats_budget = RateLimitBudget::init(
+        "wrap_stats",
+        "RATE_LIMIT_WRAP_STATS",
+        RateLimitQuota::new(30, 60),
+        trusted_proxies.clone(),
+    );
+    let create_owner_budget = RateLimitBudget::init(
+        "create_owner",
+        "RATE_LIMIT_CREATE_OWNER",
+        RateLimitQuota::new(10, 60),
+        trusted_proxies.clone(),
+    );
+
+    let wrap_router =
+        Router::new()
+            .route(
+                "/",
+                post(create_wrap)
+                    .layer(from_fn(move |req, next| {
+                        rate_limit(req, next, create_wrap_budget.clone())
+                    }))
+                    .merge(get(list_wraps).layer(from_fn(move |req, next| {
+                        rate_limit(req, next, list_wraps_budget.clone())
+                    }))),
+            )
+            .route(
+                "/:id",
+                get(get_wrap)
+                    .layer(from_fn(move |req, next| {
+                        rate_limit(req, next, get_wrap_budget.clone())
+                    }))
+                    .merge(patch(update_wrap).delete(delete_wrap).layer(from_fn(
+                        move |req, next| rate_limit(req, next, manage_wrap_budget.clone()),
+                    ))),
+            )
+            .route(
+                "/:id/authorize",
+                post(auth_wrap).layer(from_fn(move |req, next| {
+                    rate_limit(req, next, auth_wrap_budget.clone())
+                })),
+            )
+            .route(
+                "/:id/credentials",
+                get(list_credentials)
+                    .post(add_credential)
+                    .layer(from_fn(move |req, next| {
+                        rate_limit(req, next, manage_credentials_budget.clone())
+                    })),
+            )
+            .route(
+                "/:id/credentials/:credential_id",
+                delete(revoke_credential).layer(from_fn(move |req, next| {
+                    rate_limit(req, next, revoke_credential_budget.clone())
+                })),
+            )
+            .route(
+                "/:id/events",
+                get(list_wrap_events).layer(from_fn(move |req, next| {
+                    rate_limit(req, next, list_wrap_events_budget.clone())
+                })),
+            )
+            .route(
+                "/:id/stats",
+                get(get_wrap_stats).layer(from_fn(move |req, next| {
+                    rate_limit(req, next, wrap_stats_budget.clone())
+                })),
+            );
+
+    let owner_router = Router::new().route(
+        "/",
+        post(create_owner).layer(from_fn(move |req, next| {
+            rate_limit(req, next, create_owner_budget.clone())
+        })),
+    );
+
+    Router::new()
+        .nest("/v1/hc", hc_router)
+        .nest("/v1/owners", owner_router)
+        .nest("/v1/wraps", wrap_router)
+        .layer(Extension(TrustedProxies(trusted_proxies)))
+        .layer(Extension(modules))
+}
+
 pub fn init_app() {
     dotenv().ok();
     tracing_subscriber::fmt::init();
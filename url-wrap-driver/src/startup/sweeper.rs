@@ -0,0 +1,79 @@
+use crate::module::{Modules, ModulesExt};
+use chrono::Duration;
+use std::env;
+use std::sync::Arc;
+use tokio::time::{self, MissedTickBehavior};
+use tracing::{debug, error, info};
+
+const DEFAULT_GRACE_SECONDS: i64 = 7 * 24 * 60 * 60;
+const DEFAULT_INTERVAL_SECONDS: u64 = 60 * 60;
+
+/// How long expired wraps are kept and how often they are looked for.
+pub struct RetentionPolicy {
+    grace: Duration,
+    /// `None` disables the sweeper.
+    interval: Option<std::time::Duration>,
+}
+
+pub fn init_retention_policy() -> RetentionPolicy {
+    let grace_seconds = env::var_os("WRAP_PURGE_GRACE_SECONDS")
+        .map(|v| {
+            v.into_string()
+                .expect("WRAP_PURGE_GRACE_SECONDS is invalid value.")
+                .parse::<i64>()
+                .ok()
+                .filter(|v| *v >= 0)
+                .expect("WRAP_PURGE_GRACE_SECONDS is invalid value.")
+        })
+        .unwrap_or(DEFAULT_GRACE_SECONDS);
+    let interval_seconds = env::var_os("WRAP_PURGE_INTERVAL_SECONDS")
+        .map(|v| {
+            v.into_string()
+                .expect("WRAP_PURGE_INTERVAL_SECONDS is invalid value.")
+                .parse::<u64>()
+                .expect("WRAP_PURGE_INTERVAL_SECONDS is invalid value.")
+        })
+        .unwrap_or(DEFAULT_INTERVAL_SECONDS);
+
+    RetentionPolicy {
+        grace: Duration::seconds(grace_seconds),
+        interval: (interval_seconds > 0).then(|| std::time::Duration::from_secs(interval_seconds)),
+    }
+}
+
+/// Starts a task that periodically deletes the wraps whose grace period has passed,
+/// and the rate limit windows that have reset.
+pub fn spawn_sweeper(modules: Arc<Modules>, policy: RetentionPolicy) {
+    let period = match policy.interval {
+        Some(period) => period,
+        None => {
+            info!("Expired wrap sweeper is disabled.");
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        let mut interval = time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut total: u64 = 0;
+        loop {
+            interval.tick().await;
+            match modules
+                .wrap_use_case()
+                .purge_expired_wraps(policy.grace)
+                .await
+            {
+                Ok(0) => debug!("No expired wraps to purge."),
+                Ok(deleted) => {
+                    total += deleted;
+                    info!("Purged {} expired wraps, {} since startup.", deleted, total);
+                }
+                Err(err) => error!("Could not purge expired wraps: {:?}", err),
+            }
+            match modules.rate_limit_use_case().purge_expired_windows().await {
+                Ok(deleted) => debug!("Purged {} rate limit windows.", deleted),
+                Err(err) => error!("Could not purge rate limit windows: {:?}", err),
+            }
+        }
+    });
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{app, authorize, create_wrap, send, str_field};
+use serde_json::json;
+
+#[tokio::test]
+async fn alias_resolves_like_the_id() {
+    let app = app();
+
+    let created = create_wrap(&app, json!({ "alias": "Team-Notes" })).await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    // aliases are case-insensitive and stored in lowercase
+    assert_eq!(str_field(&created, "alias"), "team-notes");
+    let id = str_field(&created, "id");
+
+    let found = send(&app, Method::GET, "/v1/wraps/TEAM-notes", &[], None).await;
+    assert_eq!(found.status, StatusCode::OK);
+    assert_eq!(str_field(&found, "id"), id);
+
+    let res = authorize(&app, "team-notes", "correct horse").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(
+        str_field(&res, "redirect_url"),
+        "https://example.com/secret"
+    );
+}
+
+#[tokio::test]
+async fn alias_is_unique() {
+    let app = app();
+
+    let res = create_wrap(&app, json!({ "alias": "launch-plan" })).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let res = create_wrap(&app, json!({ "alias": "Launch-Plan" })).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(str_field(&res, "errorCode"), "alias_taken");
+}
+
+#[tokio::test]
+async fn invalid_aliases_are_rejected() {
+    let app = app();
+
+    for alias in [
+        "ab",
+        "-leading",
+        "trailing-",
+        "under_score",
+        "wraps",
+        "01HZY5J3Q8V7N2K4M6P8R0T2W4",
+    ] {
+        let res = create_wrap(&app, json!({ "alias": alias })).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", alias);
+    }
+}
+
+#[tokio::test]
+async fn deleted_wrap_frees_its_alias() {
+    let app = app();
+
+    let created = create_wrap(&app, json!({ "alias": "one-off" })).await;
+    let token = str_field(&created, "management_token").to_string();
+
+    let headers = [("x-management-token", token.as_str())];
+    let res = send(&app, Method::DELETE, "/v1/wraps/one-off", &headers, None).await;
+    assert!(res.status.is_success(), "{}", res.status);
+
+    let res = send(&app, Method::GET, "/v1/wraps/one-off", &[], None).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = create_wrap(&app, json!({ "alias": "one-off" })).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+}
@@ -0,0 +1,127 @@
+// shared by several test crates, each of which uses only part of it
+#![allow(dead_code)]
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use serde_json::{json, Value};
+use std::env;
+use std::sync::{Arc, Once};
+use tower::ServiceExt;
+use url_wrap_driver::module::Modules;
+use url_wrap_driver::startup::init_router;
+
+pub const ADMIN_API_KEY: &str = "test-admin-key";
+pub const LOCKOUT_THRESHOLD: u32 = 3;
+
+static INIT: Once = Once::new();
+
+/// Router on a fresh in-memory store. Requests carry no peer address, so they are not rate limited.
+pub fn app() -> Router {
+    app_on(Arc::new(Modules::in_memory()))
+}
+
+/// Router on `modules`, for tests that also call the use cases directly.
+pub fn app_on(modules: Arc<Modules>) -> Router {
+    INIT.call_once(|| {
+        let vars = [
+            ("ARGON2_PHC_VARIANT", "argon2id"),
+            ("ARGON2_PHC_VERSION", "19"),
+            ("ARGON2_PHC_TIME_COST", "1"),
+            ("ARGON2_PHC_MEMORY_COST", "1024"),
+            ("ARGON2_PHC_PARALLELISM_COST", "1"),
+            ("AES_GCM_SALT", "salt_string_32_bytes_for_tests__"),
+            ("WRAP_LOCKOUT_THRESHOLD", "3"),
+            ("WRAP_LOCKOUT_BACKOFF_SECONDS", "30"),
+            ("ADMIN_API_KEY", ADMIN_API_KEY),
+        ];
+        for (key, value) in vars {
+            env::set_var(key, value);
+        }
+    });
+    init_router(modules)
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub body: Value,
+}
+
+pub async fn send(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    headers: &[(&str, &str)],
+    body: Option<Value>,
+) -> TestResponse {
+    let mut req = Request::builder().method(method).uri(uri);
+    for (key, value) in headers {
+        req = req.header(*key, *value);
+    }
+    let body = match body {
+        Some(body) => {
+            req = req.header(header::CONTENT_TYPE, "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let res = app.clone().oneshot(req.body(body).unwrap()).await.unwrap();
+    let status = res.status();
+    let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    TestResponse { status, body }
+}
+
+/// Creates a text password wrap from `overrides` on top of sane defaults.
+pub async fn create_wrap(app: &Router, overrides: Value) -> TestResponse {
+    post_wrap(app, &[], overrides).await
+}
+
+/// Like `create_wrap`, but the wrap belongs to the owner of `api_key`.
+pub async fn create_owned_wrap(app: &Router, api_key: &str, overrides: Value) -> TestResponse {
+    let auth = format!("Bearer {}", api_key);
+    post_wrap(app, &[("authorization", &auth)], overrides).await
+}
+
+async fn post_wrap(app: &Router, headers: &[(&str, &str)], overrides: Value) -> TestResponse {
+    let mut body = json!({
+        "redirectUrl": "https://example.com/secret",
+        "password": "correct horse",
+        "authType": 1,
+        "comment": "test",
+        "neverExpires": true,
+    });
+    for (key, value) in overrides.as_object().unwrap() {
+        body[key] = value.clone();
+    }
+    send(app, Method::POST, "/v1/wraps", headers, Some(body)).await
+}
+
+/// Registers an owner with the admin key and returns its API key.
+pub async fn register_owner(app: &Router, name: &str) -> String {
+    let auth = format!("Bearer {}", ADMIN_API_KEY);
+    let body = json!({ "name": name });
+    let res = send(
+        app,
+        Method::POST,
+        "/v1/owners",
+        &[("authorization", &auth)],
+        Some(body),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    str_field(&res, "api_key").to_string()
+}
+
+pub async fn authorize(app: &Router, id: &str, password: &str) -> TestResponse {
+    let uri = format!("/v1/wraps/{}/authorize", id);
+    let body = json!({ "password": password });
+    send(app, Method::POST, &uri, &[], Some(body)).await
+}
+
+pub fn str_field<'a>(res: &'a TestResponse, key: &str) -> &'a str {
+    res.body[key]
+        .as_str()
+        .unwrap_or_else(|| panic!("`{}` is missing in {}", key, res.body))
+}
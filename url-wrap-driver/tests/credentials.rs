@@ -0,0 +1,158 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use axum::Router;
+use common::{app, create_wrap, send, str_field, TestResponse};
+use serde_json::{json, Value};
+
+const MAX_CREDENTIALS_PER_WRAP: usize = 20;
+
+async fn add_credential(app: &Router, id: &str, token: &str, body: Value) -> TestResponse {
+    let uri = format!("/v1/wraps/{}/credentials", id);
+    let headers = [("x-management-token", token)];
+    send(app, Method::POST, &uri, &headers, Some(body)).await
+}
+
+async fn authorize_credential(
+    app: &Router,
+    id: &str,
+    credential_id: &str,
+    password: &str,
+) -> TestResponse {
+    let uri = format!("/v1/wraps/{}/authorize", id);
+    let body = json!({ "password": password, "credentialId": credential_id });
+    send(app, Method::POST, &uri, &[], Some(body)).await
+}
+
+#[tokio::test]
+async fn credential_opens_the_wrap() {
+    let app = app();
+    let created = create_wrap(&app, json!({})).await;
+    let id = str_field(&created, "id");
+    let token = str_field(&created, "management_token");
+
+    let res = add_credential(
+        &app,
+        id,
+        token,
+        json!({ "name": "alice", "password": "s3cret" }),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    let credential_id = str_field(&res, "id");
+
+    let res = authorize_credential(&app, id, credential_id, "s3cret").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        str_field(&res, "redirect_url"),
+        "https://example.com/secret"
+    );
+
+    // the password of the wrap is not checked against the credential
+    let res = authorize_credential(&app, id, credential_id, "correct horse").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    // and the credential's password is not the wrap's
+    let res = common::authorize(&app, id, "s3cret").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn wrong_token_is_refused_before_the_password_is_checked() {
+    let app = app();
+    let created = create_wrap(&app, json!({ "authType": 2, "password": "1234" })).await;
+    let id = str_field(&created, "id");
+
+    // an invalid PIN would be a 400 and reveal the auth type of the wrap
+    let res = add_credential(&app, id, "wrong", json!({ "name": "bob", "password": "x" })).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let uri = format!("/v1/wraps/{}", id);
+    let headers = [("x-management-token", "wrong")];
+    let body = json!({ "password": "x" });
+    let res = send(&app, Method::PATCH, &uri, &headers, Some(body)).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn used_up_credential_is_refused() {
+    let app = app();
+    let created = create_wrap(&app, json!({})).await;
+    let id = str_field(&created, "id");
+    let token = str_field(&created, "management_token");
+
+    let body = json!({ "name": "once", "password": "s3cret", "maxUses": 1 });
+    let res = add_credential(&app, id, token, body).await;
+    let credential_id = str_field(&res, "id");
+
+    let res = authorize_credential(&app, id, credential_id, "s3cret").await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = authorize_credential(&app, id, credential_id, "s3cret").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn revoked_credential_is_refused() {
+    let app = app();
+    let created = create_wrap(&app, json!({})).await;
+    let id = str_field(&created, "id");
+    let token = str_field(&created, "management_token");
+
+    let res = add_credential(
+        &app,
+        id,
+        token,
+        json!({ "name": "carol", "password": "s3cret" }),
+    )
+    .await;
+    let credential_id = str_field(&res, "id");
+
+    let uri = format!("/v1/wraps/{}/credentials/{}", id, credential_id);
+    let headers = [("x-management-token", token)];
+    let res = send(&app, Method::DELETE, &uri, &headers, None).await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+
+    let res = authorize_credential(&app, id, credential_id, "s3cret").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let uri = format!("/v1/wraps/{}/credentials", id);
+    let res = send(&app, Method::GET, &uri, &headers, None).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["items"], json!([]));
+}
+
+#[tokio::test]
+async fn credentials_are_limited_per_wrap() {
+    let app = app();
+    let created = create_wrap(&app, json!({})).await;
+    let id = str_field(&created, "id");
+    let token = str_field(&created, "management_token");
+
+    for i in 0..MAX_CREDENTIALS_PER_WRAP {
+        let body = json!({ "name": format!("guest {}", i), "password": "s3cret" });
+        let res = add_credential(&app, id, token, body).await;
+        assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    }
+
+    let body = json!({ "name": "one too many", "password": "s3cret" });
+    let res = add_credential(&app, id, token, body).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(str_field(&res, "errorCode"), "invalid_request");
+}
+
+#[tokio::test]
+async fn zero_knowledge_wrap_cannot_have_credentials() {
+    let app = app();
+    let created = create_wrap(&app, json!({ "zeroKnowledge": true })).await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+    let id = str_field(&created, "id");
+    let token = str_field(&created, "management_token");
+
+    let res = add_credential(
+        &app,
+        id,
+        token,
+        json!({ "name": "dave", "password": "s3cret" }),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
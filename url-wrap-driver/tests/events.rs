@@ -0,0 +1,97 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use axum::Router;
+use common::{create_owned_wrap, create_wrap, register_owner, send, str_field, TestResponse};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+async fn attempt(app: &Router, id: &str, password: &str) -> StatusCode {
+    let uri = format!("/v1/wraps/{}/authorize", id);
+    let headers = [("user-agent", "events-test")];
+    let body = json!({ "password": password });
+    let status = send(app, Method::POST, &uri, &headers, Some(body))
+        .await
+        .status;
+    // event ids only order the events of different milliseconds
+    tokio::time::sleep(Duration::from_millis(2)).await;
+    status
+}
+
+async fn list_events(app: &Router, api_key: &str, id: &str, query: &str) -> TestResponse {
+    let uri = format!("/v1/wraps/{}/events?{}", id, query);
+    let auth = format!("Bearer {}", api_key);
+    send(app, Method::GET, &uri, &[("authorization", &auth)], None).await
+}
+
+fn outcomes(res: &TestResponse) -> Vec<&str> {
+    res.body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["outcome"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn attempts_are_listed_newest_first() {
+    let app = common::app();
+    let api_key = register_owner(&app, "alice").await;
+    let created = create_owned_wrap(&app, &api_key, json!({})).await;
+    let id = str_field(&created, "id");
+
+    assert_eq!(attempt(&app, id, "correct horse").await, StatusCode::OK);
+    for _ in 1..common::LOCKOUT_THRESHOLD {
+        assert_eq!(attempt(&app, id, "wrong").await, StatusCode::UNAUTHORIZED);
+    }
+    assert_eq!(attempt(&app, id, "wrong").await, StatusCode::LOCKED);
+
+    let res = list_events(&app, &api_key, id, "").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        outcomes(&res),
+        ["locked", "wrong_password", "wrong_password", "success"]
+    );
+    assert_eq!(res.body["nextCursor"], Value::Null);
+    let event = &res.body["items"][0];
+    assert_eq!(event["user_agent"], "events-test");
+    assert_eq!(event["credential_id"], Value::Null);
+
+    // the same events page by page
+    let res = list_events(&app, &api_key, id, "limit=3").await;
+    assert_eq!(
+        outcomes(&res),
+        ["locked", "wrong_password", "wrong_password"]
+    );
+    let cursor = str_field(&res, "nextCursor");
+    let res = list_events(&app, &api_key, id, &format!("limit=3&cursor={}", cursor)).await;
+    assert_eq!(outcomes(&res), ["success"]);
+    assert_eq!(res.body["nextCursor"], Value::Null);
+}
+
+#[tokio::test]
+async fn events_are_only_listed_to_the_owner() {
+    let app = common::app();
+    let api_key = register_owner(&app, "alice").await;
+    let created = create_owned_wrap(&app, &api_key, json!({})).await;
+    let id = str_field(&created, "id");
+    assert_eq!(attempt(&app, id, "correct horse").await, StatusCode::OK);
+
+    let uri = format!("/v1/wraps/{}/events", id);
+    let res = send(&app, Method::GET, &uri, &[], None).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let other = register_owner(&app, "mallory").await;
+    let res = list_events(&app, &other, id, "").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    // anonymous wraps have no owner to read them
+    let created = create_wrap(&app, json!({})).await;
+    let anonymous = str_field(&created, "id");
+    assert_eq!(
+        attempt(&app, anonymous, "correct horse").await,
+        StatusCode::OK
+    );
+    let res = list_events(&app, &api_key, anonymous, "").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
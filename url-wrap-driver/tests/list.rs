@@ -0,0 +1,118 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use axum::Router;
+use common::{app, create_owned_wrap, register_owner, send, str_field, TestResponse};
+use serde_json::json;
+use std::time::Duration;
+
+async fn list(app: &Router, api_key: &str, query: &str) -> TestResponse {
+    let auth = format!("Bearer {}", api_key);
+    let uri = format!("/v1/wraps?{}", query);
+    send(app, Method::GET, &uri, &[("authorization", &auth)], None).await
+}
+
+fn ids(res: &TestResponse) -> Vec<String> {
+    res.body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn list_pages_newest_first() {
+    let app = app();
+    let api_key = register_owner(&app, "pager").await;
+
+    let mut created = Vec::new();
+    for i in 0..5 {
+        let comment = format!("wrap {}", i);
+        let res = create_owned_wrap(&app, &api_key, json!({ "comment": comment })).await;
+        assert_eq!(res.status, StatusCode::CREATED);
+        created.push(str_field(&res, "id").to_string());
+    }
+    // ids sort by creation time, down to the millisecond
+    created.sort();
+    created.reverse();
+
+    let mut listed = Vec::new();
+    let mut query = "limit=2".to_string();
+    let mut pages = 0;
+    loop {
+        let res = list(&app, &api_key, &query).await;
+        assert_eq!(res.status, StatusCode::OK);
+        assert!(ids(&res).len() <= 2);
+        listed.extend(ids(&res));
+        pages += 1;
+        match res.body["nextCursor"].as_str() {
+            Some(cursor) => query = format!("limit=2&cursor={}", cursor),
+            None => break,
+        }
+    }
+    assert_eq!(pages, 3);
+    assert_eq!(listed, created);
+}
+
+#[tokio::test]
+async fn list_shows_only_own_wraps() {
+    let app = app();
+    let alice = register_owner(&app, "alice").await;
+    let bob = register_owner(&app, "bob").await;
+
+    let res = create_owned_wrap(&app, &alice, json!({})).await;
+    let alice_wrap = str_field(&res, "id").to_string();
+    create_owned_wrap(&app, &bob, json!({})).await;
+
+    let res = list(&app, &alice, "").await;
+    assert_eq!(ids(&res), vec![alice_wrap]);
+}
+
+#[tokio::test]
+async fn list_filters_by_comment_and_status() {
+    let app = app();
+    let api_key = register_owner(&app, "filter").await;
+
+    let res = create_owned_wrap(&app, &api_key, json!({ "comment": "Invoice March" })).await;
+    let invoice = str_field(&res, "id").to_string();
+    create_owned_wrap(&app, &api_key, json!({ "comment": "holiday photos" })).await;
+    let res = create_owned_wrap(
+        &app,
+        &api_key,
+        json!({ "comment": "short lived", "neverExpires": false, "expiresIn": "1s" }),
+    )
+    .await;
+    let short_lived = str_field(&res, "id").to_string();
+
+    let res = list(&app, &api_key, "q=invoice").await;
+    assert_eq!(ids(&res), vec![invoice]);
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    let res = list(&app, &api_key, "status=expired").await;
+    assert_eq!(ids(&res), vec![short_lived.clone()]);
+
+    let res = list(&app, &api_key, "status=active").await;
+    assert_eq!(ids(&res).len(), 2);
+    assert!(!ids(&res).contains(&short_lived));
+}
+
+#[tokio::test]
+async fn list_rejects_invalid_paging() {
+    let app = app();
+    let api_key = register_owner(&app, "invalid").await;
+
+    for query in ["limit=0", "limit=101", "cursor=not-an-id"] {
+        let res = list(&app, &api_key, query).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
+
+#[tokio::test]
+async fn list_requires_an_api_key() {
+    let app = app();
+
+    let res = send(&app, Method::GET, "/v1/wraps", &[], None).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{app, register_owner, send};
+use serde_json::json;
+
+#[tokio::test]
+async fn registered_api_key_authenticates_the_owner() {
+    let app = app();
+
+    let api_key = register_owner(&app, "alice").await;
+    let auth = format!("Bearer {}", api_key);
+    let res = send(
+        &app,
+        Method::GET,
+        "/v1/wraps",
+        &[("authorization", &auth)],
+        None,
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn register_owner_requires_the_admin_key() {
+    let app = app();
+    let body = json!({ "name": "mallory" });
+
+    let res = send(&app, Method::POST, "/v1/owners", &[], Some(body.clone())).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let headers = [("authorization", "Bearer wrong-key")];
+    let res = send(&app, Method::POST, "/v1/owners", &headers, Some(body)).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn unknown_api_key_is_rejected() {
+    let app = app();
+
+    let headers = [("authorization", "Bearer unknown-key")];
+    let res = send(&app, Method::GET, "/v1/wraps", &headers, None).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
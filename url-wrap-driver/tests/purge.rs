@@ -0,0 +1,50 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use chrono::Duration;
+use common::{app_on, authorize, create_wrap, send, str_field};
+use serde_json::json;
+use std::sync::Arc;
+use url_wrap_driver::module::{Modules, ModulesExt};
+
+#[tokio::test]
+async fn expired_wraps_are_purged_after_the_grace_period() {
+    let modules = Arc::new(Modules::in_memory());
+    let app = app_on(modules.clone());
+
+    let expiring = json!({ "neverExpires": false, "expiresIn": "1s" });
+    let created = create_wrap(&app, expiring).await;
+    let expired_id = str_field(&created, "id").to_string();
+    assert_eq!(
+        authorize(&app, &expired_id, "correct horse").await.status,
+        StatusCode::OK
+    );
+    let created = create_wrap(&app, json!({})).await;
+    let kept_id = str_field(&created, "id").to_string();
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+    // still within the grace period
+    let purged = modules
+        .wrap_use_case()
+        .purge_expired_wraps(Duration::hours(1))
+        .await
+        .unwrap();
+    assert_eq!(purged, 0);
+    let uri = format!("/v1/wraps/{}", expired_id);
+    let res = send(&app, Method::GET, &uri, &[], None).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let purged = modules
+        .wrap_use_case()
+        .purge_expired_wraps(Duration::zero())
+        .await
+        .unwrap();
+    assert_eq!(purged, 1);
+    let res = send(&app, Method::GET, &uri, &[], None).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let uri = format!("/v1/wraps/{}", kept_id);
+    let res = send(&app, Method::GET, &uri, &[], None).await;
+    assert_eq!(res.status, StatusCode::OK);
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{header, HeaderMap, Method, Request, StatusCode};
+use axum::Router;
+use common::{app, create_wrap, str_field};
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Once;
+use std::time::Duration;
+use tower::ServiceExt;
+
+const PROXY: &str = "10.0.0.1";
+
+static INIT: Once = Once::new();
+
+/// Router on which looking up a wrap is limited to one request per second.
+fn limited_app() -> Router {
+    INIT.call_once(|| {
+        env::set_var("RATE_LIMIT_GET_WRAP", "1/1");
+        env::set_var("RATE_LIMIT_TRUSTED_PROXIES", PROXY);
+    });
+    app()
+}
+
+async fn get_wrap(
+    app: &Router,
+    id: &str,
+    peer: &str,
+    forwarded_for: Option<&str>,
+) -> (StatusCode, HeaderMap) {
+    let mut req = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/v1/wraps/{}", id))
+        .extension(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 443)));
+    if let Some(value) = forwarded_for {
+        req = req.header("x-forwarded-for", value);
+    }
+
+    let res = app
+        .clone()
+        .oneshot(req.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    (res.status(), res.headers().clone())
+}
+
+/// Hits the route until the window is used up and returns the 429 headers.
+async fn exhaust(app: &Router, id: &str, peer: &str, forwarded_for: Option<&str>) -> HeaderMap {
+    // the first window may reset between two hits, but not twice within a second
+    for _ in 0..3 {
+        let (status, headers) = get_wrap(app, id, peer, forwarded_for).await;
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return headers;
+        }
+        assert_eq!(status, StatusCode::OK);
+    }
+    panic!("rate limit was never exceeded");
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, key: &str) -> &'a str {
+    headers
+        .get(key)
+        .unwrap_or_else(|| panic!("`{}` is missing", key))
+        .to_str()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn exceeded_limit_is_refused_until_the_window_resets() {
+    let app = limited_app();
+    let created = create_wrap(&app, serde_json::json!({})).await;
+    let id = str_field(&created, "id");
+
+    let headers = exhaust(&app, id, "203.0.113.1", None).await;
+    let retry_after: u64 = header_value(&headers, header::RETRY_AFTER.as_str())
+        .parse()
+        .unwrap();
+    assert_eq!(retry_after, 1);
+    assert_eq!(header_value(&headers, "x-ratelimit-remaining"), "0");
+
+    // other clients have their own budget
+    let (status, headers) = get_wrap(&app, id, "203.0.113.2", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(header_value(&headers, "x-ratelimit-remaining"), "0");
+
+    tokio::time::sleep(Duration::from_secs(retry_after)).await;
+    let (status, _) = get_wrap(&app, id, "203.0.113.1", None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn forwarded_for_of_untrusted_peer_does_not_reset_the_budget() {
+    let app = limited_app();
+    let created = create_wrap(&app, serde_json::json!({})).await;
+    let id = str_field(&created, "id");
+
+    exhaust(&app, id, "203.0.113.3", Some("198.51.100.1")).await;
+    let (status, _) = get_wrap(&app, id, "203.0.113.3", Some("198.51.100.2")).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn client_behind_trusted_proxy_is_the_right_most_untrusted_hop() {
+    let app = limited_app();
+    let created = create_wrap(&app, serde_json::json!({})).await;
+    let id = str_field(&created, "id");
+
+    let forwarded_for = "192.0.2.1, 198.51.100.3";
+    exhaust(&app, id, PROXY, Some(forwarded_for)).await;
+
+    // a different spoofed left-most hop is still the same client
+    let spoofed = "192.0.2.99, 198.51.100.3";
+    let (status, _) = get_wrap(&app, id, PROXY, Some(spoofed)).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // while another client behind the same proxy is not limited
+    let (status, _) = get_wrap(&app, id, PROXY, Some("198.51.100.4")).await;
+    assert_eq!(status, StatusCode::OK);
+}
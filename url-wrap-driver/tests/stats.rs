@@ -0,0 +1,91 @@
+mod common;
+
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use common::{create_owned_wrap, register_owner, send, str_field, TestResponse};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use tower::ServiceExt;
+
+/// Authorizes from `client`, so that unique opens can be told apart.
+async fn attempt(app: &Router, id: &str, password: &str, client: &str) -> StatusCode {
+    let body = json!({ "password": password });
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/v1/wraps/{}/authorize", id))
+        .header(header::CONTENT_TYPE, "application/json")
+        .extension(ConnectInfo(SocketAddr::new(client.parse().unwrap(), 443)))
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    app.clone().oneshot(req).await.unwrap().status()
+}
+
+async fn stats(app: &Router, api_key: &str, id: &str) -> TestResponse {
+    let uri = format!("/v1/wraps/{}/stats", id);
+    let auth = format!("Bearer {}", api_key);
+    send(app, Method::GET, &uri, &[("authorization", &auth)], None).await
+}
+
+/// Sums a field over the buckets, which may straddle an hour or day boundary.
+fn bucket_total(res: &TestResponse, series: &str, field: &str) -> u64 {
+    res.body[series]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v[field].as_u64().unwrap())
+        .sum()
+}
+
+#[tokio::test]
+async fn stats_count_opens_and_failures() {
+    let app = common::app();
+    let api_key = register_owner(&app, "alice").await;
+    let created = create_owned_wrap(&app, &api_key, json!({})).await;
+    let id = str_field(&created, "id");
+
+    let res = stats(&app, &api_key, id).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["total_opens"], 0);
+    assert_eq!(res.body["first_access_at"], Value::Null);
+    assert_eq!(res.body["hourly"], json!([]));
+
+    let attempts = [
+        ("wrong", "203.0.113.1", StatusCode::UNAUTHORIZED),
+        ("correct horse", "203.0.113.1", StatusCode::OK),
+        ("correct horse", "203.0.113.1", StatusCode::OK),
+        ("wrong", "203.0.113.2", StatusCode::UNAUTHORIZED),
+        ("correct horse", "203.0.113.2", StatusCode::OK),
+    ];
+    for (password, client, status) in attempts {
+        assert_eq!(attempt(&app, id, password, client).await, status);
+    }
+
+    let res = stats(&app, &api_key, id).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["total_opens"], 3);
+    assert_eq!(res.body["unique_opens"], 2);
+    assert_eq!(res.body["failed_attempts"], 2);
+    assert!(str_field(&res, "first_access_at") <= str_field(&res, "last_access_at"));
+    for series in ["hourly", "daily"] {
+        assert_eq!(bucket_total(&res, series, "opens"), 3);
+        assert_eq!(bucket_total(&res, series, "failures"), 2);
+    }
+}
+
+#[tokio::test]
+async fn stats_are_only_shown_to_the_owner() {
+    let app = common::app();
+    let api_key = register_owner(&app, "alice").await;
+    let created = create_owned_wrap(&app, &api_key, json!({})).await;
+    let id = str_field(&created, "id");
+
+    let uri = format!("/v1/wraps/{}/stats", id);
+    let res = send(&app, Method::GET, &uri, &[], None).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let other = register_owner(&app, "mallory").await;
+    let res = stats(&app, &other, id).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use axum::Router;
+use common::{app, authorize, create_wrap, send, str_field, TestResponse};
+use serde_json::json;
+
+const MAX_TEXT_PAYLOAD_BYTES: usize = 4096;
+
+async fn create_text_wrap(app: &Router, text: &str) -> TestResponse {
+    let body = json!({ "payloadKind": "text", "redirectUrl": null, "text": text });
+    create_wrap(app, body).await
+}
+
+#[tokio::test]
+async fn text_is_revealed_with_the_password() {
+    let app = app();
+    let created = create_text_wrap(&app, "the vault code is 0451").await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+    let id = str_field(&created, "id");
+
+    let res = send(&app, Method::GET, &format!("/v1/wraps/{}", id), &[], None).await;
+    assert_eq!(str_field(&res, "payload_kind"), "text");
+
+    let res = authorize(&app, id, "correct horse").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(str_field(&res, "text"), "the vault code is 0451");
+    assert_eq!(res.body.get("redirect_url"), None);
+}
+
+#[tokio::test]
+async fn text_size_is_limited() {
+    let app = app();
+
+    let res = create_text_wrap(&app, &"x".repeat(MAX_TEXT_PAYLOAD_BYTES)).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+
+    let res = create_text_wrap(&app, &"x".repeat(MAX_TEXT_PAYLOAD_BYTES + 1)).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(str_field(&res, "errorCode"), "invalid_request");
+
+    let res = create_text_wrap(&app, "").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn payload_must_match_the_kind() {
+    let app = app();
+
+    let body = json!({ "payloadKind": "text", "text": "note" });
+    let res = create_wrap(&app, body).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    let res = create_wrap(&app, json!({ "text": "note" })).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn text_is_updated_but_not_replaced_with_a_url() {
+    let app = app();
+    let created = create_text_wrap(&app, "first").await;
+    let id = str_field(&created, "id");
+    let token = str_field(&created, "management_token");
+    let uri = format!("/v1/wraps/{}", id);
+    let headers = [("x-management-token", token)];
+
+    let body = json!({ "redirectUrl": "https://example.com/elsewhere" });
+    let res = send(&app, Method::PATCH, &uri, &headers, Some(body)).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    let res = send(
+        &app,
+        Method::PATCH,
+        &uri,
+        &headers,
+        Some(json!({ "text": "second" })),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = authorize(&app, id, "correct horse").await;
+    assert_eq!(str_field(&res, "text"), "second");
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{authorize, create_wrap, str_field};
+use data_encoding::BASE32_NOPAD;
+use serde_json::{json, Value};
+use totp_rs::{Algorithm, TOTP};
+
+/// Generator for the secret in the `otpauth://` URI of a new TOTP wrap.
+fn authenticator(totp_uri: &str) -> TOTP {
+    let secret = totp_uri
+        .split(['?', '&'])
+        .find_map(|v| v.strip_prefix("secret="))
+        .unwrap();
+    let secret = BASE32_NOPAD.decode(secret.as_bytes()).unwrap();
+    TOTP::new(Algorithm::SHA1, 6, 0, 30, secret).unwrap()
+}
+
+fn totp_wrap() -> Value {
+    json!({ "authType": 3, "password": Value::Null })
+}
+
+#[tokio::test]
+async fn current_code_authorizes_once() {
+    let app = common::app();
+
+    let created = create_wrap(&app, totp_wrap()).await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+    let id = str_field(&created, "id");
+    let code = authenticator(str_field(&created, "totp_uri"))
+        .generate_current()
+        .unwrap();
+
+    let res = authorize(&app, id, &code).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(
+        str_field(&res, "redirect_url"),
+        "https://example.com/secret"
+    );
+
+    // a code is only good once, even within its period
+    let res = authorize(&app, id, &code).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn older_code_is_rejected_after_a_newer_one() {
+    let app = common::app();
+
+    let created = create_wrap(&app, totp_wrap()).await;
+    let id = str_field(&created, "id");
+    let totp = authenticator(str_field(&created, "totp_uri"));
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let res = authorize(&app, id, &totp.generate(now)).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    // still within the default skew of one step, but older than the accepted code
+    let res = authorize(&app, id, &totp.generate(now - 30)).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn wrong_code_is_rejected() {
+    let app = common::app();
+
+    let created = create_wrap(&app, totp_wrap()).await;
+    let id = str_field(&created, "id");
+
+    let res = authorize(&app, id, "not-a-code").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
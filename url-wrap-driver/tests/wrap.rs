@@ -0,0 +1,215 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{app, authorize, create_wrap, send, str_field, LOCKOUT_THRESHOLD};
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::test]
+async fn create_and_get_wrap() {
+    let app = app();
+
+    let created = create_wrap(&app, json!({ "comment": "hello" })).await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    assert!(!str_field(&created, "management_token").is_empty());
+    // the URL is only revealed with the password
+    assert!(created.body.get("redirect_url").is_none());
+
+    let id = str_field(&created, "id");
+    let found = send(&app, Method::GET, &format!("/v1/wraps/{}", id), &[], None).await;
+    assert_eq!(found.status, StatusCode::OK);
+    assert_eq!(str_field(&found, "comment"), "hello");
+    assert!(found.body.get("redirect_url").is_none());
+}
+
+#[tokio::test]
+async fn create_wrap_rejects_invalid_input() {
+    let app = app();
+
+    let res = create_wrap(&app, json!({ "redirectUrl": "not a url" })).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    let res = create_wrap(&app, json!({ "authType": 2, "password": "12a4" })).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    let res = create_wrap(&app, json!({ "neverExpires": false })).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn get_unknown_wrap_is_not_found() {
+    let app = app();
+
+    let uri = "/v1/wraps/01ARZ3NDEKTSV4RRFFQ69G5FAV";
+    let res = send(&app, Method::GET, uri, &[], None).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn authorize_reveals_redirect_url() {
+    let app = app();
+    let created = create_wrap(&app, json!({})).await;
+    let id = str_field(&created, "id");
+
+    let res = authorize(&app, id, "correct horse").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(
+        str_field(&res, "redirect_url"),
+        "https://example.com/secret"
+    );
+
+    let res = authorize(&app, id, "wrong").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    assert!(res.body.get("redirect_url").is_none());
+}
+
+#[tokio::test]
+async fn repeated_failures_lock_the_wrap() {
+    let app = app();
+    let created = create_wrap(&app, json!({})).await;
+    let id = str_field(&created, "id");
+
+    for _ in 1..LOCKOUT_THRESHOLD {
+        assert_eq!(
+            authorize(&app, id, "wrong").await.status,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+    assert_eq!(
+        authorize(&app, id, "wrong").await.status,
+        StatusCode::LOCKED
+    );
+
+    // even the right password is refused while locked
+    let res = authorize(&app, id, "correct horse").await;
+    assert_eq!(res.status, StatusCode::LOCKED);
+    assert_eq!(str_field(&res, "errorCode"), "locked");
+}
+
+#[tokio::test]
+async fn success_resets_failed_attempts() {
+    let app = app();
+    let created = create_wrap(&app, json!({})).await;
+    let id = str_field(&created, "id");
+
+    for _ in 0..2 {
+        for _ in 1..LOCKOUT_THRESHOLD {
+            assert_eq!(
+                authorize(&app, id, "wrong").await.status,
+                StatusCode::UNAUTHORIZED
+            );
+        }
+        assert_eq!(
+            authorize(&app, id, "correct horse").await.status,
+            StatusCode::OK
+        );
+    }
+}
+
+#[tokio::test]
+async fn expired_wrap_is_refused() {
+    let app = app();
+    let created = create_wrap(&app, json!({ "neverExpires": false, "expiresIn": "1s" })).await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    let id = str_field(&created, "id");
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    let res = authorize(&app, id, "correct horse").await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+    assert_eq!(str_field(&res, "errorCode"), "expired");
+
+    // guesses at an expired wrap are refused before the password is checked
+    for _ in 0..=LOCKOUT_THRESHOLD {
+        let res = authorize(&app, id, "wrong").await;
+        assert_eq!(res.status, StatusCode::FORBIDDEN);
+    }
+}
+
+#[tokio::test]
+async fn expiration_in_the_past_is_rejected() {
+    let app = app();
+
+    let res = create_wrap(&app, json!({ "neverExpires": false, "expirationAt": 1 })).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn max_views_are_consumed() {
+    let app = app();
+    let created = create_wrap(&app, json!({ "maxViews": 2 })).await;
+    let id = str_field(&created, "id");
+
+    let res = authorize(&app, id, "correct horse").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["remaining_views"], 1);
+
+    let res = authorize(&app, id, "correct horse").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["remaining_views"], 0);
+
+    let res = authorize(&app, id, "correct horse").await;
+    assert_eq!(res.status, StatusCode::GONE);
+    assert_eq!(str_field(&res, "errorCode"), "consumed");
+
+    // once consumed the password is no longer checked
+    let res = authorize(&app, id, "wrong").await;
+    assert_eq!(res.status, StatusCode::GONE);
+}
+
+#[tokio::test]
+async fn wrong_management_token_is_refused() {
+    let app = app();
+    let created = create_wrap(&app, json!({})).await;
+    let uri = format!("/v1/wraps/{}", str_field(&created, "id"));
+    let headers = [("x-management-token", "wrong")];
+
+    let body = json!({ "comment": "changed" });
+    let res = send(&app, Method::PATCH, &uri, &headers, Some(body)).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let res = send(&app, Method::DELETE, &uri, &headers, None).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let res = send(&app, Method::GET, &uri, &[], None).await;
+    assert_eq!(str_field(&res, "comment"), "test");
+}
+
+#[tokio::test]
+async fn password_change_replaces_the_old_password() {
+    let app = app();
+    let created = create_wrap(&app, json!({})).await;
+    let id = str_field(&created, "id");
+    let token = str_field(&created, "management_token");
+
+    let uri = format!("/v1/wraps/{}", id);
+    let headers = [("x-management-token", token)];
+    let body = json!({ "password": "battery staple" });
+    let res = send(&app, Method::PATCH, &uri, &headers, Some(body)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = authorize(&app, id, "correct horse").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = authorize(&app, id, "battery staple").await;
+    assert_eq!(res.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn deleted_wrap_is_not_found() {
+    let app = app();
+    let created = create_wrap(&app, json!({})).await;
+    let id = str_field(&created, "id");
+    let token = str_field(&created, "management_token");
+
+    let uri = format!("/v1/wraps/{}", id);
+    let headers = [("x-management-token", token)];
+    let res = send(&app, Method::DELETE, &uri, &headers, None).await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+
+    let res = send(&app, Method::GET, &uri, &[], None).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    let res = authorize(&app, id, "correct horse").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    let res = send(&app, Method::DELETE, &uri, &headers, None).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{app, authorize, create_wrap, send, str_field};
+use serde_json::json;
+
+#[tokio::test]
+async fn url_is_revealed_with_the_password() {
+    let app = app();
+    let created = create_wrap(&app, json!({ "zeroKnowledge": true })).await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+    let id = str_field(&created, "id");
+
+    let res = authorize(&app, id, "correct horse").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        str_field(&res, "redirect_url"),
+        "https://example.com/secret"
+    );
+}
+
+#[tokio::test]
+async fn pin_and_totp_wraps_are_refused() {
+    let app = app();
+
+    // a PIN is too short to protect the derived key against offline guessing
+    let res = create_wrap(
+        &app,
+        json!({ "zeroKnowledge": true, "authType": 2, "password": "1234" }),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(str_field(&res, "errorCode"), "invalid_request");
+
+    let res = create_wrap(
+        &app,
+        json!({ "zeroKnowledge": true, "authType": 3, "password": null }),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn password_and_url_change_together() {
+    let app = app();
+    let created = create_wrap(&app, json!({ "zeroKnowledge": true })).await;
+    let id = str_field(&created, "id");
+    let token = str_field(&created, "management_token");
+    let uri = format!("/v1/wraps/{}", id);
+    let headers = [("x-management-token", token)];
+
+    let body = json!({ "password": "battery staple" });
+    let res = send(&app, Method::PATCH, &uri, &headers, Some(body)).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(str_field(&res, "errorCode"), "invalid_request");
+
+    let body = json!({
+        "password": "battery staple",
+        "redirectUrl": "https://example.com/moved",
+    });
+    let res = send(&app, Method::PATCH, &uri, &headers, Some(body)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = authorize(&app, id, "battery staple").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(str_field(&res, "redirect_url"), "https://example.com/moved");
+}
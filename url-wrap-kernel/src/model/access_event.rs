@@ -0,0 +1,153 @@
+use crate::model::wrap::error::WrapError;
+use crate::model::wrap::Wrap;
+use crate::model::Id;
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+
+pub mod stats;
+
+/// User agents longer than this are truncated before they are stored.
+const USER_AGENT_MAX_CHARS: usize = 512;
+/// Requested ids are stored as given, so they are truncated too; ULIDs and aliases both fit.
+const WRAP_ID_MAX_CHARS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessOutcome {
+    Success,
+    WrongPassword,
+    Expired,
+    NotFound,
+    Locked,
+    Consumed,
+    /// The attempt failed for a reason unrelated to the credentials.
+    Failed,
+}
+
+impl AccessOutcome {
+    pub fn of<T>(res: &Result<T, WrapError>) -> Self {
+        match res {
+            Ok(_) => AccessOutcome::Success,
+            Err(WrapError::InvalidCredentials) => AccessOutcome::WrongPassword,
+            Err(WrapError::Expired) => AccessOutcome::Expired,
+            Err(WrapError::NotFound) => AccessOutcome::NotFound,
+            Err(WrapError::Locked { .. }) => AccessOutcome::Locked,
+            Err(WrapError::Consumed) => AccessOutcome::Consumed,
+            Err(_) => AccessOutcome::Failed,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccessOutcome::Success => "success",
+            AccessOutcome::WrongPassword => "wrong_password",
+            AccessOutcome::Expired => "expired",
+            AccessOutcome::NotFound => "not_found",
+            AccessOutcome::Locked => "locked",
+            AccessOutcome::Consumed => "consumed",
+            AccessOutcome::Failed => "failed",
+        }
+    }
+}
+
+impl TryFrom<&str> for AccessOutcome {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "success" => Ok(AccessOutcome::Success),
+            "wrong_password" => Ok(AccessOutcome::WrongPassword),
+            "expired" => Ok(AccessOutcome::Expired),
+            "not_found" => Ok(AccessOutcome::NotFound),
+            "locked" => Ok(AccessOutcome::Locked),
+            "consumed" => Ok(AccessOutcome::Consumed),
+            "failed" => Ok(AccessOutcome::Failed),
+            _ => Err(anyhow!("Unknown access outcome: {}", value)),
+        }
+    }
+}
+
+/// One attempt to open a wrap.
+pub struct AccessEvent {
+    pub id: Id<AccessEvent>,
+    /// Kept as requested, since attempts on unknown or malformed ids are recorded too.
+    pub wrap_id: String,
+    pub outcome: AccessOutcome,
+    /// Credential that matched, `None` for the primary password and failed attempts.
+    pub credential_id: Option<String>,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl AccessEvent {
+    pub fn new(
+        id: Id<AccessEvent>,
+        wrap_id: String,
+        outcome: AccessOutcome,
+        credential_id: Option<String>,
+        client_ip: Option<String>,
+        user_agent: Option<String>,
+        occurred_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id,
+            wrap_id,
+            outcome,
+            credential_id,
+            client_ip,
+            user_agent,
+            occurred_at,
+        }
+    }
+}
+
+pub struct NewAccessEvent {
+    pub id: Id<AccessEvent>,
+    pub wrap_id: String,
+    pub outcome: AccessOutcome,
+    /// Credential that matched, `None` for the primary password and failed attempts.
+    pub credential_id: Option<String>,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl NewAccessEvent {
+    pub fn new(
+        id: Id<AccessEvent>,
+        wrap_id: String,
+        outcome: AccessOutcome,
+        credential_id: Option<String>,
+        client_ip: Option<String>,
+        user_agent: Option<String>,
+        occurred_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id,
+            wrap_id: wrap_id.chars().take(WRAP_ID_MAX_CHARS).collect(),
+            outcome,
+            credential_id,
+            client_ip,
+            user_agent: user_agent.map(|v| v.chars().take(USER_AGENT_MAX_CHARS).collect()),
+            occurred_at,
+        }
+    }
+}
+
+/// Newest-first page of the events of one wrap.
+pub struct AccessEventListQuery {
+    pub wrap_id: Id<Wrap>,
+    /// Only events older than this one are listed.
+    pub cursor: Option<Id<AccessEvent>>,
+    pub limit: u32,
+}
+
+impl AccessEventListQuery {
+    pub fn new(wrap_id: Id<Wrap>, cursor: Option<Id<AccessEvent>>, limit: u32) -> Self {
+        Self {
+            wrap_id,
+            cursor,
+            limit,
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::model::access_event::{AccessEvent, AccessOutcome};
+use crate::model::wrap::Wrap;
+use crate::model::Id;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::collections::{BTreeMap, HashSet};
+
+/// Hours covered by `AccessStats::hourly`, the current one included.
+const HOURLY_BUCKETS: i64 = 48;
+/// Days covered by `AccessStats::daily`, the current one included.
+const DAILY_BUCKETS: i64 = 30;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Granularity {
+    Hour,
+    Day,
+}
+
+impl Granularity {
+    pub fn millis(&self) -> i64 {
+        match self {
+            Granularity::Hour => 3_600_000,
+            Granularity::Day => 86_400_000,
+        }
+    }
+
+    /// Start of the UTC hour or day that contains `at`.
+    pub fn truncate(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let millis = at.timestamp_millis();
+        Utc.timestamp_millis_opt(millis - millis.rem_euclid(self.millis()))
+            .single()
+            .unwrap_or(at)
+    }
+}
+
+pub struct AccessStatsQuery {
+    pub wrap_id: Id<Wrap>,
+    /// Start of the oldest hourly bucket.
+    pub hourly_since: DateTime<Utc>,
+    /// Start of the oldest daily bucket.
+    pub daily_since: DateTime<Utc>,
+}
+
+impl AccessStatsQuery {
+    pub fn new(wrap_id: Id<Wrap>, now: DateTime<Utc>) -> Self {
+        Self {
+            wrap_id,
+            hourly_since: Granularity::Hour.truncate(now) - Duration::hours(HOURLY_BUCKETS - 1),
+            daily_since: Granularity::Day.truncate(now) - Duration::days(DAILY_BUCKETS - 1),
+        }
+    }
+}
+
+/// Attempts within one hour or day. Buckets without any attempt are left out.
+#[derive(Debug)]
+pub struct AccessBucket {
+    pub start: DateTime<Utc>,
+    pub opens: u64,
+    pub failures: u64,
+}
+
+impl AccessBucket {
+    pub fn new(start: DateTime<Utc>, opens: u64, failures: u64) -> Self {
+        Self {
+            start,
+            opens,
+            failures,
+        }
+    }
+}
+
+pub struct AccessStats {
+    /// Successful opens.
+    pub total_opens: u64,
+    /// Distinct client addresses among the successful opens.
+    pub unique_opens: u64,
+    /// Attempts with any outcome other than success.
+    pub failed_attempts: u64,
+    pub first_access_at: Option<DateTime<Utc>>,
+    pub last_access_at: Option<DateTime<Utc>>,
+    /// Oldest first.
+    pub hourly: Vec<AccessBucket>,
+    /// Oldest first.
+    pub daily: Vec<AccessBucket>,
+}
+
+impl AccessStats {
+    pub fn new(
+        total_opens: u64,
+        unique_opens: u64,
+        failed_attempts: u64,
+        first_access_at: Option<DateTime<Utc>>,
+        last_access_at: Option<DateTime<Utc>>,
+        hourly: Vec<AccessBucket>,
+        daily: Vec<AccessBucket>,
+    ) -> Self {
+        Self {
+            total_opens,
+            unique_opens,
+            failed_attempts,
+            first_access_at,
+            last_access_at,
+            hourly,
+            daily,
+        }
+    }
+
+    /// Computes the statistics in process, for stores that cannot aggregate themselves.
+    pub fn from_events(events: &[AccessEvent], query: &AccessStatsQuery) -> Self {
+        let opens: Vec<&AccessEvent> = events
+            .iter()
+            .filter(|e| e.outcome == AccessOutcome::Success)
+            .collect();
+        let unique_opens = opens
+            .iter()
+            .filter_map(|e| e.client_ip.as_deref())
+            .collect::<HashSet<_>>()
+            .len();
+
+        Self::new(
+            opens.len() as u64,
+            unique_opens as u64,
+            (events.len() - opens.len()) as u64,
+            events.iter().map(|e| e.occurred_at).min(),
+            events.iter().map(|e| e.occurred_at).max(),
+            buckets(events, Granularity::Hour, query.hourly_since),
+            buckets(events, Granularity::Day, query.daily_since),
+        )
+    }
+}
+
+fn buckets(
+    events: &[AccessEvent],
+    granularity: Granularity,
+    since: DateTime<Utc>,
+) -> Vec<AccessBucket> {
+    let mut counts: BTreeMap<DateTime<Utc>, (u64, u64)> = BTreeMap::new();
+    for event in events.iter().filter(|e| e.occurred_at >= since) {
+        let count = counts
+            .entry(granularity.truncate(event.occurred_at))
+            .or_default();
+        match event.outcome {
+            AccessOutcome::Success => count.0 += 1,
+            _ => count.1 += 1,
+        }
+    }
+
+    counts
+        .into_iter()
+        .map(|(start, (opens, failures))| AccessBucket::new(start, opens, failures))
+        .collect()
+}
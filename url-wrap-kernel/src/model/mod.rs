@@ -1,7 +1,14 @@
 use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use rand::rngs::OsRng;
+use rand::RngCore;
 use std::marker::PhantomData;
 use ulid::Ulid;
 
+pub mod access_event;
+pub mod owner;
+pub mod page;
+pub mod rate_limit;
 pub mod wrap;
 
 pub struct Id<T> {
@@ -31,3 +38,17 @@ impl<T> TryFrom<String> for Id<T> {
             .map_err(|err| anyhow!("{:?}", err))
     }
 }
+
+/// Seconds from `now` to `deadline` for `Retry-After`, rounded up to at least one second
+/// so clients never retry too early.
+pub fn seconds_until(deadline: DateTime<Utc>, now: DateTime<Utc>) -> i64 {
+    let millis = (deadline - now).num_milliseconds();
+    (millis + 999).div_euclid(1000).max(1)
+}
+
+/// Hex encoded 256-bit random secret for tokens and API keys.
+pub(crate) fn gen_secret() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,49 @@
+use crate::model::{gen_secret, Id};
+use chrono::{DateTime, Utc};
+
+pub struct Owner {
+    pub id: Id<Owner>,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Owner {
+    pub fn new(id: Id<Owner>, name: String, created_at: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            name,
+            created_at,
+        }
+    }
+}
+
+pub struct NewOwner {
+    pub id: Id<Owner>,
+    pub name: String,
+}
+
+impl NewOwner {
+    pub fn new(id: Id<Owner>, name: String) -> Self {
+        Self { id, name }
+    }
+}
+
+/// Secret that authenticates an owner. Only its hash is stored.
+pub struct ApiKey(pub String);
+
+impl ApiKey {
+    pub fn gen() -> Self {
+        Self(gen_secret())
+    }
+}
+
+pub struct NewApiKey {
+    pub owner_id: Id<Owner>,
+    pub api_key: ApiKey,
+}
+
+impl NewApiKey {
+    pub fn new(owner_id: Id<Owner>, api_key: ApiKey) -> Self {
+        Self { owner_id, api_key }
+    }
+}
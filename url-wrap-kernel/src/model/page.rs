@@ -0,0 +1,22 @@
+use crate::model::Id;
+
+/// One page of a listing ordered by id.
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor for the next page, `None` on the last page.
+    pub next_cursor: Option<Id<T>>,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from up to `limit + 1` items; the extra one only tells that more remain.
+    pub fn new(mut items: Vec<T>, limit: u32, id: impl Fn(&T) -> &Id<T>) -> Self {
+        let has_more = items.len() > limit as usize;
+        items.truncate(limit as usize);
+
+        let next_cursor = match (has_more, items.last()) {
+            (true, Some(last)) => Some(Id::new(id(last).value)),
+            _ => None,
+        };
+        Self { items, next_cursor }
+    }
+}
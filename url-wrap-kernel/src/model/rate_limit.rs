@@ -0,0 +1,18 @@
+use chrono::{DateTime, Utc};
+
+/// Requests counted for one client in one fixed window.
+pub struct RateLimit {
+    pub key: String,
+    pub count: u64,
+    pub reset_at: DateTime<Utc>,
+}
+
+impl RateLimit {
+    pub fn new(key: String, count: u64, reset_at: DateTime<Utc>) -> Self {
+        Self {
+            key,
+            count,
+            reset_at,
+        }
+    }
+}
@@ -1,37 +1,74 @@
+pub mod alias;
 pub mod auth_type;
+pub mod credential;
+pub mod error;
+pub mod payload;
+pub mod query;
+pub mod totp;
 
+use crate::model::owner::Owner;
+use crate::model::wrap::alias::WrapAlias;
 use crate::model::wrap::auth_type::WrapAuthType;
-use crate::model::Id;
+use crate::model::wrap::error::WrapError;
+use crate::model::wrap::payload::WrapPayloadKind;
+use crate::model::wrap::totp::TotpSecret;
+use crate::model::{gen_secret, Id};
 use chrono::{DateTime, Utc};
 
 pub struct Wrap {
     pub id: Id<Wrap>,
-    pub redirect_url: String,
+    /// The URL, or the note of text wraps. `None` for zero-knowledge wraps,
+    /// unless they were opened with their password.
+    pub redirect_url: Option<String>,
+    pub payload_kind: WrapPayloadKind,
+    /// Empty for TOTP wraps, whose secret never leaves the repository.
     pub password: PHCString,
     pub auth_type: WrapAuthType,
     pub comment: String,
-    pub expiration_at: DateTime<Utc>,
+    /// `None` when the wrap never expires.
+    pub expiration_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// `None` when the wrap can be viewed any number of times.
+    pub max_views: Option<u32>,
+    pub remaining_views: Option<u32>,
+    /// `None` for wraps created anonymously.
+    pub owner_id: Option<Id<Owner>>,
+    pub alias: Option<String>,
+    /// The redirect URL is encrypted with a key derived from the password.
+    pub zero_knowledge: bool,
 }
 
 impl Wrap {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: Id<Wrap>,
-        redirect_url: String,
+        redirect_url: Option<String>,
+        payload_kind: WrapPayloadKind,
         password: PHCString,
         auth_type: WrapAuthType,
         comment: String,
-        expiration_at: DateTime<Utc>,
+        expiration_at: Option<DateTime<Utc>>,
         created_at: DateTime<Utc>,
+        max_views: Option<u32>,
+        remaining_views: Option<u32>,
+        owner_id: Option<Id<Owner>>,
+        alias: Option<String>,
+        zero_knowledge: bool,
     ) -> Self {
         Self {
             id,
             redirect_url,
+            payload_kind,
             password,
             auth_type,
             comment,
             expiration_at,
             created_at,
+            max_views,
+            remaining_views,
+            owner_id,
+            alias,
+            zero_knowledge,
         }
     }
 }
@@ -44,29 +81,135 @@ impl From<String> for PHCString {
     }
 }
 
+/// Secret that lets the creator of a wrap update or delete it. Only its hash is stored.
+pub struct ManagementToken(pub String);
+
+impl ManagementToken {
+    pub fn gen() -> Self {
+        Self(gen_secret())
+    }
+}
+
+/// What a visitor has to prove to open a wrap.
+pub enum WrapSecret {
+    Password(String),
+    Totp(TotpSecret),
+}
+
 pub struct NewWrap {
     pub id: Id<Wrap>,
+    /// The URL, or the note of text wraps.
     pub redirect_url: String,
-    pub password: String,
+    pub payload_kind: WrapPayloadKind,
+    pub secret: WrapSecret,
     pub auth_type: WrapAuthType,
     pub comment: String,
-    pub expiration_at: DateTime<Utc>,
+    pub expiration_at: Option<DateTime<Utc>>,
+    pub max_views: Option<u32>,
+    pub management_token: ManagementToken,
+    pub owner_id: Option<Id<Owner>>,
+    pub alias: Option<WrapAlias>,
+    pub zero_knowledge: bool,
 }
 
 impl NewWrap {
+    /// Fails when the password does not satisfy the policy of `auth_type`,
+    /// or a text payload exceeds `MAX_TEXT_PAYLOAD_BYTES`.
+    /// TOTP wraps take no password, their secret is generated here.
+    /// Zero-knowledge wraps need a static text password to derive their key from,
+    /// a PIN is too short to resist guessing offline.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: Id<Wrap>,
         redirect_url: String,
-        password: String,
+        payload_kind: WrapPayloadKind,
+        password: Option<String>,
         auth_type: WrapAuthType,
         comment: String,
-        expiration_at: DateTime<Utc>,
+        expiration_at: Option<DateTime<Utc>>,
+        max_views: Option<u32>,
+        management_token: ManagementToken,
+        owner_id: Option<Id<Owner>>,
+        alias: Option<WrapAlias>,
+        zero_knowledge: bool,
+    ) -> Result<Self, WrapError> {
+        payload_kind.validate(&redirect_url)?;
+        match auth_type {
+            WrapAuthType::Totp if zero_knowledge => {
+                return Err(WrapError::InvalidInput(
+                    "TOTP wraps cannot be zero-knowledge.".to_string(),
+                ))
+            }
+            WrapAuthType::Pin(_) if zero_knowledge => {
+                return Err(WrapError::InvalidInput(
+                    "PIN wraps cannot be zero-knowledge.".to_string(),
+                ))
+            }
+            _ => {}
+        }
+
+        let secret = match (auth_type, password) {
+            (WrapAuthType::Totp, None) => WrapSecret::Totp(TotpSecret::gen()),
+            (_, Some(password)) => {
+                auth_type.validate_password(&password)?;
+                WrapSecret::Password(password)
+            }
+            (_, None) => {
+                return Err(WrapError::InvalidInput("`password` is null.".to_string()));
+            }
+        };
+
+        Ok(Self {
+            id,
+            redirect_url,
+            payload_kind,
+            secret,
+            auth_type,
+            comment,
+            expiration_at,
+            max_views,
+            management_token,
+            owner_id,
+            alias,
+            zero_knowledge,
+        })
+    }
+
+    /// `otpauth://` URI for TOTP wraps, labelled with the alias when there is one.
+    pub fn totp_provisioning_uri(&self) -> Option<String> {
+        match &self.secret {
+            WrapSecret::Totp(secret) => {
+                let account = match &self.alias {
+                    Some(alias) => alias.as_str().to_string(),
+                    None => self.id.value.to_string(),
+                };
+                Some(secret.provisioning_uri(&account))
+            }
+            WrapSecret::Password(_) => None,
+        }
+    }
+}
+
+/// Changes to an existing wrap. `None` leaves the field as it is.
+pub struct WrapUpdate {
+    /// The new URL, or the new note of text wraps.
+    pub redirect_url: Option<String>,
+    pub password: Option<String>,
+    pub comment: Option<String>,
+    /// `Some(None)` makes the wrap never expire.
+    pub expiration_at: Option<Option<DateTime<Utc>>>,
+}
+
+impl WrapUpdate {
+    pub fn new(
+        redirect_url: Option<String>,
+        password: Option<String>,
+        comment: Option<String>,
+        expiration_at: Option<Option<DateTime<Utc>>>,
     ) -> Self {
         Self {
-            id,
             redirect_url,
             password,
-            auth_type,
             comment,
             expiration_at,
         }
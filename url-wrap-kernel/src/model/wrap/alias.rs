@@ -0,0 +1,92 @@
+use crate::model::wrap::error::WrapError;
+use crate::model::wrap::Wrap;
+use crate::model::Id;
+use ulid::Ulid;
+
+const MIN_LEN: usize = 3;
+const MAX_LEN: usize = 64;
+
+/// Aliases that would be confused with routes or look official.
+const RESERVED: [&str; 16] = [
+    "admin",
+    "api",
+    "auth",
+    "authorize",
+    "events",
+    "hc",
+    "health",
+    "help",
+    "login",
+    "new",
+    "owners",
+    "stats",
+    "static",
+    "support",
+    "v1",
+    "wraps",
+];
+
+/// Human readable name of a wrap, usable wherever a wrap id is accepted.
+/// Lowercase letters, digits and inner hyphens, unique per deployment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrapAlias(String);
+
+impl WrapAlias {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for WrapAlias {
+    type Error = WrapError;
+
+    /// Aliases are case-insensitive and stored in lowercase.
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let alias = value.to_ascii_lowercase();
+
+        let valid_charset = alias
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !(MIN_LEN..=MAX_LEN).contains(&alias.len())
+            || !valid_charset
+            || alias.starts_with('-')
+            || alias.ends_with('-')
+        {
+            return Err(WrapError::InvalidInput(format!(
+                "`alias` must be {} to {} letters, digits or inner hyphens.",
+                MIN_LEN, MAX_LEN
+            )));
+        }
+        if RESERVED.contains(&alias.as_str()) {
+            return Err(WrapError::InvalidInput("`alias` is reserved.".to_string()));
+        }
+        // it would be resolved as an id instead
+        if Ulid::from_string(&alias).is_ok() {
+            return Err(WrapError::InvalidInput(
+                "`alias` must not be a wrap id.".to_string(),
+            ));
+        }
+
+        Ok(Self(alias))
+    }
+}
+
+/// What a client used to name a wrap: its id, or else its alias.
+pub enum WrapRef {
+    Id(Id<Wrap>),
+    Alias(WrapAlias),
+}
+
+impl TryFrom<String> for WrapRef {
+    type Error = WrapError;
+
+    /// Neither an id nor a valid alias can match a wrap, so it is reported as not found.
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if let Ok(id) = Ulid::from_string(&value) {
+            return Ok(WrapRef::Id(Id::new(id)));
+        }
+        WrapAlias::try_from(value)
+            .map(WrapRef::Alias)
+            .map_err(|_| WrapError::NotFound)
+    }
+}
@@ -1,40 +1,89 @@
+use crate::model::wrap::error::WrapError;
 use anyhow::anyhow;
 use std::fmt;
 use std::fmt::Formatter;
 
-#[derive(Eq, PartialEq)]
+/// How the password of a wrap is entered, which also decides what passwords are accepted.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum WrapAuthType {
     Text,
-    FourDigit,
+    Pin(PinLength),
+    /// Rotating RFC 6238 codes instead of a static password.
+    Totp,
 }
 
 impl WrapAuthType {
     pub fn id(&self) -> u32 {
         match self {
             WrapAuthType::Text => 1,
-            WrapAuthType::FourDigit => 2,
+            WrapAuthType::Pin(_) => 2,
+            WrapAuthType::Totp => 3,
         }
     }
-}
 
-impl From<String> for WrapAuthType {
-    fn from(type_name: String) -> Self {
-        match &*type_name {
-            "Text" => WrapAuthType::Text,
-            "FourDigit" => WrapAuthType::FourDigit,
-            _ => WrapAuthType::Text,
+    /// `pin_length` is only meaningful for PIN wraps and defaults to four digits.
+    pub fn new(type_id: u32, pin_length: Option<u32>) -> Result<Self, WrapError> {
+        match (type_id, pin_length) {
+            (1, None) => Ok(WrapAuthType::Text),
+            (3, None) => Ok(WrapAuthType::Totp),
+            (1 | 3, Some(_)) => Err(WrapError::InvalidInput(
+                "`pinLength` is only allowed for PIN wraps.".to_string(),
+            )),
+            (2, pin_length) => pin_length
+                .map_or(Ok(PinLength::DEFAULT), PinLength::try_from)
+                .map(WrapAuthType::Pin),
+            _ => Err(WrapError::InvalidInput(
+                "`authType` is 1, 2 or 3.".to_string(),
+            )),
+        }
+    }
+
+    pub fn pin_length(&self) -> Option<u32> {
+        match self {
+            WrapAuthType::Pin(length) => Some(length.value()),
+            _ => None,
+        }
+    }
+
+    /// Checks a password against the policy of this auth type before it is hashed.
+    pub fn validate_password(&self, password: &str) -> Result<(), WrapError> {
+        match self {
+            WrapAuthType::Text if password.is_empty() => {
+                Err(WrapError::InvalidInput("`password` is empty.".to_string()))
+            }
+            WrapAuthType::Pin(length)
+                if password.len() != length.value() as usize
+                    || !password.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                Err(WrapError::InvalidInput(format!(
+                    "`password` must be exactly {} digits.",
+                    length.value()
+                )))
+            }
+            WrapAuthType::Totp => Err(WrapError::InvalidInput(
+                "`password` cannot be set on TOTP wraps.".to_string(),
+            )),
+            _ => Ok(()),
         }
     }
 }
 
-impl TryFrom<u32> for WrapAuthType {
+/// Stored names. `FourDigit` predates configurable PIN lengths; stored wraps are renamed
+/// to `Pin4` when they are upgraded, but the old name is still understood.
+impl TryFrom<String> for WrapAuthType {
     type Error = anyhow::Error;
 
-    fn try_from(type_id: u32) -> Result<Self, Self::Error> {
-        match type_id {
-            1 => Ok(WrapAuthType::Text),
-            2 => Ok(WrapAuthType::FourDigit),
-            _ => Err(anyhow!("WrapAuthTypeId is invalid value.")),
+    fn try_from(type_name: String) -> Result<Self, Self::Error> {
+        match &*type_name {
+            "Text" => Ok(WrapAuthType::Text),
+            "FourDigit" => Ok(WrapAuthType::Pin(PinLength::DEFAULT)),
+            "Totp" => Ok(WrapAuthType::Totp),
+            name => name
+                .strip_prefix("Pin")
+                .and_then(|v| v.parse::<u32>().ok())
+                .and_then(|v| PinLength::try_from(v).ok())
+                .map(WrapAuthType::Pin)
+                .ok_or_else(|| anyhow!("Unknown auth type `{}`.", type_name)),
         }
     }
 }
@@ -43,7 +92,38 @@ impl fmt::Display for WrapAuthType {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             WrapAuthType::Text => write!(f, "Text"),
-            WrapAuthType::FourDigit => write!(f, "FourDigit"),
+            WrapAuthType::Pin(length) => write!(f, "Pin{}", length.value()),
+            WrapAuthType::Totp => write!(f, "Totp"),
+        }
+    }
+}
+
+/// Number of digits of a PIN, 4 to 12.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PinLength(u32);
+
+impl PinLength {
+    pub const MIN: u32 = 4;
+    pub const MAX: u32 = 12;
+    pub const DEFAULT: PinLength = PinLength(4);
+
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+impl TryFrom<u32> for PinLength {
+    type Error = WrapError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        if (Self::MIN..=Self::MAX).contains(&value) {
+            Ok(Self(value))
+        } else {
+            Err(WrapError::InvalidInput(format!(
+                "`pinLength` is minimum {} and maximum {}.",
+                Self::MIN,
+                Self::MAX
+            )))
         }
     }
 }
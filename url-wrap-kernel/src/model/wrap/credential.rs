@@ -0,0 +1,96 @@
+use crate::model::wrap::error::WrapError;
+use crate::model::wrap::Wrap;
+use crate::model::Id;
+use chrono::{DateTime, Utc};
+
+/// Upper bound of named credentials per wrap.
+pub const MAX_CREDENTIALS_PER_WRAP: usize = 20;
+const NAME_MAX_CHARS: usize = 64;
+
+/// Additional password of a wrap, handed to one recipient and revocable on its own.
+pub struct WrapCredential {
+    pub id: Id<WrapCredential>,
+    pub name: String,
+    /// `None` when the credential never expires.
+    pub expiration_at: Option<DateTime<Utc>>,
+    /// `None` when the credential can be used any number of times.
+    pub max_uses: Option<u32>,
+    pub uses: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WrapCredential {
+    pub fn new(
+        id: Id<WrapCredential>,
+        name: String,
+        expiration_at: Option<DateTime<Utc>>,
+        max_uses: Option<u32>,
+        uses: u32,
+        created_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            expiration_at,
+            max_uses,
+            uses,
+            created_at,
+        }
+    }
+}
+
+pub struct NewWrapCredential {
+    pub id: Id<WrapCredential>,
+    pub name: String,
+    pub password: String,
+    pub expiration_at: Option<DateTime<Utc>>,
+    pub max_uses: Option<u32>,
+}
+
+impl NewWrapCredential {
+    /// The password is checked against the policy of the wrap by the caller.
+    pub fn new(
+        id: Id<WrapCredential>,
+        name: String,
+        password: String,
+        expiration_at: Option<DateTime<Utc>>,
+        max_uses: Option<u32>,
+    ) -> Result<Self, WrapError> {
+        let name = name.trim().to_string();
+        if name.is_empty() || name.chars().count() > NAME_MAX_CHARS {
+            return Err(WrapError::InvalidInput(format!(
+                "`name` must be 1 to {} characters.",
+                NAME_MAX_CHARS
+            )));
+        }
+        if max_uses == Some(0) {
+            return Err(WrapError::InvalidInput(
+                "`maxUses` is minimum 1.".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            id,
+            name,
+            password,
+            expiration_at,
+            max_uses,
+        })
+    }
+}
+
+/// A wrap whose password was verified, with the credential that matched.
+pub struct VerifiedWrap {
+    pub wrap: Wrap,
+    /// `None` when the primary password of the wrap matched.
+    pub credential_id: Option<Id<WrapCredential>>,
+}
+
+impl VerifiedWrap {
+    pub fn new(wrap: Wrap, credential_id: Option<Id<WrapCredential>>) -> Self {
+        Self {
+            wrap,
+            credential_id,
+        }
+    }
+}
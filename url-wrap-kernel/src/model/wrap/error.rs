@@ -0,0 +1,69 @@
+use crate::model::seconds_until;
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum WrapError {
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("Wrap is not found.")]
+    NotFound,
+    #[error("Authentication failed.")]
+    InvalidCredentials,
+    #[error("Expiration date has expired.")]
+    Expired,
+    #[error("Wrap has reached its view limit.")]
+    Consumed,
+    #[error("Wrap is locked until {}.", .locked_until.to_rfc3339())]
+    Locked { locked_until: DateTime<Utc> },
+    #[error("Alias is already taken.")]
+    AliasTaken,
+    #[error("Wrap is corrupted: {0}")]
+    Corrupted(anyhow::Error),
+    /// Hashing, encryption or configuration failed.
+    #[error(transparent)]
+    Internal(anyhow::Error),
+    /// The database or store is unreachable or failed.
+    #[error(transparent)]
+    Infrastructure(anyhow::Error),
+}
+
+impl WrapError {
+    /// Seconds until the next attempt is accepted, rounded up to at least one second.
+    pub fn retry_after(&self, now: DateTime<Utc>) -> Option<i64> {
+        match self {
+            WrapError::Locked { locked_until } => Some(seconds_until(*locked_until, now)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn retry_after_rounds_up() {
+        let now = Utc::now();
+        let locked = |millis| WrapError::Locked {
+            locked_until: now + Duration::milliseconds(millis),
+        };
+
+        assert_eq!(locked(1).retry_after(now), Some(1));
+        assert_eq!(locked(1000).retry_after(now), Some(1));
+        assert_eq!(locked(1001).retry_after(now), Some(2));
+        assert_eq!(locked(59_500).retry_after(now), Some(60));
+    }
+
+    #[test]
+    fn retry_after_is_at_least_one_second() {
+        let now = Utc::now();
+        let locked = WrapError::Locked {
+            locked_until: now - Duration::seconds(5),
+        };
+
+        assert_eq!(locked.retry_after(now), Some(1));
+        assert_eq!(WrapError::NotFound.retry_after(now), None);
+    }
+}
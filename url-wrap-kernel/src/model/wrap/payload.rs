@@ -0,0 +1,52 @@
+use crate::model::wrap::error::WrapError;
+use anyhow::anyhow;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// Upper bound of a text payload in bytes, before it is encrypted.
+pub const MAX_TEXT_PAYLOAD_BYTES: usize = 4096;
+
+/// What a wrap reveals once it is opened: a URL to redirect to, or a secret note.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum WrapPayloadKind {
+    Url,
+    Text,
+}
+
+impl WrapPayloadKind {
+    /// Checks a payload before it is encrypted. URLs are validated by the driver.
+    pub fn validate(&self, payload: &str) -> Result<(), WrapError> {
+        match self {
+            WrapPayloadKind::Text
+                if payload.is_empty() || payload.len() > MAX_TEXT_PAYLOAD_BYTES =>
+            {
+                Err(WrapError::InvalidInput(format!(
+                    "`text` must be 1 to {} bytes.",
+                    MAX_TEXT_PAYLOAD_BYTES
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl TryFrom<String> for WrapPayloadKind {
+    type Error = anyhow::Error;
+
+    fn try_from(kind_name: String) -> Result<Self, Self::Error> {
+        match &*kind_name {
+            "Url" => Ok(WrapPayloadKind::Url),
+            "Text" => Ok(WrapPayloadKind::Text),
+            _ => Err(anyhow!("Unknown payload kind `{}`.", kind_name)),
+        }
+    }
+}
+
+impl fmt::Display for WrapPayloadKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WrapPayloadKind::Url => write!(f, "Url"),
+            WrapPayloadKind::Text => write!(f, "Text"),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::model::owner::Owner;
+use crate::model::wrap::Wrap;
+use crate::model::Id;
+use chrono::{DateTime, Utc};
+
+pub enum WrapStatus {
+    /// Never expires or expires after `WrapListQuery::now`.
+    Active,
+    Expired,
+}
+
+/// Newest-first page of an owner's wraps. Ids are ULIDs, so ordering by id orders by creation.
+pub struct WrapListQuery {
+    pub owner_id: Id<Owner>,
+    /// Only wraps older than this one are listed.
+    pub cursor: Option<Id<Wrap>>,
+    pub limit: u32,
+    pub status: Option<WrapStatus>,
+    /// Case-insensitive substring of `comment`.
+    pub comment: Option<String>,
+    pub now: DateTime<Utc>,
+}
+
+impl WrapListQuery {
+    pub fn new(
+        owner_id: Id<Owner>,
+        cursor: Option<Id<Wrap>>,
+        limit: u32,
+        status: Option<WrapStatus>,
+        comment: Option<String>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            owner_id,
+            cursor,
+            limit,
+            status,
+            comment,
+            now,
+        }
+    }
+
+    /// Whether a wrap with these fields satisfies the status and comment filters.
+    pub fn matches(&self, expiration_at: Option<DateTime<Utc>>, comment: &str) -> bool {
+        let status = match self.status {
+            Some(WrapStatus::Active) => expiration_at.is_none_or(|v| v > self.now),
+            Some(WrapStatus::Expired) => expiration_at.is_some_and(|v| v <= self.now),
+            None => true,
+        };
+        let comment = match &self.comment {
+            Some(q) => comment.to_lowercase().contains(&q.to_lowercase()),
+            None => true,
+        };
+        status && comment
+    }
+}
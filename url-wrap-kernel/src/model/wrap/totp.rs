@@ -0,0 +1,38 @@
+use data_encoding::BASE32_NOPAD;
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+/// Shown as the account issuer by authenticator apps.
+pub const TOTP_ISSUER: &str = "url-wrap";
+/// RFC 6238 parameters shared by the provisioning URI and the verifier.
+pub const TOTP_DIGITS: usize = 6;
+pub const TOTP_PERIOD_SECONDS: u64 = 30;
+
+/// Shared secret of a TOTP wrap, RFC 4226 recommends 160 bits.
+pub struct TotpSecret(Vec<u8>);
+
+impl TotpSecret {
+    pub fn gen() -> Self {
+        let mut bytes = vec![0u8; 20];
+        OsRng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    pub fn to_base32(&self) -> String {
+        BASE32_NOPAD.encode(&self.0)
+    }
+
+    /// `otpauth://` URI to register the secret in an authenticator app.
+    /// `account` must be URI safe, like a wrap id or alias.
+    pub fn provisioning_uri(&self, account: &str) -> String {
+        format!(
+            "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}\
+             &algorithm=SHA1&digits={digits}&period={period}",
+            issuer = TOTP_ISSUER,
+            account = account,
+            secret = self.to_base32(),
+            digits = TOTP_DIGITS,
+            period = TOTP_PERIOD_SECONDS,
+        )
+    }
+}
@@ -0,0 +1,11 @@
+use crate::model::access_event::stats::{AccessStats, AccessStatsQuery};
+use crate::model::access_event::{AccessEvent, AccessEventListQuery, NewAccessEvent};
+use crate::model::page::Page;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait AccessLogRepository {
+    async fn insert(&self, source: NewAccessEvent) -> anyhow::Result<()>;
+    async fn list(&self, query: &AccessEventListQuery) -> anyhow::Result<Page<AccessEvent>>;
+    async fn stats(&self, query: &AccessStatsQuery) -> anyhow::Result<AccessStats>;
+}
@@ -0,0 +1,9 @@
+use crate::model::owner::Owner;
+use crate::model::Id;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait ApiKeyRepository {
+    /// Returns the owner the key was issued to, or `None` for an unknown key.
+    async fn find_owner_id(&self, api_key: &str) -> anyhow::Result<Option<Id<Owner>>>;
+}
@@ -1 +1,5 @@
+pub mod access_log;
+pub mod api_key;
+pub mod owner;
+pub mod rate_limit;
 pub mod wrap;
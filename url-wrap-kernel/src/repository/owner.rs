@@ -0,0 +1,10 @@
+use crate::model::owner::{NewApiKey, NewOwner, Owner};
+use crate::model::Id;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait OwnerRepository {
+    async fn get(&self, id: &Id<Owner>) -> anyhow::Result<Option<Owner>>;
+    /// Inserts the owner together with its first API key, so neither is stored without the other.
+    async fn insert(&self, source: NewOwner, api_key: NewApiKey) -> anyhow::Result<Owner>;
+}
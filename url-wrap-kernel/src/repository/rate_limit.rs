@@ -0,0 +1,12 @@
+use crate::model::rate_limit::RateLimit;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+#[async_trait]
+pub trait RateLimitRepository {
+    /// Counts one request against `key` in the window ending at `reset_at`.
+    async fn hit(&self, key: &str, reset_at: DateTime<Utc>) -> anyhow::Result<RateLimit>;
+
+    /// Deletes the windows that reset at or before `now`. Returns how many were deleted.
+    async fn delete_expired(&self, now: DateTime<Utc>) -> anyhow::Result<u64>;
+}
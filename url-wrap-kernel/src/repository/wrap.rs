@@ -1,10 +1,60 @@
-use crate::model::wrap::{NewWrap, Wrap};
+use crate::model::page::Page;
+use crate::model::wrap::alias::WrapAlias;
+use crate::model::wrap::credential::{NewWrapCredential, VerifiedWrap, WrapCredential};
+use crate::model::wrap::error::WrapError;
+use crate::model::wrap::query::WrapListQuery;
+use crate::model::wrap::{NewWrap, Wrap, WrapUpdate};
 use crate::model::Id;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 #[async_trait]
 pub trait WrapRepository {
-    async fn get(&self, id: &Id<Wrap>) -> anyhow::Result<Option<Wrap>>;
-    async fn insert(&self, source: NewWrap) -> anyhow::Result<Wrap>;
-    async fn find(&self, id: &Id<Wrap>, password: &str) -> anyhow::Result<Wrap>;
+    async fn get(&self, id: &Id<Wrap>) -> Result<Option<Wrap>, WrapError>;
+    /// Returns the wrap only to the holder of its management token, so that changes can be
+    /// checked against it without revealing anything about the wrap to others.
+    async fn get_managed(&self, id: &Id<Wrap>, management_token: &str) -> Result<Wrap, WrapError>;
+    /// Fails with `WrapError::AliasTaken` when another wrap already uses the alias.
+    async fn insert(&self, source: NewWrap) -> Result<Wrap, WrapError>;
+    async fn resolve_alias(&self, alias: &WrapAlias) -> Result<Option<Id<Wrap>>, WrapError>;
+    /// Checks the primary password, or only the given credential if it is neither expired nor
+    /// used up, so that an attempt costs a single hash. A matching credential uses up one use.
+    async fn find(
+        &self,
+        id: &Id<Wrap>,
+        password: &str,
+        credential_id: Option<&Id<WrapCredential>>,
+    ) -> Result<VerifiedWrap, WrapError>;
+    /// Atomically uses up one view of a wrap that has a view limit.
+    /// Fails with `WrapError::Consumed` once no views remain.
+    async fn consume_view(&self, id: &Id<Wrap>) -> Result<Wrap, WrapError>;
+    async fn update(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+        source: WrapUpdate,
+    ) -> Result<Wrap, WrapError>;
+    async fn delete(&self, id: &Id<Wrap>, management_token: &str) -> Result<(), WrapError>;
+    async fn list_credentials(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+    ) -> Result<Vec<WrapCredential>, WrapError>;
+    /// Fails with `WrapError::InvalidInput` once the wrap holds `MAX_CREDENTIALS_PER_WRAP`.
+    async fn add_credential(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+        source: NewWrapCredential,
+    ) -> Result<WrapCredential, WrapError>;
+    async fn revoke_credential(
+        &self,
+        id: &Id<Wrap>,
+        management_token: &str,
+        credential_id: &Id<WrapCredential>,
+    ) -> Result<(), WrapError>;
+    async fn list(&self, query: &WrapListQuery) -> Result<Page<Wrap>, WrapError>;
+    /// Removes every wrap that expired at or before `expired_before`, with its credentials and
+    /// access events, and returns how many wraps were removed.
+    async fn delete_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, WrapError>;
 }